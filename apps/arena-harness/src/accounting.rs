@@ -0,0 +1,335 @@
+//! Core-spec accounting for `backtest`: uniform-price execution with
+//! slippage and transient impact, taker fees, position/PnL bookkeeping,
+//! margin and liquidation, funding, and window/round metrics. Mirrors
+//! `packages/simulator` step for step, including its `f64` arithmetic, so
+//! both produce the same logs for the same actions.
+
+use crate::config::{ArenaConfig, ScoringWeights};
+use serde::Serialize;
+
+const BPS: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Account {
+    pub cash_balance: f64,
+    pub position_qty: i64,
+    /// 0 when flat.
+    pub avg_entry_price: f64,
+}
+
+impl Account {
+    pub fn new(cash_balance: f64) -> Self {
+        Self {
+            cash_balance,
+            position_qty: 0,
+            avg_entry_price: 0.0,
+        }
+    }
+
+    pub fn equity(&self, mark_price: f64) -> f64 {
+        self.cash_balance + self.position_qty as f64 * mark_price
+    }
+
+    fn notional(&self, mark_price: f64) -> f64 {
+        self.position_qty.unsigned_abs() as f64 * mark_price
+    }
+
+    /// Applies a fill of `delta_qty` at `exec_price`, realizing PnL on any
+    /// closed portion and charging `fee` to cash.
+    pub fn apply_trade(&self, delta_qty: i64, exec_price: f64, fee: f64) -> Account {
+        if delta_qty == 0 {
+            return *self;
+        }
+        let position = self.position_qty;
+        let same_direction = position == 0 || (position > 0) == (delta_qty > 0);
+        let (abs_position, abs_delta) = (position.unsigned_abs(), delta_qty.unsigned_abs());
+        let mut realized_pnl = 0.0;
+        let mut avg_entry_price = self.avg_entry_price;
+        if same_direction {
+            avg_entry_price = (abs_position as f64 * self.avg_entry_price
+                + abs_delta as f64 * exec_price)
+                / (abs_position + abs_delta) as f64;
+        } else {
+            let closed_qty = abs_position.min(abs_delta) as f64;
+            realized_pnl =
+                closed_qty * (exec_price - self.avg_entry_price) * position.signum() as f64;
+            if abs_delta > abs_position {
+                avg_entry_price = exec_price;
+            } else if abs_delta == abs_position {
+                avg_entry_price = 0.0;
+            }
+        }
+        Account {
+            cash_balance: self.cash_balance + realized_pnl - fee,
+            position_qty: position + delta_qty,
+            avg_entry_price,
+        }
+    }
+
+    pub fn apply_funding(&self, mark_price: f64, funding_rate_bps_per_bar: u32) -> Account {
+        if funding_rate_bps_per_bar == 0 || self.position_qty == 0 {
+            return *self;
+        }
+        let payment =
+            self.position_qty as f64 * mark_price * (funding_rate_bps_per_bar as f64 / BPS);
+        Account {
+            cash_balance: self.cash_balance - payment,
+            ..*self
+        }
+    }
+
+    /// Whether equity at `mark_price` is below the maintenance margin.
+    pub fn below_maintenance(&self, mark_price: f64, maintenance_margin_bps: u32) -> bool {
+        let maintenance = self.notional(mark_price) * (maintenance_margin_bps as f64 / BPS);
+        self.position_qty != 0 && self.equity(mark_price) < maintenance
+    }
+
+    /// Initial margin and max leverage checks for a post-trade account.
+    pub fn within_limits(&self, price: f64, config: &ArenaConfig) -> bool {
+        let notional = self.notional(price);
+        if notional == 0.0 {
+            return true;
+        }
+        let equity = self.equity(price);
+        let initial_ok = equity >= notional * (config.initial_margin_bps as f64 / BPS);
+        let leverage_ok =
+            equity > 0.0 && notional <= equity * (config.max_leverage_bps as f64 / BPS);
+        initial_ok && leverage_ok
+    }
+
+    /// Closes the whole position at `exec_price`, charging the liquidation
+    /// fee. Returns the new account and the fee.
+    pub fn liquidate(&self, exec_price: f64, liquidation_fee_bps: u32) -> (Account, f64) {
+        let qty = self.position_qty.unsigned_abs() as f64;
+        let side = if self.position_qty > 0 { 1.0 } else { -1.0 };
+        let realized_pnl = qty * (exec_price - self.avg_entry_price) * side;
+        let fee = qty * exec_price * (liquidation_fee_bps as f64 / BPS);
+        (Account::new(self.cash_balance + realized_pnl - fee), fee)
+    }
+}
+
+/// Execution price shared by every fill at one open, from the net flow
+/// across agents (microstructure spec §4). Returns `(exec_price, impact_bps)`.
+pub fn uniform_exec_price(
+    open: f64,
+    net_qty: i64,
+    volume: f64,
+    config: &ArenaConfig,
+) -> (f64, f64) {
+    if net_qty == 0 {
+        return (open, 0.0);
+    }
+    let liquidity = (config.min_liquidity as f64).max(volume * config.liquidity_multiplier);
+    let mut impact_bps = if liquidity <= 0.0 {
+        0.0
+    } else {
+        config.impact_k_bps as f64 * (net_qty.unsigned_abs() as f64 / liquidity)
+    };
+    if let Some(cap) = config.impact_cap_bps {
+        impact_bps = impact_bps.min(cap as f64);
+    }
+    let side = net_qty.signum() as f64;
+    let exec_price = open * (1.0 + side * (config.slippage_bps as f64 + impact_bps) / BPS);
+    (exec_price, impact_bps)
+}
+
+pub fn taker_fee(delta_qty: i64, exec_price: f64, taker_fee_bps: u32) -> f64 {
+    delta_qty.unsigned_abs() as f64 * exec_price * (taker_fee_bps as f64 / BPS)
+}
+
+/// Per-step mark-to-market sample.
+#[derive(Debug, Clone, Copy)]
+pub struct EquityPoint {
+    pub equity: f64,
+    pub notional_exposure: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowMetrics {
+    pub window_id: String,
+    pub pnl: f64,
+    pub drawdown: f64,
+    pub exposure: f64,
+    pub total_fees: f64,
+    pub liquidation_count: u32,
+    pub equity_start: f64,
+    pub equity_end: f64,
+    pub peak_equity: f64,
+    pub trough_equity: f64,
+}
+
+impl WindowMetrics {
+    pub fn compute(
+        window_id: &str,
+        curve: &[EquityPoint],
+        total_fees: f64,
+        liquidation_count: u32,
+    ) -> Self {
+        let (Some(first), Some(last)) = (curve.first(), curve.last()) else {
+            return Self {
+                window_id: window_id.to_string(),
+                pnl: 0.0,
+                drawdown: 0.0,
+                exposure: 0.0,
+                total_fees: 0.0,
+                liquidation_count: 0,
+                equity_start: 0.0,
+                equity_end: 0.0,
+                peak_equity: 0.0,
+                trough_equity: 0.0,
+            };
+        };
+        let mut peak = first.equity;
+        let mut drawdown = 0.0_f64;
+        let mut exposure_sum = 0.0;
+        for point in curve {
+            peak = peak.max(point.equity);
+            drawdown = drawdown.max(peak - point.equity);
+            exposure_sum += point.notional_exposure;
+        }
+        Self {
+            window_id: window_id.to_string(),
+            pnl: last.equity - first.equity,
+            drawdown,
+            exposure: exposure_sum / curve.len() as f64,
+            total_fees,
+            liquidation_count,
+            equity_start: first.equity,
+            equity_end: last.equity,
+            peak_equity: peak,
+            trough_equity: peak - drawdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundMetrics {
+    pub pnl_total: f64,
+    pub drawdown_max: f64,
+    pub exposure_avg: f64,
+    pub score: f64,
+    pub weights: ScoringWeights,
+    pub window_metrics: Vec<WindowMetrics>,
+}
+
+impl RoundMetrics {
+    /// Aggregates per-window metrics and scores them (data-ops spec §3.5).
+    pub fn aggregate(window_metrics: Vec<WindowMetrics>, weights: ScoringWeights) -> Self {
+        let pnl_total: f64 = window_metrics.iter().map(|m| m.pnl).sum();
+        let drawdown_max = window_metrics
+            .iter()
+            .map(|m| m.drawdown)
+            .fold(0.0, f64::max);
+        let exposure_avg = if window_metrics.is_empty() {
+            0.0
+        } else {
+            window_metrics.iter().map(|m| m.exposure).sum::<f64>() / window_metrics.len() as f64
+        };
+        Self {
+            pnl_total,
+            drawdown_max,
+            exposure_avg,
+            score: weights.pnl * pnl_total
+                + weights.drawdown * drawdown_max
+                + weights.exposure * exposure_avg,
+            weights,
+            window_metrics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::sample_config;
+
+    #[test]
+    fn trades_average_in_and_realize_on_reduce_and_flip() {
+        let account = Account::new(10_000.0);
+        let long = account.apply_trade(2, 100.0, 1.0);
+        assert_eq!(long.position_qty, 2);
+        assert_eq!(long.avg_entry_price, 100.0);
+        assert_eq!(long.cash_balance, 9_999.0);
+
+        let added = long.apply_trade(2, 110.0, 0.0);
+        assert_eq!(added.avg_entry_price, 105.0);
+
+        let reduced = added.apply_trade(-1, 115.0, 0.0);
+        assert_eq!(reduced.cash_balance, 9_999.0 + 10.0);
+        assert_eq!(reduced.avg_entry_price, 105.0);
+
+        let flipped = reduced.apply_trade(-5, 95.0, 0.0);
+        assert_eq!(flipped.position_qty, -2);
+        assert_eq!(flipped.avg_entry_price, 95.0);
+        assert_eq!(flipped.cash_balance, 10_009.0 - 30.0);
+
+        let flat = flipped.apply_trade(2, 90.0, 0.0);
+        assert_eq!(flat.position_qty, 0);
+        assert_eq!(flat.avg_entry_price, 0.0);
+        assert_eq!(flat.cash_balance, 9_979.0 + 10.0);
+    }
+
+    #[test]
+    fn exec_price_adds_slippage_and_capped_impact_on_net_flow() {
+        let config = sample_config();
+        assert_eq!(uniform_exec_price(100.0, 0, 10.0, &config), (100.0, 0.0));
+
+        // 5 bps slippage plus 5 bps * (2 / 10) impact.
+        let (price, impact) = uniform_exec_price(100.0, 2, 10.0, &config);
+        assert_eq!(impact, 1.0);
+        assert!((price - 100.06).abs() < 1e-9);
+
+        let (price, impact) = uniform_exec_price(100.0, -1_000, 1.0, &config);
+        assert_eq!(impact, 50.0);
+        assert!((price - 99.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn margin_checks_and_liquidation() {
+        let config = sample_config();
+        // Cash is not moved into the position, so equity = cash - notional
+        // for a short; the default 1x leverage needs equity >= notional.
+        let short = Account::new(2_050.0).apply_trade(-1, 1_000.0, 0.0);
+        assert!(short.within_limits(1_000.0, &config));
+        assert!(!Account::new(1_000.0)
+            .apply_trade(-1, 1_000.0, 0.0)
+            .within_limits(1_000.0, &config));
+
+        assert!(!short.below_maintenance(1_000.0, 500));
+        assert!(short.below_maintenance(2_000.0, 500));
+        let (liquidated, fee) = short.liquidate(2_000.0, 50);
+        assert_eq!(liquidated.position_qty, 0);
+        assert_eq!(fee, 10.0);
+        assert_eq!(liquidated.cash_balance, 2_050.0 - 1_000.0 - 10.0);
+
+        let funded = short.apply_funding(1_000.0, 10);
+        assert_eq!(funded.cash_balance, short.cash_balance + 1.0);
+    }
+
+    #[test]
+    fn window_metrics_track_drawdown_and_exposure() {
+        let curve: Vec<EquityPoint> = [(100.0, 0.0), (120.0, 50.0), (90.0, 50.0), (110.0, 0.0)]
+            .iter()
+            .map(|&(equity, notional_exposure)| EquityPoint {
+                equity,
+                notional_exposure,
+            })
+            .collect();
+        let metrics = WindowMetrics::compute("w0", &curve, 2.0, 0);
+        assert_eq!(metrics.pnl, 10.0);
+        assert_eq!(metrics.drawdown, 30.0);
+        assert_eq!(metrics.exposure, 25.0);
+        assert_eq!(metrics.peak_equity, 120.0);
+        assert_eq!(metrics.trough_equity, 90.0);
+
+        let weights = ScoringWeights {
+            pnl: 1.0,
+            drawdown: -0.5,
+            exposure: -0.1,
+        };
+        let round = RoundMetrics::aggregate(vec![metrics.clone(), metrics], weights);
+        assert_eq!(round.pnl_total, 20.0);
+        assert_eq!(round.drawdown_max, 30.0);
+        assert_eq!(round.score, 20.0 - 15.0 - 2.5);
+    }
+}
@@ -0,0 +1,177 @@
+use goblin::elf::header::{EM_BPF, ET_DYN};
+use goblin::elf::Elf;
+use thiserror::Error;
+
+/// Default upper bound on an uploaded program, matching the runtime's 10 MiB
+/// max program data length.
+pub const DEFAULT_MAX_PROGRAM_BYTES: u64 = 10 * 1024 * 1024;
+
+/// `e_machine` used by newer SBF toolchains alongside the legacy `EM_BPF`.
+const EM_SBF: u16 = 263;
+
+const ENTRYPOINT_SYMBOL: &str = "entrypoint";
+
+/// Syscalls whose results change between runs of the same input, breaking
+/// the determinism rules in `solclash-onchain-abi.md` §9. Deterministic
+/// ones (rent, return data, ...) stay allowed.
+const FORBIDDEN_SYSCALLS: &[&str] = &[
+    // Current slot, epoch and wall-clock time.
+    "sol_get_clock_sysvar",
+    // Depends on the cluster's genesis config, not the input.
+    "sol_get_epoch_schedule_sysvar",
+    // Changes as epochs advance.
+    "sol_get_epoch_rewards_sysvar",
+    // Fee rate of the current bank.
+    "sol_get_fees_sysvar",
+    // Slot of the last cluster restart.
+    "sol_get_last_restart_slot",
+    // Stake of the current epoch.
+    "sol_get_epoch_stake",
+    // Reads any sysvar by id, including the forbidden ones above and
+    // SlotHashes, the usual on-chain randomness source.
+    "sol_get_sysvar",
+];
+
+/// Prefixes of forbidden syscall families: CPI (`sol_invoke_signed_c`,
+/// `sol_invoke_signed_rust`) reaches programs outside the sandbox.
+const FORBIDDEN_SYSCALL_PREFIXES: &[&str] = &["sol_invoke_signed_"];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ArtifactViolation {
+    #[error("program is {size} bytes, max is {max}")]
+    TooLarge { size: u64, max: u64 },
+    #[error("not a valid ELF file: {0}")]
+    Malformed(String),
+    #[error("not an SBF program: expected 64-bit little-endian ELF")]
+    WrongClass,
+    #[error("not an SBF program: e_machine {0}")]
+    WrongMachine(u16),
+    #[error("not a shared object: e_type {0}")]
+    WrongType(u16),
+    #[error("missing `entrypoint` symbol")]
+    MissingEntrypoint,
+    #[error("forbidden syscall: {0}")]
+    ForbiddenSyscall(String),
+}
+
+/// Checks an uploaded `.so` before it is handed to `ProgramTest`.
+pub fn validate_artifact(bytes: &[u8], max_bytes: u64) -> Result<(), ArtifactViolation> {
+    let size = bytes.len() as u64;
+    if size > max_bytes {
+        return Err(ArtifactViolation::TooLarge {
+            size,
+            max: max_bytes,
+        });
+    }
+    let elf = Elf::parse(bytes).map_err(|err| ArtifactViolation::Malformed(err.to_string()))?;
+    if !elf.is_64 || !elf.little_endian {
+        return Err(ArtifactViolation::WrongClass);
+    }
+    if elf.header.e_machine != EM_BPF && elf.header.e_machine != EM_SBF {
+        return Err(ArtifactViolation::WrongMachine(elf.header.e_machine));
+    }
+    if elf.header.e_type != ET_DYN {
+        return Err(ArtifactViolation::WrongType(elf.header.e_type));
+    }
+
+    let mut defined = Vec::new();
+    let mut imported = Vec::new();
+    for (syms, strtab) in [(&elf.dynsyms, &elf.dynstrtab), (&elf.syms, &elf.strtab)] {
+        for sym in syms.iter() {
+            let Some(name) = strtab.get_at(sym.st_name) else {
+                continue;
+            };
+            if sym.st_shndx == 0 {
+                imported.push(name);
+            } else {
+                defined.push(name);
+            }
+        }
+    }
+    check_symbols(&defined, &imported)
+}
+
+/// Requires an `entrypoint` definition and rejects forbidden imports.
+fn check_symbols(defined: &[&str], imported: &[&str]) -> Result<(), ArtifactViolation> {
+    if let Some(name) = imported.iter().find(|name| is_forbidden(name)) {
+        return Err(ArtifactViolation::ForbiddenSyscall(name.to_string()));
+    }
+    if !defined.contains(&ENTRYPOINT_SYMBOL) {
+        return Err(ArtifactViolation::MissingEntrypoint);
+    }
+    Ok(())
+}
+
+fn is_forbidden(name: &str) -> bool {
+    FORBIDDEN_SYSCALLS.contains(&name)
+        || FORBIDDEN_SYSCALL_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bare ELF64 header with no sections or segments.
+    fn elf_header(machine: u16, e_type: u16) -> Vec<u8> {
+        let mut bytes = vec![0u8; 64];
+        bytes[..4].copy_from_slice(b"\x7fELF");
+        bytes[4] = 2; // ELFCLASS64
+        bytes[5] = 1; // little-endian
+        bytes[6] = 1; // EV_CURRENT
+        bytes[16..18].copy_from_slice(&e_type.to_le_bytes());
+        bytes[18..20].copy_from_slice(&machine.to_le_bytes());
+        bytes[20..24].copy_from_slice(&1u32.to_le_bytes());
+        bytes[52..54].copy_from_slice(&64u16.to_le_bytes()); // e_ehsize
+        bytes
+    }
+
+    #[test]
+    fn rejects_oversized_and_non_sbf_artifacts() {
+        let sbf = elf_header(EM_BPF, ET_DYN);
+        assert_eq!(
+            validate_artifact(&sbf, 32),
+            Err(ArtifactViolation::TooLarge { size: 64, max: 32 })
+        );
+        assert!(matches!(
+            validate_artifact(b"not an elf", DEFAULT_MAX_PROGRAM_BYTES),
+            Err(ArtifactViolation::Malformed(_))
+        ));
+        assert_eq!(
+            validate_artifact(&elf_header(62, ET_DYN), DEFAULT_MAX_PROGRAM_BYTES),
+            Err(ArtifactViolation::WrongMachine(62))
+        );
+        assert_eq!(
+            validate_artifact(&sbf, DEFAULT_MAX_PROGRAM_BYTES),
+            Err(ArtifactViolation::MissingEntrypoint)
+        );
+    }
+
+    #[test]
+    fn rejects_forbidden_syscalls() {
+        assert_eq!(
+            check_symbols(&["entrypoint"], &["sol_log_", "sol_memcpy_"]),
+            Ok(())
+        );
+        assert_eq!(
+            check_symbols(
+                &["entrypoint"],
+                &["sol_get_rent_sysvar", "sol_get_return_data"]
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_symbols(&["entrypoint"], &["sol_log_", "sol_get_clock_sysvar"]),
+            Err(ArtifactViolation::ForbiddenSyscall(
+                "sol_get_clock_sysvar".to_string()
+            ))
+        );
+        assert_eq!(
+            check_symbols(&["entrypoint"], &["sol_invoke_signed_rust"]),
+            Err(ArtifactViolation::ForbiddenSyscall(
+                "sol_invoke_signed_rust".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! `solclash-harness backtest`: replays whole windows against agent programs
+//! and the config's baselines with core-spec accounting (next-open
+//! execution, uniform-price impact, fees, margin, liquidation, funding), and
+//! writes the data-ops logs. Follows `runWindow` in `packages/simulator`, so
+//! the logs match what a tournament round would write for the same tape.
+
+use crate::abi::{
+    Bar, EvalInputV1, EvalOutputV3, ACTION_BUY, ACTION_BUY_NOTIONAL, ACTION_CLOSE,
+    ACTION_FLAG_REDUCE_ONLY, ACTION_SELL, ACTION_SELL_NOTIONAL, ACTION_TARGET_POSITION,
+    ERR_ORDER_TYPE_UNSUPPORTED, ERR_OUTPUT_INVALID, ORDER_MARKET, OUTPUT_VERSION_ANNOTATED,
+};
+use crate::accounting::{
+    taker_fee, uniform_exec_price, Account, EquityPoint, RoundMetrics, WindowMetrics,
+};
+use crate::artifact::DEFAULT_MAX_PROGRAM_BYTES;
+use crate::cli::BacktestOptions;
+use crate::config::ArenaConfig;
+use crate::protocol::ProgramRejectionJson;
+use crate::replay::{load_tape, select_windows, ReplayBar, Window};
+use crate::status::{self, Failure};
+use crate::{
+    evaluate_output, init_programs, invoke_program, parse_window_id, LookbackBounds, ProgramInfo,
+    DEFAULT_COMPUTE_UNIT_LIMIT,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use solana_program_test::ProgramTestContext;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const DEFAULT_SCALE: u32 = 1_000_000;
+
+#[derive(Serialize)]
+struct PolicyLogEntry<'a> {
+    window_id: &'a str,
+    step_index: usize,
+    agent_id: &'a str,
+    action_type: u8,
+    order_qty: i64,
+    status: &'static str,
+    err_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    annotation: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TradeLogEntry<'a> {
+    window_id: &'a str,
+    step_index: usize,
+    agent_id: &'a str,
+    delta_qty: i64,
+    exec_price: f64,
+    fee_paid: f64,
+    slippage_bps: u32,
+    impact_bps: f64,
+    net_qty: i64,
+}
+
+#[derive(Serialize)]
+struct EquityLogEntry<'a> {
+    window_id: &'a str,
+    step_index: usize,
+    equity: f64,
+    cash_balance: f64,
+    position_qty: i64,
+    mark_price: f64,
+}
+
+#[derive(Serialize)]
+struct LiquidationLogEntry<'a> {
+    window_id: &'a str,
+    step_index: usize,
+    agent_id: &'a str,
+    liquidated_qty: i64,
+    exec_price: f64,
+    liquidation_fee: f64,
+}
+
+#[derive(Serialize)]
+struct WindowSummary<'a> {
+    window_id: &'a str,
+    metrics_by_agent: BTreeMap<&'a str, WindowMetrics>,
+    invalid_window_reason: Option<String>,
+}
+
+/// JSONL log lines of one agent across all windows.
+#[derive(Default)]
+struct Logs {
+    policy: String,
+    trade: String,
+    equity: String,
+    liquidation: String,
+}
+
+fn push_line(log: &mut String, entry: &impl Serialize) -> Result<()> {
+    log.push_str(&serde_json::to_string(entry)?);
+    log.push('\n');
+    Ok(())
+}
+
+/// One agent's state within a window.
+struct Agent<'a> {
+    id: &'a str,
+    program: &'a ProgramInfo,
+    account: Account,
+    curve: Vec<EquityPoint>,
+    total_fees: f64,
+    liquidation_count: u32,
+}
+
+/// What an agent does at one step, decided before any fills.
+struct StepAction {
+    delta_qty: i64,
+    is_liquidation: bool,
+    output: EvalOutputV3,
+    status: &'static str,
+}
+
+struct Backtest<'a> {
+    config: &'a ArenaConfig,
+    context: ProgramTestContext,
+    compute_unit_limit: u32,
+    price_scale: u32,
+    volume_scale: u32,
+}
+
+/// Report printed by `solclash-harness backtest`; logs and `summary.json`
+/// are written to `out_dir`.
+#[derive(Debug, Serialize)]
+pub struct BacktestReport {
+    pub config: String,
+    pub data: String,
+    pub out_dir: String,
+    /// False when any agent or baseline failed to load.
+    pub passed: bool,
+    pub rejected: Vec<ProgramRejectionJson>,
+    pub windows: Vec<String>,
+    pub agents: Vec<BacktestAgentJson>,
+}
+
+/// Round metrics of one agent, as in `round_results.json`.
+#[derive(Debug, Serialize)]
+pub struct BacktestAgentJson {
+    pub id: String,
+    pub score: f64,
+    pub pnl_total: f64,
+    pub drawdown_max: f64,
+    pub exposure_avg: f64,
+}
+
+pub async fn run(options: &BacktestOptions) -> Result<BacktestReport> {
+    let config = ArenaConfig::load(&options.config_path)?;
+    let tape = load_tape(&options.data_path)?;
+    let windows = select_windows(&config, &tape.bars)?;
+    let instrument = tape.instrument.unwrap_or_default();
+    let compute_unit_limit = options
+        .compute_unit_limit
+        .or(config.compute_unit_limit)
+        .unwrap_or(DEFAULT_COMPUTE_UNIT_LIMIT);
+
+    let ids: Vec<String> = options
+        .agents
+        .iter()
+        .map(|spec| spec.id.clone())
+        .chain(config.baseline_bots_enabled.iter().cloned())
+        .collect();
+    let (context, programs, rejected) = init_programs(
+        options.agents.clone(),
+        &config.baseline_bots_enabled,
+        compute_unit_limit,
+        LookbackBounds {
+            min: 1,
+            max: u16::MAX,
+        },
+        DEFAULT_MAX_PROGRAM_BYTES,
+    )
+    .await?;
+    let ids: Vec<&str> = ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| programs.contains_key(*id))
+        .collect();
+
+    let mut backtest = Backtest {
+        config: &config,
+        context,
+        compute_unit_limit,
+        price_scale: config
+            .price_scale
+            .or(instrument.price_scale)
+            .unwrap_or(DEFAULT_SCALE),
+        volume_scale: config
+            .volume_scale
+            .or(instrument.volume_scale)
+            .unwrap_or(DEFAULT_SCALE),
+    };
+    let mut logs: HashMap<&str, Logs> = ids.iter().map(|&id| (id, Logs::default())).collect();
+    let mut window_metrics: HashMap<&str, Vec<WindowMetrics>> =
+        ids.iter().map(|&id| (id, Vec::new())).collect();
+    let mut summaries = Vec::new();
+    for window in &windows {
+        let bars = &tape.bars[window.start..=window.end];
+        let mut agents: Vec<Agent> = ids
+            .iter()
+            .map(|&id| Agent {
+                id,
+                program: &programs[id],
+                account: Account::new(config.initial_cash()),
+                curve: Vec::new(),
+                total_fees: 0.0,
+                liquidation_count: 0,
+            })
+            .collect();
+        backtest
+            .run_window(window, bars, &mut agents, &mut logs)
+            .await?;
+        let mut metrics_by_agent = BTreeMap::new();
+        for agent in &agents {
+            let metrics = WindowMetrics::compute(
+                &window.id,
+                &agent.curve,
+                agent.total_fees,
+                agent.liquidation_count,
+            );
+            window_metrics
+                .get_mut(agent.id)
+                .expect("agent metrics")
+                .push(metrics.clone());
+            metrics_by_agent.insert(agent.id, metrics);
+        }
+        summaries.push(WindowSummary {
+            window_id: &window.id,
+            metrics_by_agent,
+            invalid_window_reason: None,
+        });
+    }
+
+    let round_metrics: BTreeMap<&str, RoundMetrics> = ids
+        .iter()
+        .map(|&id| {
+            let metrics = window_metrics.remove(id).unwrap_or_default();
+            (id, RoundMetrics::aggregate(metrics, config.weights()))
+        })
+        .collect();
+    write_outputs(&options.out_dir, &logs, &summaries, &round_metrics)?;
+
+    Ok(BacktestReport {
+        config: options.config_path.display().to_string(),
+        data: options.data_path.display().to_string(),
+        out_dir: options.out_dir.display().to_string(),
+        passed: rejected.is_empty(),
+        rejected,
+        windows: windows.iter().map(|w| w.id.clone()).collect(),
+        agents: ids
+            .iter()
+            .map(|&id| {
+                let metrics = &round_metrics[id];
+                BacktestAgentJson {
+                    id: id.to_string(),
+                    score: metrics.score,
+                    pnl_total: metrics.pnl_total,
+                    drawdown_max: metrics.drawdown_max,
+                    exposure_avg: metrics.exposure_avg,
+                }
+            })
+            .collect(),
+    })
+}
+
+impl Backtest<'_> {
+    /// Each step first collects every agent's action (funding, eval,
+    /// mark-to-market, maintenance check), then fills them all at the next
+    /// bar's open at one uniform price. The last bar only marks to market.
+    async fn run_window(
+        &mut self,
+        window: &Window,
+        bars: &[ReplayBar],
+        agents: &mut [Agent<'_>],
+        logs: &mut HashMap<&str, Logs>,
+    ) -> Result<()> {
+        let config = self.config;
+        let window_id = parse_window_id(&window.id)?;
+        for (t, bar) in bars.iter().enumerate() {
+            let mut actions = Vec::with_capacity(agents.len());
+            for agent in agents.iter_mut() {
+                agent.account = agent
+                    .account
+                    .apply_funding(bar.close, config.funding_rate_bps_per_bar);
+                let lookback = agent
+                    .program
+                    .lookback_len
+                    .map_or(config.lookback_len, usize::from);
+                let ohlcv = &bars[(t + 1).saturating_sub(lookback)..=t];
+                let input = EvalInputV1 {
+                    version: 1,
+                    window_id,
+                    step_index: t as u32,
+                    bar_interval_seconds: config.bar_interval_seconds,
+                    price_scale: self.price_scale,
+                    volume_scale: self.volume_scale,
+                    cash_balance: to_i64(agent.account.cash_balance),
+                    position_qty: agent.account.position_qty,
+                    avg_entry_price: to_i64(agent.account.avg_entry_price),
+                    max_leverage_bps: config.max_leverage_bps,
+                    initial_margin_bps: config.initial_margin_bps,
+                    maintenance_margin_bps: config.maintenance_margin_bps,
+                    lookback_len: ohlcv.len() as u16,
+                    ohlcv: ohlcv.iter().map(to_abi_bar).collect(),
+                    depth: None,
+                    features: None,
+                };
+                let (output, status) = self.decide(agent.program, &input).await?;
+
+                let conversion_price = bars.get(t + 1).unwrap_or(bar).open;
+                let position = agent.account.position_qty;
+                let mut delta_qty =
+                    delta_qty(&output, position, conversion_price, self.volume_scale);
+                if output.action_type & ACTION_FLAG_REDUCE_ONLY != 0 {
+                    delta_qty = clip_reduce_only(delta_qty, position);
+                }
+
+                // Equity is recorded before the fill, for the position held
+                // through this bar.
+                let equity = agent.account.equity(bar.close);
+                agent.curve.push(EquityPoint {
+                    equity,
+                    notional_exposure: position.unsigned_abs() as f64 * bar.close,
+                });
+                push_line(
+                    &mut logs.get_mut(agent.id).expect("agent logs").equity,
+                    &EquityLogEntry {
+                        window_id: &window.id,
+                        step_index: t,
+                        equity,
+                        cash_balance: agent.account.cash_balance,
+                        position_qty: position,
+                        mark_price: bar.close,
+                    },
+                )?;
+
+                let is_liquidation = agent
+                    .account
+                    .below_maintenance(bar.close, config.maintenance_margin_bps);
+                actions.push(StepAction {
+                    delta_qty: if is_liquidation { -position } else { delta_qty },
+                    is_liquidation,
+                    output,
+                    status,
+                });
+            }
+
+            if let Some(next) = bars.get(t + 1) {
+                let net_qty = actions
+                    .iter()
+                    .fold(0i64, |sum, a| sum.saturating_add(a.delta_qty));
+                let (exec_price, impact_bps) =
+                    uniform_exec_price(next.open, net_qty, next.volume, config);
+                for (agent, action) in agents.iter_mut().zip(actions.iter_mut()) {
+                    if action.delta_qty == 0 {
+                        continue;
+                    }
+                    let log = logs.get_mut(agent.id).expect("agent logs");
+                    if action.is_liquidation {
+                        let liquidated_qty = agent.account.position_qty;
+                        let (account, fee) = agent
+                            .account
+                            .liquidate(exec_price, config.liquidation_fee_bps);
+                        agent.account = account;
+                        agent.total_fees += fee;
+                        agent.liquidation_count += 1;
+                        push_line(
+                            &mut log.liquidation,
+                            &LiquidationLogEntry {
+                                window_id: &window.id,
+                                step_index: t,
+                                agent_id: agent.id,
+                                liquidated_qty,
+                                exec_price,
+                                liquidation_fee: fee,
+                            },
+                        )?;
+                        continue;
+                    }
+                    let fee = taker_fee(action.delta_qty, exec_price, config.taker_fee_bps);
+                    let account = agent.account.apply_trade(action.delta_qty, exec_price, fee);
+                    // Margin rules only gate trades that add exposure, so an
+                    // agent can always de-risk.
+                    let increases_exposure = account.position_qty.unsigned_abs()
+                        > agent.account.position_qty.unsigned_abs();
+                    if increases_exposure && !account.within_limits(exec_price, config) {
+                        action.delta_qty = 0;
+                        action.status = status::ERR;
+                        action.output = EvalOutputV3::hold(ERR_OUTPUT_INVALID);
+                        continue;
+                    }
+                    agent.account = account;
+                    agent.total_fees += fee;
+                    push_line(
+                        &mut log.trade,
+                        &TradeLogEntry {
+                            window_id: &window.id,
+                            step_index: t,
+                            agent_id: agent.id,
+                            delta_qty: action.delta_qty,
+                            exec_price,
+                            fee_paid: fee,
+                            slippage_bps: config.slippage_bps,
+                            impact_bps,
+                            net_qty,
+                        },
+                    )?;
+                }
+            }
+
+            for (agent, action) in agents.iter().zip(&actions) {
+                let output = &action.output;
+                push_line(
+                    &mut logs.get_mut(agent.id).expect("agent logs").policy,
+                    &PolicyLogEntry {
+                        window_id: &window.id,
+                        step_index: t,
+                        agent_id: agent.id,
+                        action_type: output.action_type,
+                        order_qty: output.order_qty,
+                        status: action.status,
+                        err_code: output.err_code,
+                        annotation: (output.version >= OUTPUT_VERSION_ANNOTATED)
+                            .then(|| hex::encode(output.annotation)),
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates one agent the way the TS runtime sees a serve-mode eval:
+    /// ABI rejections are HOLDs with their err code, failed evals the HOLD
+    /// `Failure` stands in for them, and resting orders are held with
+    /// `ERR_ORDER_TYPE_UNSUPPORTED`.
+    async fn decide(
+        &mut self,
+        program: &ProgramInfo,
+        input: &EvalInputV1,
+    ) -> Result<(EvalOutputV3, &'static str)> {
+        let input_bytes = input.to_bytes()?;
+        let result = match invoke_program(
+            &mut self.context,
+            program.id,
+            self.compute_unit_limit,
+            &input_bytes,
+            None,
+            &[],
+        )
+        .await
+        {
+            Ok(data) => evaluate_output(&data, program.output_version, input.position_qty),
+            Err(err) => Err(err),
+        };
+        let mut output = match result {
+            Ok(evaluated) => evaluated.output,
+            Err(err) => {
+                let failure = Failure::of(&err);
+                return Ok((failure.hold(), failure.status));
+            }
+        };
+        if output.order_type != ORDER_MARKET {
+            let hold = EvalOutputV3::hold(ERR_ORDER_TYPE_UNSUPPORTED);
+            output = EvalOutputV3 {
+                annotation: output.annotation,
+                version: output.version,
+                ..hold
+            };
+        }
+        Ok((output, status::OK))
+    }
+}
+
+/// Core spec §5: converts an action into a signed position change. Notional
+/// orders are sized at the open they execute against.
+fn delta_qty(
+    output: &EvalOutputV3,
+    position_qty: i64,
+    conversion_price: f64,
+    volume_scale: u32,
+) -> i64 {
+    let qty = output.order_qty;
+    match output.action_type & !ACTION_FLAG_REDUCE_ONLY {
+        ACTION_BUY => qty,
+        ACTION_SELL => qty.saturating_neg(),
+        ACTION_CLOSE => position_qty.saturating_neg(),
+        ACTION_TARGET_POSITION => qty.saturating_sub(position_qty),
+        ACTION_BUY_NOTIONAL => notional_qty(qty, conversion_price, volume_scale),
+        ACTION_SELL_NOTIONAL => -notional_qty(qty, conversion_price, volume_scale),
+        _ => 0,
+    }
+}
+
+/// Notional orders are quote amounts at `price_scale`, like prices, and are
+/// sized in base units at `volume_scale`: `(notional / price_scale) /
+/// (price / price_scale) * volume_scale`, where `price_scale` cancels.
+/// Computed in `f64` as the simulator does, so both truncate alike.
+fn notional_qty(notional: i64, price: f64, volume_scale: u32) -> i64 {
+    (notional as f64 * f64::from(volume_scale) / price).trunc() as i64
+}
+
+/// Reduce-only deltas may shrink the position but never grow or flip it.
+pub(crate) fn clip_reduce_only(delta_qty: i64, position_qty: i64) -> i64 {
+    if position_qty == 0 || delta_qty.signum() == position_qty.signum() {
+        return 0;
+    }
+    delta_qty.signum() * delta_qty.unsigned_abs().min(position_qty.unsigned_abs()) as i64
+}
+
+/// Account values are `f64` in the simulator and truncated into the input.
+fn to_i64(value: f64) -> i64 {
+    if value.is_finite() {
+        value.trunc() as i64
+    } else {
+        0
+    }
+}
+
+fn to_abi_bar(bar: &ReplayBar) -> Bar {
+    Bar {
+        open: to_i64(bar.open),
+        high: to_i64(bar.high),
+        low: to_i64(bar.low),
+        close: to_i64(bar.close),
+        volume: to_i64(bar.volume),
+    }
+}
+
+/// Writes `<agent>/{policy,trade,equity,liquidation}_log.jsonl`,
+/// `summary.json` and `round_results.json` under `out_dir`, replacing
+/// earlier runs.
+fn write_outputs(
+    out_dir: &Path,
+    logs: &HashMap<&str, Logs>,
+    summaries: &[WindowSummary],
+    round_metrics: &BTreeMap<&str, RoundMetrics>,
+) -> Result<()> {
+    let write = |path: &Path, contents: &str| {
+        std::fs::write(path, contents).with_context(|| format!("cannot write {}", path.display()))
+    };
+    for (id, log) in logs {
+        let dir = out_dir.join(id);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create {}", dir.display()))?;
+        write(&dir.join("policy_log.jsonl"), &log.policy)?;
+        write(&dir.join("trade_log.jsonl"), &log.trade)?;
+        write(&dir.join("equity_log.jsonl"), &log.equity)?;
+        write(&dir.join("liquidation_log.jsonl"), &log.liquidation)?;
+    }
+    std::fs::create_dir_all(out_dir)?;
+    write(
+        &out_dir.join("summary.json"),
+        &serde_json::to_string_pretty(summaries)?,
+    )?;
+    write(
+        &out_dir.join("round_results.json"),
+        &serde_json::to_string_pretty(round_metrics)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{ActionType, EvalOutputV1, EvalOutputV2};
+
+    fn output(action: ActionType, qty: i64) -> EvalOutputV3 {
+        EvalOutputV2::from(EvalOutputV1::new(action, qty)).into()
+    }
+
+    #[test]
+    fn actions_convert_to_deltas() {
+        assert_eq!(delta_qty(&output(ActionType::Buy, 3), 1, 100.0, 1), 3);
+        assert_eq!(delta_qty(&output(ActionType::Sell, 3), 1, 100.0, 1), -3);
+        assert_eq!(delta_qty(&output(ActionType::Close, 0), -4, 100.0, 1), 4);
+        assert_eq!(
+            delta_qty(&output(ActionType::TargetPosition, -2), 3, 100.0, 1),
+            -5
+        );
+        assert_eq!(
+            delta_qty(&output(ActionType::BuyNotional, 250), 0, 100.0, 1),
+            2
+        );
+        assert_eq!(
+            delta_qty(&output(ActionType::SellNotional, 250), 0, 100.0, 1),
+            -2
+        );
+        assert_eq!(delta_qty(&EvalOutputV3::hold(0), 5, 100.0, 1), 0);
+
+        // At price_scale 100 and volume_scale 1000, 100.00 of quote at
+        // 50.00 buys 2 BTC, and 25.00 sells 0.5 BTC.
+        assert_eq!(
+            delta_qty(&output(ActionType::BuyNotional, 10_000), 0, 5_000.0, 1000),
+            2000
+        );
+        assert_eq!(
+            delta_qty(&output(ActionType::SellNotional, 2_500), 0, 5_000.0, 1000),
+            -500
+        );
+
+        assert_eq!(clip_reduce_only(-5, 3), -3);
+        assert_eq!(clip_reduce_only(2, 3), 0);
+        assert_eq!(clip_reduce_only(-1, 0), 0);
+        assert_eq!(to_i64(f64::NAN), 0);
+        assert_eq!(to_i64(-1.9), -1);
+    }
+}
@@ -0,0 +1,264 @@
+//! Reference policies that run inside the harness as native programs,
+//! selected by id in the init request's `baselines`. They answer `eval`
+//! through the same path as agent programs (input encoding, ABI output
+//! validation, responses), so arenas and tests can use them as opponents.
+//! Like the builtins, they negotiate ABI v1. Native code cannot be metered
+//! per instruction, so each invocation is charged a flat
+//! `BASELINE_COMPUTE_UNITS` up front: baselines report compute units and run
+//! out of budget under a low limit like agent programs.
+
+use crate::abi::{
+    ActionType, Bar, EvalInputV1, EvalOutputV1, ERR_INPUT_DESER_FAIL, ERR_INVALID_INPUT_VERSION,
+    ERR_INVALID_INSTRUCTION_DATA, ERR_INVALID_LOOKBACK_LEN, ERR_OK, INPUT_VERSION_MAX,
+};
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program_runtime::declare_process_instruction;
+use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
+use solana_program_test::invoke_builtin_function;
+use solana_sdk::instruction::InstructionError;
+
+pub const IDS: &[&str] = &["BUY_AND_HOLD", "FLAT", "SMA_CROSSOVER", "MEAN_REVERSION"];
+
+/// Compute units charged per baseline invocation.
+pub const BASELINE_COMPUTE_UNITS: u64 = 5_000;
+
+const SMA_FAST: usize = 5;
+const SMA_SLOW: usize = 20;
+const REVERSION_PERIOD: usize = 20;
+/// Entry band width in mean absolute deviations from the SMA.
+const REVERSION_BAND: i128 = 2;
+
+/// Declares a native entrypoint that charges `BASELINE_COMPUTE_UNITS`
+/// before running `$policy` behind the starter's entrypoint checks.
+macro_rules! metered {
+    ($entrypoint:ident, $policy:expr) => {{
+        declare_process_instruction!($entrypoint, BASELINE_COMPUTE_UNITS, |invoke_context| {
+            invoke_builtin_function(
+                |_, accounts, data| run(accounts, data, $policy),
+                invoke_context,
+            )
+            .map(|_| ())
+            .map_err(|err| match err.downcast::<InstructionError>() {
+                Ok(err) => *err,
+                Err(_) => InstructionError::ProgramFailedToComplete,
+            })
+        });
+        Some($entrypoint::vm)
+    }};
+}
+
+pub fn lookup(id: &str) -> Option<BuiltinFunctionWithContext> {
+    match id {
+        "BUY_AND_HOLD" => metered!(BuyAndHold, buy_and_hold),
+        "FLAT" => metered!(Flat, flat),
+        "SMA_CROSSOVER" => metered!(SmaCrossover, sma_crossover),
+        "MEAN_REVERSION" => metered!(MeanReversion, mean_reversion),
+        _ => None,
+    }
+}
+
+pub fn output_account<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+pub fn write_output(accounts: &[AccountInfo], output: EvalOutputV1) -> ProgramResult {
+    let bytes = borsh::to_vec(&output)?;
+    output_account(accounts)?.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// The starter contract's entrypoint checks around a policy: unusable input
+/// or instruction data is answered with a HOLD and its err code.
+fn run(
+    accounts: &[AccountInfo],
+    data: &[u8],
+    policy: fn(&EvalInputV1) -> EvalOutputV1,
+) -> ProgramResult {
+    let input = accounts
+        .first()
+        .map(|account| EvalInputV1::from_bytes(&account.data.borrow()));
+    let output = match input {
+        _ if !data.is_empty() => EvalOutputV1::hold(ERR_INVALID_INSTRUCTION_DATA),
+        Some(Ok(input)) if input.version == 0 || input.version > INPUT_VERSION_MAX => {
+            EvalOutputV1::hold(ERR_INVALID_INPUT_VERSION)
+        }
+        Some(Ok(input)) if input.lookback_len as usize != input.ohlcv.len() => {
+            EvalOutputV1::hold(ERR_INVALID_LOOKBACK_LEN)
+        }
+        Some(Ok(input)) => policy(&input),
+        _ => EvalOutputV1::hold(ERR_INPUT_DESER_FAIL),
+    };
+    write_output(accounts, output)
+}
+
+/// Buys one unit when flat, then holds.
+fn buy_and_hold(input: &EvalInputV1) -> EvalOutputV1 {
+    if input.position_qty == 0 {
+        EvalOutputV1::new(ActionType::Buy, 1)
+    } else {
+        EvalOutputV1::hold(ERR_OK)
+    }
+}
+
+/// Never trades.
+fn flat(_input: &EvalInputV1) -> EvalOutputV1 {
+    EvalOutputV1::hold(ERR_OK)
+}
+
+/// Long one unit while the fast SMA of closes is above the slow one, short
+/// while below; holds until there are enough bars.
+fn sma_crossover(input: &EvalInputV1) -> EvalOutputV1 {
+    match (sma(&input.ohlcv, SMA_FAST), sma(&input.ohlcv, SMA_SLOW)) {
+        (Some(fast), Some(slow)) if fast > slow => EvalOutputV1::target_position(1),
+        (Some(fast), Some(slow)) if fast < slow => EvalOutputV1::target_position(-1),
+        _ => EvalOutputV1::hold(ERR_OK),
+    }
+}
+
+/// Fades closes outside a band around the SMA and exits once price is back
+/// at the mean.
+fn mean_reversion(input: &EvalInputV1) -> EvalOutputV1 {
+    let (Some(mean), Some(last)) = (sma(&input.ohlcv, REVERSION_PERIOD), input.ohlcv.last()) else {
+        return EvalOutputV1::hold(ERR_OK);
+    };
+    let window = &input.ohlcv[input.ohlcv.len() - REVERSION_PERIOD..];
+    let deviation = window
+        .iter()
+        .map(|bar| (bar.close as i128 - mean).abs())
+        .sum::<i128>()
+        / REVERSION_PERIOD as i128;
+    let close = last.close as i128;
+    let position = input.position_qty;
+    if close < mean - REVERSION_BAND * deviation {
+        EvalOutputV1::target_position(1)
+    } else if close > mean + REVERSION_BAND * deviation {
+        EvalOutputV1::target_position(-1)
+    } else if (position > 0 && close >= mean) || (position < 0 && close <= mean) {
+        EvalOutputV1::target_position(0)
+    } else {
+        EvalOutputV1::hold(ERR_OK)
+    }
+}
+
+/// Mean close of the last `period` bars.
+fn sma(bars: &[Bar], period: usize) -> Option<i128> {
+    if period == 0 || bars.len() < period {
+        return None;
+    }
+    let sum: i128 = bars[bars.len() - period..]
+        .iter()
+        .map(|bar| bar.close as i128)
+        .sum();
+    Some(sum / period as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fixtures::sample_input;
+    use crate::abi::{ACTION_BUY, ACTION_HOLD, ACTION_TARGET_POSITION};
+
+    fn input_with_closes(closes: &[i64], position_qty: i64) -> EvalInputV1 {
+        let mut input = sample_input(1);
+        input.position_qty = position_qty;
+        input.ohlcv = closes
+            .iter()
+            .map(|&close| Bar {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 0,
+            })
+            .collect();
+        input.lookback_len = input.ohlcv.len() as u16;
+        input
+    }
+
+    #[test]
+    fn buy_and_hold_buys_once() {
+        assert_eq!(
+            buy_and_hold(&input_with_closes(&[], 0)).action_type,
+            ACTION_BUY
+        );
+        assert_eq!(
+            buy_and_hold(&input_with_closes(&[], 1)).action_type,
+            ACTION_HOLD
+        );
+    }
+
+    #[test]
+    fn sma_crossover_follows_the_trend() {
+        let rising: Vec<i64> = (1..=20).collect();
+        let output = sma_crossover(&input_with_closes(&rising, 0));
+        assert_eq!(
+            (output.action_type, output.order_qty),
+            (ACTION_TARGET_POSITION, 1)
+        );
+
+        let falling: Vec<i64> = (1..=20).rev().collect();
+        assert_eq!(sma_crossover(&input_with_closes(&falling, 0)).order_qty, -1);
+        assert_eq!(
+            sma_crossover(&input_with_closes(&rising[..19], 0)).action_type,
+            ACTION_HOLD
+        );
+    }
+
+    #[test]
+    fn mean_reversion_fades_outliers_and_exits_at_the_mean() {
+        let mut closes = [100, 102, 98, 101, 99].repeat(4);
+        closes[19] = 80;
+        let output = mean_reversion(&input_with_closes(&closes, 0));
+        assert_eq!(
+            (output.action_type, output.order_qty),
+            (ACTION_TARGET_POSITION, 1)
+        );
+
+        closes[19] = 120;
+        assert_eq!(mean_reversion(&input_with_closes(&closes, 0)).order_qty, -1);
+
+        closes[19] = 101;
+        let output = mean_reversion(&input_with_closes(&closes, 1));
+        assert_eq!(
+            (output.action_type, output.order_qty),
+            (ACTION_TARGET_POSITION, 0)
+        );
+        assert_eq!(
+            mean_reversion(&input_with_closes(&closes, 0)).action_type,
+            ACTION_HOLD
+        );
+    }
+
+    #[tokio::test]
+    async fn baselines_are_charged_compute_units() {
+        let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+        let mut program_test = solana_program_test::ProgramTest::default();
+        program_test.add_builtin_program("FLAT", program_id, lookup("FLAT").expect("baseline"));
+        let mut context = program_test.start_with_context().await;
+        let input = borsh::to_vec(&sample_input(1)).expect("serialize");
+
+        let invocation =
+            crate::invoke_program_metered(&mut context, program_id, 200_000, &input, None, &[])
+                .await
+                .expect("evaluates");
+        assert!(invocation.compute_units >= BASELINE_COMPUTE_UNITS);
+
+        let limit = BASELINE_COMPUTE_UNITS as u32 - 1;
+        let Err(err) =
+            crate::invoke_program_metered(&mut context, program_id, limit, &input, None, &[]).await
+        else {
+            panic!("a baseline ran under a limit below its charge");
+        };
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(crate::HarnessError::ComputeExceeded(l)) if *l == limit
+            ),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! `solclash-harness bench <path.so> --data <bars.json>`: measures what a
+//! program costs on real steps of a tape. Every sample is evaluated at the
+//! runtime's maximum limit so the report shows true compute use, which is
+//! then compared with `--compute-unit-limit`. `--calibrate` binary-searches
+//! the smallest limit at which no sample fails.
+
+use crate::abi::EvalInputV1;
+use crate::artifact::DEFAULT_MAX_PROGRAM_BYTES;
+use crate::cli::BenchOptions;
+use crate::error::HarnessError;
+use crate::protocol::BenchStatsJson;
+use crate::replay::{load_tape, Tape};
+use crate::rng::Rng;
+use crate::{init_programs, invoke_program_metered, LookbackBounds, ProgramInfo};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use solana_program_test::ProgramTestContext;
+use std::time::Instant;
+
+/// The most a transaction may request (`MAX_COMPUTE_UNIT_LIMIT`).
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// One sampled step: the bar it ends at and how many bars it sees.
+struct Sample {
+    input: EvalInputV1,
+    input_bytes: Vec<u8>,
+}
+
+struct Measured {
+    sample: usize,
+    compute_units: u64,
+    latency_us: u64,
+}
+
+/// Report printed by `solclash-harness bench`. Compute units are measured
+/// at the runtime maximum and include the compute budget instruction.
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub program: String,
+    pub data: String,
+    pub seed: u64,
+    pub samples: u32,
+    pub compute_unit_limit: u32,
+    /// False when any sample failed or needed more than `compute_unit_limit`.
+    pub passed: bool,
+    /// Load failure; nothing was measured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<String>,
+    /// The program's declared lookback; unset, samples vary the `ohlcv` length.
+    pub lookback_len: Option<u16>,
+    pub compute_units: BenchStatsJson,
+    /// Wall-clock time per eval, including the bank round trip.
+    pub latency_us: BenchStatsJson,
+    /// The first sample at the highest compute cost.
+    pub peak: Option<BenchSampleJson>,
+    /// Samples that needed more than `compute_unit_limit`.
+    pub over_limit: u32,
+    /// Samples that failed even at the maximum limit.
+    pub failures: Vec<BenchSampleJson>,
+    /// Set by `--calibrate`: the smallest limit every sample passes at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calibrated_compute_unit_limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchSampleJson {
+    pub step_index: u32,
+    pub ohlcv_len: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_units: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+pub async fn run(options: &BenchOptions) -> Result<BenchReport> {
+    let tape = load_tape(&options.data_path)?;
+    if tape.bars.is_empty() {
+        return Err(anyhow!("no bars in {}", options.data_path.display()));
+    }
+    let mut report = BenchReport {
+        program: options.program.source(),
+        data: options.data_path.display().to_string(),
+        seed: options.seed,
+        samples: options.samples,
+        compute_unit_limit: options.compute_unit_limit,
+        passed: false,
+        rejected: None,
+        lookback_len: None,
+        compute_units: BenchStatsJson::default(),
+        latency_us: BenchStatsJson::default(),
+        peak: None,
+        over_limit: 0,
+        failures: Vec::new(),
+        calibrated_compute_unit_limit: None,
+    };
+    let (context, mut programs, rejected) = init_programs(
+        vec![options.program.clone()],
+        &[],
+        MAX_COMPUTE_UNIT_LIMIT,
+        LookbackBounds {
+            min: 1,
+            max: options.max_lookback,
+        },
+        DEFAULT_MAX_PROGRAM_BYTES,
+    )
+    .await?;
+    if let Some(rejection) = rejected.into_iter().next() {
+        report.rejected = Some(rejection.reason);
+        return Ok(report);
+    }
+    let program = programs
+        .remove(&options.program.id)
+        .expect("loaded program");
+    report.lookback_len = program.lookback_len;
+
+    let samples = sample_steps(&tape, options, program.lookback_len)?;
+    let mut bench = Bench { context, program };
+    let mut measured = Vec::with_capacity(samples.len());
+    for (index, sample) in samples.iter().enumerate() {
+        let started = Instant::now();
+        let result = bench.eval(sample, MAX_COMPUTE_UNIT_LIMIT).await;
+        let latency_us = started.elapsed().as_micros() as u64;
+        match result {
+            Ok(compute_units) => measured.push(Measured {
+                sample: index,
+                compute_units,
+                latency_us,
+            }),
+            Err(err) => report
+                .failures
+                .push(sample_json(sample, None, err.to_string())),
+        }
+    }
+
+    let compute_units: Vec<u64> = measured.iter().map(|m| m.compute_units).collect();
+    report.compute_units = stats(&compute_units);
+    report.latency_us = stats(&measured.iter().map(|m| m.latency_us).collect::<Vec<_>>());
+    report.over_limit = compute_units
+        .iter()
+        .filter(|&&cu| cu > options.compute_unit_limit as u64)
+        .count() as u32;
+    // The first sample at the highest cost.
+    report.peak = measured
+        .iter()
+        .rev()
+        .max_by_key(|m| m.compute_units)
+        .map(|m| sample_json(&samples[m.sample], Some(m.compute_units), String::new()));
+    report.passed = report.failures.is_empty() && report.over_limit == 0;
+
+    if options.calibrate && !measured.is_empty() {
+        // Costliest first, so a failing limit fails fast.
+        measured.sort_by_key(|m| std::cmp::Reverse(m.compute_units));
+        let passing: Vec<&Sample> = measured.iter().map(|m| &samples[m.sample]).collect();
+        report.calibrated_compute_unit_limit = bench.calibrate(&passing).await?;
+    }
+    Ok(report)
+}
+
+struct Bench {
+    context: ProgramTestContext,
+    program: ProgramInfo,
+}
+
+impl Bench {
+    /// Compute units of one eval; failed transactions are errors.
+    async fn eval(&mut self, sample: &Sample, compute_unit_limit: u32) -> Result<u64> {
+        invoke_program_metered(
+            &mut self.context,
+            self.program.id,
+            compute_unit_limit,
+            &sample.input_bytes,
+            None,
+            &[],
+        )
+        .await
+        .map(|invocation| invocation.compute_units)
+    }
+
+    async fn passes_all(&mut self, samples: &[&Sample], compute_unit_limit: u32) -> Result<bool> {
+        for sample in samples {
+            match self.eval(sample, compute_unit_limit).await {
+                Ok(_) => {}
+                // Too low a limit, whether a builtin or SBF code ran out.
+                Err(err) if is_compute_exceeded(&err) => return Ok(false),
+                // Anything else failed at the maximum limit too.
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(true)
+    }
+
+    /// The smallest limit at which every sample that passed at the maximum
+    /// still passes. Evals are deterministic, so the search is exact.
+    async fn calibrate(&mut self, samples: &[&Sample]) -> Result<Option<u32>> {
+        let (mut fails, mut passes) = (0, MAX_COMPUTE_UNIT_LIMIT);
+        if !self.passes_all(samples, passes).await? {
+            return Ok(None);
+        }
+        while passes - fails > 1 {
+            let mid = fails + (passes - fails) / 2;
+            if self.passes_all(samples, mid).await? {
+                passes = mid;
+            } else {
+                fails = mid;
+            }
+        }
+        Ok(Some(passes))
+    }
+}
+
+fn is_compute_exceeded(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<HarnessError>(),
+        Some(HarnessError::ComputeExceeded(_))
+    )
+}
+
+/// Draws `options.samples` steps: an `ohlcv` length (the program's declared
+/// lookback, or uniform up to `--max-lookback`), then a step with at least
+/// that many bars behind it.
+fn sample_steps(
+    tape: &Tape,
+    options: &BenchOptions,
+    lookback_len: Option<u16>,
+) -> Result<Vec<Sample>> {
+    let bars = tape.bars.len();
+    let mut rng = Rng::new(options.seed);
+    let max_len = (options.max_lookback as usize).min(bars).max(1);
+    (0..options.samples)
+        .map(|_| {
+            let len = match lookback_len {
+                Some(len) => (len as usize).min(bars),
+                None => 1 + rng.below(max_len as u64) as usize,
+            };
+            let end = len - 1 + rng.below((bars - len + 1) as u64) as usize;
+            let input = tape.flat_input(end, len);
+            Ok(Sample {
+                input_bytes: input.to_bytes()?,
+                input,
+            })
+        })
+        .collect()
+}
+
+fn sample_json(sample: &Sample, compute_units: Option<u64>, detail: String) -> BenchSampleJson {
+    BenchSampleJson {
+        step_index: sample.input.step_index,
+        ohlcv_len: sample.input.lookback_len,
+        compute_units,
+        detail: (!detail.is_empty()).then_some(detail),
+    }
+}
+
+/// Min, nearest-rank p50 and p95, and max.
+pub fn stats(values: &[u64]) -> BenchStatsJson {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = |pct: usize| {
+        let index = (sorted.len() * pct).div_ceil(100).max(1) - 1;
+        sorted.get(index).copied().unwrap_or(0)
+    };
+    BenchStatsJson {
+        min: sorted.first().copied().unwrap_or(0),
+        p50: rank(50),
+        p95: rank(95),
+        max: sorted.last().copied().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ProgramSpec;
+    use crate::replay::tests::flat_bars;
+
+    fn options(samples: u32, max_lookback: u16) -> BenchOptions {
+        BenchOptions {
+            program: ProgramSpec {
+                id: "hold".to_string(),
+                so_path: None,
+                builtin: Some("hold".to_string()),
+                lookback_len: None,
+            },
+            data_path: "bars.json".into(),
+            samples,
+            seed: 3,
+            max_lookback,
+            compute_unit_limit: 200_000,
+            calibrate: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn calibrates_sbf_programs_through_budget_exhaustion() {
+        let dir = std::env::temp_dir().join(format!("solclash-bench-sbf-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let bars: Vec<serde_json::Value> = (0..20)
+            .map(|i| {
+                serde_json::json!({
+                    "bar_start_ts_ms": i * 60_000,
+                    "open": 100.0, "high": 100.0, "low": 100.0, "close": 100.0, "volume": 10.0,
+                })
+            })
+            .collect();
+        let data_path = dir.join("bars.json");
+        std::fs::write(&data_path, serde_json::to_vec(&bars).unwrap()).expect("tape");
+        let so_path = crate::sbf_fixtures::looping_program(&dir, Some(1_000));
+        let options = BenchOptions {
+            program: ProgramSpec {
+                id: "loop".to_string(),
+                so_path: Some(so_path.display().to_string()),
+                builtin: None,
+                lookback_len: None,
+            },
+            data_path,
+            calibrate: true,
+            ..options(5, 8)
+        };
+
+        let report = run(&options).await.expect("bench");
+        assert!(report.passed, "{:?}", report.failures);
+        let max = report.compute_units.max;
+        assert!(max > 1_000);
+        assert_eq!(report.calibrated_compute_unit_limit, Some(max as u32));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stats_use_nearest_rank() {
+        let values: Vec<u64> = (1..=100).rev().collect();
+        let stats = stats(&values);
+        assert_eq!(
+            (stats.min, stats.p50, stats.p95, stats.max),
+            (1, 50, 95, 100)
+        );
+        assert_eq!(super::stats(&[7]).p95, 7);
+        assert_eq!(super::stats(&[]).max, 0);
+    }
+
+    #[test]
+    fn samples_end_at_real_steps_with_full_lookbacks() {
+        let tape = Tape {
+            instrument: None,
+            bars: flat_bars(50, 100.0),
+        };
+        let samples = sample_steps(&tape, &options(200, 64), None).unwrap();
+        assert_eq!(samples.len(), 200);
+        for sample in &samples {
+            let input = &sample.input;
+            assert!(input.lookback_len >= 1 && input.lookback_len <= 50);
+            assert!(input.step_index as usize + 1 >= input.lookback_len as usize);
+            assert!((input.step_index as usize) < tape.bars.len());
+        }
+        assert!(samples.iter().any(|s| s.input.lookback_len == 50));
+
+        let fixed = sample_steps(&tape, &options(20, 64), Some(10)).unwrap();
+        assert!(fixed.iter().all(|s| s.input.lookback_len == 10));
+    }
+}
@@ -0,0 +1,119 @@
+//! Native test programs, registered with `ProgramTest::add_builtin_program`
+//! so `init`/`eval` can be exercised without `cargo build-sbf`. A
+//! `ProgramSpec` selects one with `"builtin": "<name>"` instead of `so_path`.
+//! They inject faults into the runtime, so only debug builds and builds with
+//! the `test-programs` feature include them.
+//!
+//! Builtins skip artifact validation and are not metered like SBF code:
+//! apart from `future_abi`, they answer the ABI probe like any other
+//! instruction (so negotiate v1) and may read sysvars at runtime.
+
+use crate::abi::{AbiDeclaration, ActionType, EvalOutputV1, ABI_PROBE_IX};
+use crate::baselines::{output_account, write_output};
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::InstructionError;
+use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::Sysvar;
+use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
+use solana_program_test::{
+    get_runtime_environment_key, processor, EbpfError, EbpfVm, InvokeContext,
+};
+
+pub const CATALOG: &[&str] = &[
+    "hold",
+    "always_buy",
+    "panic",
+    "infinite_loop",
+    "garbage",
+    "short_output",
+    "read_clock",
+    "future_abi",
+];
+
+pub fn lookup(name: &str) -> Option<BuiltinFunctionWithContext> {
+    match name {
+        "hold" => processor!(hold),
+        "always_buy" => processor!(always_buy),
+        "panic" => Some(panic),
+        "infinite_loop" => Some(infinite_loop),
+        "garbage" => processor!(garbage),
+        "short_output" => processor!(short_output),
+        "read_clock" => processor!(read_clock),
+        "future_abi" => processor!(future_abi),
+        _ => None,
+    }
+}
+
+/// HOLD with `err_code` 0 on every instruction.
+fn hold(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    write_output(accounts, EvalOutputV1::hold(0))
+}
+
+/// BUY 1 on every instruction.
+fn always_buy(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    write_output(accounts, EvalOutputV1::new(ActionType::Buy, 1))
+}
+
+/// Fills the output account with a byte pattern that is no output version.
+fn garbage(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    output_account(accounts)?.data.borrow_mut().fill(0xa5);
+    Ok(())
+}
+
+/// Shrinks the output account below the v1 layout and writes what fits.
+fn short_output(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    let output = output_account(accounts)?;
+    output.realloc(10, false)?;
+    let bytes = borsh::to_vec(&EvalOutputV1::hold(0))?;
+    output.data.borrow_mut().copy_from_slice(&bytes[..10]);
+    Ok(())
+}
+
+/// Reads the clock sysvar and targets a position of the current slot.
+fn read_clock(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    let clock = Clock::get()?;
+    write_output(accounts, EvalOutputV1::target_position(clock.slot as i64))
+}
+
+/// Declares only ABI versions newer than any the harness speaks, so init
+/// rejects it; other instructions HOLD.
+fn future_abi(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data != ABI_PROBE_IX {
+        return write_output(accounts, EvalOutputV1::hold(0));
+    }
+    let declaration = borsh::to_vec(&AbiDeclaration::new(&[16], &[16], 0))?;
+    output_account(accounts)?.data.borrow_mut()[..declaration.len()].copy_from_slice(&declaration);
+    Ok(())
+}
+
+/// Fails the way an SBF program that panics does, e.g. a policy unwrapping
+/// an overflowed value. A real native panic would unwind through the bank's
+/// worker and stall the request until its deadline.
+fn panic(vm: *mut EbpfVm<InvokeContext>, _: u64, _: u64, _: u64, _: u64, _: u64) {
+    let vm = recover_vm(vm);
+    let err = Box::new(InstructionError::ProgramFailedToComplete);
+    vm.program_result = Err(EbpfError::SyscallError(err)).into();
+}
+
+/// Runs out of compute the way an SBF program stuck in a loop does: the
+/// meter is drained, then the VM stops with `ExceededMaxInstructions`, which
+/// the runtime reports as `ProgramFailedToComplete` with an "exceeded CUs
+/// meter" log.
+fn infinite_loop(vm: *mut EbpfVm<InvokeContext>, _: u64, _: u64, _: u64, _: u64, _: u64) {
+    let vm = recover_vm(vm);
+    let _ = vm.context_object_pointer.consume_checked(u64::MAX);
+    vm.program_result = Err(EbpfError::ExceededMaxInstructions).into();
+}
+
+/// Recovers the VM from the pointer the runtime passes to every builtin, for
+/// builtins that set their own result instead of going through `processor!`.
+fn recover_vm<'a>(vm: *mut EbpfVm<InvokeContext>) -> &'a mut EbpfVm<'a, InvokeContext<'a>> {
+    // SAFETY: mirrors `processor!`; the runtime stores the VM at this offset
+    // before the pointer it hands to builtins.
+    unsafe {
+        &mut *((vm as *mut u64).offset(-(get_runtime_environment_key() as isize))
+            as *mut EbpfVm<InvokeContext>)
+    }
+}
@@ -0,0 +1,495 @@
+//! Command-line subcommands. Without one the harness serves the JSON-lines
+//! protocol on stdin/stdout, taking only serve flags.
+
+use crate::logging::LogOptions;
+use crate::protocol::ProgramSpec;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const USAGE: &str =
+    "usage: solclash-harness [[--policy-log <policy_log.jsonl>] [--profile-dir <dir>] \
+[--log <filter>] [--log-format human|json] [--log-file <path>] \
+[--log-rotation minutely|hourly|daily|never] | conformance <path.so> | fuzz <path.so> \
+[--iterations N] [--seed N] [--out DIR] [--max-lookback N] | backtest --config <config.json> \
+--data <bars.json> --agent [id=]<path.so>|builtin:<name> ... [--out DIR] | bench \
+<path.so>|builtin:<name> --data <bars.json> [--samples N] [--seed N] [--max-lookback N] \
+[--calibrate] | diff <a> <b> --data <bars.json> [--lookback N]|--transcript <requests.jsonl> \
+| profile <path.so> --input <input.bin>|--data <bars.json> [--step N] \
+[--lookback N] [--out <profile.folded>] | generate-tape \
+<generator_id> [--seed N] [--params <json>|<params.json>] [--out <tape.json|tape.jsonl>]] \
+[--compute-unit-limit N]";
+
+pub enum Command {
+    Serve(ServeOptions),
+    Conformance(ConformanceOptions),
+    Fuzz(FuzzOptions),
+    Backtest(BacktestOptions),
+    Bench(BenchOptions),
+    Diff(DiffOptions),
+    Profile(ProfileOptions),
+    GenerateTape(GenerateTapeOptions),
+}
+
+#[derive(Default)]
+pub struct ServeOptions {
+    /// Appended with one data-ops policy log record per eval.
+    pub policy_log: Option<PathBuf>,
+    /// Where eval `profile` paths are written; profiling is refused
+    /// without it.
+    pub profile_dir: Option<PathBuf>,
+    /// Subcommands take only the `SOLCLASH_LOG*` environment variables.
+    pub log: LogOptions,
+}
+
+pub struct ConformanceOptions {
+    pub so_path: PathBuf,
+    pub compute_unit_limit: u32,
+}
+
+pub struct FuzzOptions {
+    pub so_path: PathBuf,
+    pub compute_unit_limit: u32,
+    pub iterations: u32,
+    pub seed: u64,
+    /// Where minimized reproducing inputs are written.
+    pub out_dir: PathBuf,
+    /// Upper bound on generated `ohlcv` lengths.
+    pub max_lookback: u16,
+}
+
+pub struct BacktestOptions {
+    pub config_path: PathBuf,
+    pub data_path: PathBuf,
+    /// In `--agent` order; ids are unique.
+    pub agents: Vec<ProgramSpec>,
+    /// Where logs, `summary.json` and `round_results.json` are written.
+    pub out_dir: PathBuf,
+    /// Overrides the config's `compute_unit_limit`.
+    pub compute_unit_limit: Option<u32>,
+}
+
+pub struct BenchOptions {
+    pub program: ProgramSpec,
+    pub data_path: PathBuf,
+    pub samples: u32,
+    pub seed: u64,
+    /// Upper bound on sampled `ohlcv` lengths.
+    pub max_lookback: u16,
+    /// The budget samples are checked against.
+    pub compute_unit_limit: u32,
+    pub calibrate: bool,
+}
+
+pub struct DiffOptions {
+    /// Both run under ids `a` and `b`.
+    pub a: ProgramSpec,
+    pub b: ProgramSpec,
+    /// A tape; every step is an input.
+    pub data_path: Option<PathBuf>,
+    /// Bars per tape input; defaults to the arena's 120.
+    pub lookback_len: Option<usize>,
+    /// A recorded serve-protocol session whose eval inputs are replayed;
+    /// exclusive with `data_path`.
+    pub transcript_path: Option<PathBuf>,
+    pub compute_unit_limit: u32,
+}
+
+pub struct ProfileOptions {
+    pub so_path: PathBuf,
+    /// Raw input account bytes, e.g. a fuzz repro.
+    pub input_path: Option<PathBuf>,
+    /// A tape to build the input from; exclusive with `input_path`.
+    pub data_path: Option<PathBuf>,
+    /// Defaults to the last bar.
+    pub step: Option<usize>,
+    pub lookback_len: Option<usize>,
+    /// Where the folded stacks are written.
+    pub out: PathBuf,
+    pub compute_unit_limit: u32,
+}
+
+pub struct GenerateTapeOptions {
+    pub generator_id: String,
+    pub seed: u64,
+    /// Inline JSON object, or a path to one.
+    pub params: Option<String>,
+    /// Written to stdout when unset.
+    pub out: Option<PathBuf>,
+}
+
+pub fn parse(args: &[String], default_compute_unit_limit: u32) -> Result<Command> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(Command::Serve(ServeOptions::default()));
+    };
+    if command.starts_with("--") {
+        let mut parsed = Args::parse(args)?;
+        let options = ServeOptions {
+            policy_log: parsed.flag("policy-log")?,
+            profile_dir: parsed.flag("profile-dir")?,
+            log: LogOptions {
+                filter: parsed.flag("log")?,
+                format: parsed.flag("log-format")?,
+                file: parsed.flag("log-file")?,
+                rotation: parsed.flag("log-rotation")?,
+            },
+        };
+        parsed.finish()?;
+        return Ok(Command::Serve(options));
+    }
+    let mut parsed = Args::parse(rest)?;
+    let compute_unit_limit = parsed.flag("compute-unit-limit")?;
+    let command = match command.as_str() {
+        "conformance" => Command::Conformance(ConformanceOptions {
+            so_path: parsed.positional(0)?.into(),
+            compute_unit_limit: compute_unit_limit.unwrap_or(default_compute_unit_limit),
+        }),
+        "fuzz" => Command::Fuzz(FuzzOptions {
+            so_path: parsed.positional(0)?.into(),
+            compute_unit_limit: compute_unit_limit.unwrap_or(default_compute_unit_limit),
+            iterations: parsed.flag("iterations")?.unwrap_or(1000),
+            seed: parsed.flag("seed")?.unwrap_or(1),
+            out_dir: parsed
+                .flag("out")?
+                .unwrap_or_else(|| PathBuf::from("fuzz-findings")),
+            max_lookback: parsed.flag("max-lookback")?.unwrap_or(1024),
+        }),
+        "backtest" => Command::Backtest(BacktestOptions {
+            config_path: parsed.required("config")?,
+            data_path: parsed.required("data")?,
+            agents: agent_specs(parsed.flag_all("agent"))?,
+            out_dir: parsed
+                .flag("out")?
+                .unwrap_or_else(|| PathBuf::from("backtest")),
+            compute_unit_limit,
+        }),
+        "bench" => Command::Bench(BenchOptions {
+            program: agent_specs(vec![parsed.positional(0)?.to_string()])?.remove(0),
+            data_path: parsed.required("data")?,
+            samples: parsed.flag("samples")?.unwrap_or(200),
+            seed: parsed.flag("seed")?.unwrap_or(1),
+            max_lookback: parsed.flag("max-lookback")?.unwrap_or(1024),
+            compute_unit_limit: compute_unit_limit.unwrap_or(default_compute_unit_limit),
+            calibrate: parsed.switch("calibrate"),
+        }),
+        "diff" => Command::Diff(DiffOptions {
+            a: agent_specs(vec![parsed.positional(0)?.to_string()])?.remove(0),
+            b: agent_specs(vec![parsed.positional(1)?.to_string()])?.remove(0),
+            data_path: parsed.flag("data")?,
+            lookback_len: parsed.flag("lookback")?,
+            transcript_path: parsed.flag("transcript")?,
+            compute_unit_limit: compute_unit_limit.unwrap_or(default_compute_unit_limit),
+        }),
+        "profile" => Command::Profile(ProfileOptions {
+            so_path: parsed.positional(0)?.into(),
+            input_path: parsed.flag("input")?,
+            data_path: parsed.flag("data")?,
+            step: parsed.flag("step")?,
+            lookback_len: parsed.flag("lookback")?,
+            out: parsed
+                .flag("out")?
+                .unwrap_or_else(|| PathBuf::from("profile.folded")),
+            compute_unit_limit: compute_unit_limit.unwrap_or(default_compute_unit_limit),
+        }),
+        "generate-tape" => Command::GenerateTape(GenerateTapeOptions {
+            generator_id: parsed.positional(0)?.to_string(),
+            seed: parsed.flag("seed")?.unwrap_or(1),
+            params: parsed.flag("params")?,
+            out: parsed.flag("out")?,
+        }),
+        other => return Err(anyhow!("unknown command: {other}\n{USAGE}")),
+    };
+    parsed.finish()?;
+    Ok(command)
+}
+
+/// Parses `--agent` values: `builtin:<name>` loads a native test program
+/// under its name, anything else is a `.so` path optionally prefixed with
+/// `<id>=`; the id defaults to the file stem.
+fn agent_specs(values: Vec<String>) -> Result<Vec<ProgramSpec>> {
+    if values.is_empty() {
+        return Err(anyhow!("at least one --agent is required\n{USAGE}"));
+    }
+    let mut specs: Vec<ProgramSpec> = Vec::with_capacity(values.len());
+    for value in values {
+        let spec = match value.strip_prefix("builtin:") {
+            Some(name) => ProgramSpec {
+                id: name.to_string(),
+                so_path: None,
+                builtin: Some(name.to_string()),
+                lookback_len: None,
+            },
+            None => {
+                let (id, path) = match value.split_once('=') {
+                    Some((id, path)) => (id.to_string(), path.to_string()),
+                    None => {
+                        let stem = Path::new(&value)
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().into_owned());
+                        (stem.unwrap_or_else(|| value.clone()), value)
+                    }
+                };
+                ProgramSpec {
+                    id,
+                    so_path: Some(path),
+                    builtin: None,
+                    lookback_len: None,
+                }
+            }
+        };
+        if specs.iter().any(|s| s.id == spec.id) {
+            return Err(anyhow!(
+                "duplicate agent id: {} (name agents with --agent <id>=<path.so>)",
+                spec.id
+            ));
+        }
+        specs.push(spec);
+    }
+    Ok(specs)
+}
+
+/// Flags that take no value.
+const SWITCHES: &[&str] = &["calibrate"];
+
+/// Positional arguments plus `--name value` flags and `SWITCHES`; every
+/// argument must be consumed by the subcommand. Only flags read with
+/// `flag_all` may repeat.
+struct Args {
+    positional: Vec<String>,
+    consumed: usize,
+    flags: HashMap<String, Vec<String>>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut flags: HashMap<String, Vec<String>> = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if SWITCHES.contains(&name) => {
+                    flags.entry(name.to_string()).or_default();
+                }
+                Some(name) => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| anyhow!("--{name} needs a value\n{USAGE}"))?;
+                    flags
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(value.clone());
+                }
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Self {
+            positional,
+            consumed: 0,
+            flags,
+        })
+    }
+
+    fn positional(&mut self, index: usize) -> Result<&str> {
+        self.consumed = self.consumed.max(index + 1);
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("missing argument\n{USAGE}"))
+    }
+
+    fn flag<T: FromStr>(&mut self, name: &str) -> Result<Option<T>> {
+        let Some(mut values) = self.flags.remove(name) else {
+            return Ok(None);
+        };
+        if values.len() > 1 {
+            return Err(anyhow!("--{name} given more than once\n{USAGE}"));
+        }
+        let value = values.remove(0);
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("invalid value for --{name}: {value}"))
+    }
+
+    fn required<T: FromStr>(&mut self, name: &str) -> Result<T> {
+        self.flag(name)?
+            .ok_or_else(|| anyhow!("--{name} is required\n{USAGE}"))
+    }
+
+    fn switch(&mut self, name: &str) -> bool {
+        self.flags.remove(name).is_some()
+    }
+
+    fn flag_all(&mut self, name: &str) -> Vec<String> {
+        self.flags.remove(name).unwrap_or_default()
+    }
+
+    fn finish(self) -> Result<()> {
+        if let Some(name) = self.flags.keys().next() {
+            return Err(anyhow!("unknown option --{name}\n{USAGE}"));
+        }
+        if let Some(extra) = self.positional.get(self.consumed) {
+            return Err(anyhow!("unexpected argument: {extra}\n{USAGE}"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::{LogFormat, LogRotation};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_fuzz_flags_and_rejects_unknown_ones() {
+        let Command::Fuzz(options) =
+            parse(&args("fuzz p.so --seed 7 --iterations 20"), 200_000).unwrap()
+        else {
+            panic!("expected fuzz");
+        };
+        assert_eq!(options.seed, 7);
+        assert_eq!(options.iterations, 20);
+        assert_eq!(options.max_lookback, 1024);
+        assert_eq!(options.compute_unit_limit, 200_000);
+
+        assert!(parse(&args("fuzz p.so --sead 7"), 200_000).is_err());
+        assert!(parse(&args("conformance"), 200_000).is_err());
+        assert!(matches!(
+            parse(&[], 200_000),
+            Ok(Command::Serve(ServeOptions {
+                policy_log: None,
+                ..
+            }))
+        ));
+        assert!(parse(&args("fuzz p.so --seed 1 --seed 2"), 200_000).is_err());
+    }
+
+    #[test]
+    fn parses_repeated_backtest_agents() {
+        let line = "backtest --config c.json --data bars.json --agent a.so \
+                    --agent b=target/deploy/solclash_policy.so --agent builtin:hold";
+        let Command::Backtest(options) = parse(&args(line), 200_000).unwrap() else {
+            panic!("expected backtest");
+        };
+        let ids: Vec<&str> = options.agents.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "hold"]);
+        assert_eq!(
+            options.agents[1].so_path.as_deref(),
+            Some("target/deploy/solclash_policy.so")
+        );
+        assert_eq!(options.agents[2].builtin.as_deref(), Some("hold"));
+        assert_eq!(options.out_dir, PathBuf::from("backtest"));
+        assert_eq!(options.compute_unit_limit, None);
+
+        assert!(parse(&args("backtest --config c.json --data d.json"), 200_000).is_err());
+        assert!(parse(&args("backtest --data d.json --agent a.so"), 200_000).is_err());
+        assert!(parse(
+            &args("backtest --config c.json --data d.json --agent x/a.so --agent y/a.so"),
+            200_000
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parses_serve_policy_log() {
+        let Command::Serve(options) = parse(&args("--policy-log logs/p.jsonl"), 1).unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(options.policy_log, Some(PathBuf::from("logs/p.jsonl")));
+        assert_eq!(options.profile_dir, None);
+        assert_eq!(options.log.filter, None);
+        let Command::Serve(options) = parse(&args("--profile-dir profiles"), 1).unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(options.profile_dir, Some(PathBuf::from("profiles")));
+        assert!(parse(&args("--policy-log p.jsonl --compute-unit-limit 5"), 1).is_err());
+        assert!(parse(&args("--policy-log p.jsonl extra"), 1).is_err());
+    }
+
+    #[test]
+    fn parses_serve_log_flags() {
+        let line = "--log solclash_harness=debug --log-format json --log-file logs/harness.log \
+                    --log-rotation hourly";
+        let Command::Serve(options) = parse(&args(line), 1).unwrap() else {
+            panic!("expected serve");
+        };
+        assert_eq!(
+            options.log.filter.as_deref(),
+            Some("solclash_harness=debug")
+        );
+        assert_eq!(options.log.format, Some(LogFormat::Json));
+        assert_eq!(options.log.file, Some(PathBuf::from("logs/harness.log")));
+        assert_eq!(options.log.rotation, Some(LogRotation::Hourly));
+        assert!(parse(&args("--log-format xml"), 1).is_err());
+    }
+
+    #[test]
+    fn parses_generate_tape() {
+        let line = "generate-tape gap_v1 --seed 9 --params p.json --out tape.jsonl";
+        let Command::GenerateTape(options) = parse(&args(line), 200_000).unwrap() else {
+            panic!("expected generate-tape");
+        };
+        assert_eq!(options.generator_id, "gap_v1");
+        assert_eq!(options.seed, 9);
+        assert_eq!(options.params.as_deref(), Some("p.json"));
+        assert_eq!(options.out, Some(PathBuf::from("tape.jsonl")));
+
+        assert!(parse(&args("generate-tape"), 200_000).is_err());
+    }
+
+    #[test]
+    fn parses_bench_switch() {
+        let line = "bench builtin:hold --data bars.json --calibrate --samples 50";
+        let Command::Bench(options) = parse(&args(line), 200_000).unwrap() else {
+            panic!("expected bench");
+        };
+        assert_eq!(options.program.builtin.as_deref(), Some("hold"));
+        assert!(options.calibrate);
+        assert_eq!(options.samples, 50);
+        assert_eq!(options.compute_unit_limit, 200_000);
+
+        let Command::Bench(options) = parse(&args("bench p.so --data d.json"), 1).unwrap() else {
+            panic!("expected bench");
+        };
+        assert!(!options.calibrate);
+        assert_eq!(options.program.so_path.as_deref(), Some("p.so"));
+        assert!(parse(&args("fuzz p.so --calibrate"), 1).is_err());
+    }
+
+    #[test]
+    fn parses_diff_programs_and_sources() {
+        let line = "diff builtin:always_buy target/deploy/p.so --transcript session.jsonl";
+        let Command::Diff(options) = parse(&args(line), 200_000).unwrap() else {
+            panic!("expected diff");
+        };
+        assert_eq!(options.a.source(), "builtin:always_buy");
+        assert_eq!(options.b.so_path.as_deref(), Some("target/deploy/p.so"));
+        assert_eq!(
+            options.transcript_path,
+            Some(PathBuf::from("session.jsonl"))
+        );
+        assert_eq!((options.data_path, options.lookback_len), (None, None));
+
+        assert!(parse(&args("diff a.so --data bars.json"), 200_000).is_err());
+        assert!(parse(&args("diff a.so b.so c.so --data bars.json"), 200_000).is_err());
+    }
+
+    #[test]
+    fn parses_profile_inputs() {
+        let line = "profile p.so --data bars.json --step 40 --lookback 16";
+        let Command::Profile(options) = parse(&args(line), 200_000).unwrap() else {
+            panic!("expected profile");
+        };
+        assert_eq!(options.data_path, Some(PathBuf::from("bars.json")));
+        assert_eq!(options.input_path, None);
+        assert_eq!((options.step, options.lookback_len), (Some(40), Some(16)));
+        assert_eq!(options.out, PathBuf::from("profile.folded"));
+
+        assert!(parse(&args("profile --input repro.bin"), 200_000).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! The arena config read by `backtest`: the same JSON file the TS loader
+//! takes (e.g. `default-config.json`), with the same defaults and validation
+//! rules (data-ops spec §4). Keys that do not affect the simulation, such as
+//! `arena_id`, `players` and `tape_source`, are ignored.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+pub struct ArenaConfig {
+    pub quote_mint: String,
+    pub bar_interval_seconds: u32,
+    /// Falls back to the tape's instrument metadata, then 1_000_000.
+    pub price_scale: Option<u32>,
+    pub volume_scale: Option<u32>,
+    pub window_duration_bars: usize,
+    pub max_window_overlap_pct: u32,
+    pub number_of_windows_per_round: usize,
+    #[serde(default)]
+    pub window_sampling: WindowSampling,
+    pub lookback_len: usize,
+    pub slippage_bps: u32,
+    pub impact_k_bps: u32,
+    pub impact_cap_bps: Option<u32>,
+    pub liquidity_multiplier: f64,
+    pub min_liquidity: u64,
+    pub taker_fee_bps: u32,
+    pub initial_margin_bps: u32,
+    pub maintenance_margin_bps: u32,
+    pub max_leverage_bps: u32,
+    pub liquidation_fee_bps: u32,
+    pub funding_rate_bps_per_bar: u32,
+    pub initial_balances: Vec<Balance>,
+    pub scoring_weights: Option<ScoringWeights>,
+    #[serde(default = "default_scoring_weights_reference")]
+    pub scoring_weights_reference: String,
+    pub baseline_bots_enabled: Vec<String>,
+    pub compute_unit_limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Balance {
+    pub mint: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ScoringWeights {
+    pub pnl: f64,
+    pub drawdown: f64,
+    pub exposure: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WindowSampling {
+    #[serde(default)]
+    pub mode: SamplingMode,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingMode {
+    #[default]
+    Sequential,
+    Stratified,
+}
+
+fn default_scoring_weights_reference() -> String {
+    "docs/scoring-weights.json".to_string()
+}
+
+impl ArenaConfig {
+    /// Reads and validates a config, resolving `scoring_weights_reference`
+    /// against the working directory when no weights are inline.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read config {}", path.display()))?;
+        let mut config: ArenaConfig = serde_json::from_str(&text)
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        config.validate()?;
+        if config.scoring_weights.is_none() {
+            let weights_path = resolve_weights_path(&config.scoring_weights_reference);
+            let text = std::fs::read_to_string(&weights_path).with_context(|| {
+                format!("cannot read scoring weights {}", weights_path.display())
+            })?;
+            config.scoring_weights = Some(serde_json::from_str(&text).with_context(|| {
+                format!("invalid scoring weights at {}", weights_path.display())
+            })?);
+        }
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.maintenance_margin_bps > self.initial_margin_bps {
+            return Err(anyhow!(
+                "invalid config: maintenance_margin_bps must be <= initial_margin_bps"
+            ));
+        }
+        if self.max_leverage_bps < 10_000 {
+            return Err(anyhow!("invalid config: max_leverage_bps must be >= 10000"));
+        }
+        if self.lookback_len >= self.window_duration_bars {
+            return Err(anyhow!(
+                "invalid config: lookback_len must be < window_duration_bars"
+            ));
+        }
+        if self.max_window_overlap_pct > 100 {
+            return Err(anyhow!(
+                "invalid config: max_window_overlap_pct must be <= 100"
+            ));
+        }
+        if !self
+            .initial_balances
+            .iter()
+            .any(|b| b.mint == self.quote_mint)
+        {
+            return Err(anyhow!(
+                "invalid config: initial_balances must include quote_mint"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn initial_cash(&self) -> f64 {
+        self.initial_balances
+            .iter()
+            .find(|b| b.mint == self.quote_mint)
+            .map_or(0.0, |b| b.amount)
+    }
+
+    pub fn weights(&self) -> ScoringWeights {
+        self.scoring_weights.unwrap_or(ScoringWeights {
+            pnl: 0.0,
+            drawdown: 0.0,
+            exposure: 0.0,
+        })
+    }
+}
+
+/// Paths (anything with a `/` or ending in `.json`) resolve as given;
+/// bare ids resolve to `docs/scoring-weights/<id>.json`.
+fn resolve_weights_path(reference: &str) -> PathBuf {
+    if reference.contains('/') || reference.ends_with(".json") {
+        PathBuf::from(reference)
+    } else {
+        Path::new("docs")
+            .join("scoring-weights")
+            .join(format!("{reference}.json"))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const DEFAULT_CONFIG: &str =
+        include_str!("../../../packages/arenas/arenas/btc-perp-v1/default-config.json");
+
+    pub fn sample_config() -> ArenaConfig {
+        serde_json::from_str(DEFAULT_CONFIG).expect("default config")
+    }
+
+    #[test]
+    fn parses_the_arena_default_config() {
+        let config = sample_config();
+        config.validate().unwrap();
+        assert_eq!(config.window_sampling.mode, SamplingMode::Sequential);
+        assert_eq!(config.impact_cap_bps, Some(50));
+        assert_eq!(config.initial_cash(), 10_000.0);
+        assert_eq!(config.weights().drawdown, -0.5);
+        assert_eq!(
+            resolve_weights_path("aggressive"),
+            Path::new("docs/scoring-weights/aggressive.json")
+        );
+
+        let mut config = sample_config();
+        config.lookback_len = config.window_duration_bars;
+        assert!(config.validate().is_err());
+    }
+}
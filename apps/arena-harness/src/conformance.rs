@@ -0,0 +1,410 @@
+//! `solclash-harness conformance <path.so>`: runs the starter-contract
+//! checklist (`docs/solclash-starter-contract.md` §3) against one program and
+//! reports each check. A conforming program returns `Ok` on every case and
+//! writes the documented HOLD `err_code` wherever the input is unusable.
+
+use crate::abi::fixtures::sample_input;
+use crate::abi::validation::{self, OutputFields};
+use crate::abi::{
+    highest_common_version, AbiDeclaration, ABI_PROBE_IX, ACTION_HOLD, ERR_INPUT_DESER_FAIL,
+    ERR_INVALID_INPUT_VERSION, ERR_INVALID_INSTRUCTION_DATA, ERR_INVALID_LOOKBACK_LEN,
+    INPUT_VERSION_MAX, OUTPUT_LEN, OUTPUT_V3_LEN, OUTPUT_VERSION_MAX,
+};
+use crate::artifact::DEFAULT_MAX_PROGRAM_BYTES;
+use crate::cli::ConformanceOptions;
+use crate::{check_artifact, decode_output, sandbox, start_program};
+use anyhow::Result;
+use serde::Serialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::account::AccountSharedData;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+
+const PROGRAM_NAME: &str = "solclash-conformance";
+
+/// One checklist case: the accounts and instruction data to pass, and what
+/// the program must write. `None` leaves the account out of the instruction.
+struct Check {
+    name: &'static str,
+    input: Option<Vec<u8>>,
+    output_len: Option<usize>,
+    instruction_data: &'static [u8],
+    expect: Expect,
+}
+
+enum Expect {
+    /// An `AbiDeclaration`, or the v1 HOLD legacy programs answer with.
+    Declaration,
+    /// Any output that passes the ABI rules.
+    ValidOutput,
+    /// HOLD with this err code.
+    Hold(u16),
+    /// Only `Ok`; there is no usable output account to write to.
+    ReturnsOk,
+}
+
+impl Expect {
+    fn describe(&self) -> String {
+        match self {
+            Expect::Declaration => "Ok with an AbiDeclaration or a v1 HOLD".to_string(),
+            Expect::ValidOutput => "Ok with a valid output".to_string(),
+            Expect::Hold(err_code) => format!("Ok with HOLD err_code={err_code}"),
+            Expect::ReturnsOk => "Ok".to_string(),
+        }
+    }
+}
+
+/// Report printed by `solclash-harness conformance <path.so>`.
+#[derive(Debug, Serialize)]
+pub struct ConformanceReport {
+    pub program: String,
+    pub passed: bool,
+    /// Artifact validation failure; the program was not loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<String>,
+    pub input_version: u8,
+    pub output_version: u8,
+    pub checks: Vec<ConformanceCheckJson>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConformanceCheckJson {
+    pub name: String,
+    pub passed: bool,
+    pub expected: String,
+    /// What the program did instead, set on failed checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Loads the program and runs the checklist. Unreadable or invalid artifacts
+/// are reported as rejected without running any checks.
+pub async fn run(options: &ConformanceOptions) -> Result<ConformanceReport> {
+    let so_path = options.so_path.as_path();
+    let mut report = ConformanceReport {
+        program: so_path.display().to_string(),
+        passed: false,
+        rejected: None,
+        input_version: 1,
+        output_version: 1,
+        checks: Vec::new(),
+    };
+    if let Err(reason) = check_artifact(so_path, DEFAULT_MAX_PROGRAM_BYTES) {
+        report.rejected = Some(reason);
+        return Ok(report);
+    }
+    let (mut context, program_id) = start_program(so_path, PROGRAM_NAME).await?;
+    let compute_unit_limit = options.compute_unit_limit;
+
+    let probe = Check {
+        name: "abi_probe",
+        input: Some(Vec::new()),
+        output_len: Some(OUTPUT_V3_LEN),
+        instruction_data: ABI_PROBE_IX,
+        expect: Expect::Declaration,
+    };
+    let answer = invoke(&mut context, program_id, compute_unit_limit, &probe).await;
+    let mut probe_result = verify(&probe.expect, &answer, 1, 0);
+    if let Some(decl) = answer
+        .as_ref()
+        .ok()
+        .and_then(|data| AbiDeclaration::decode(data.as_deref().unwrap_or_default()))
+    {
+        let versions = highest_common_version(decl.input_versions, INPUT_VERSION_MAX).zip(
+            highest_common_version(decl.output_versions, OUTPUT_VERSION_MAX),
+        );
+        match versions {
+            Some((input_version, output_version)) => {
+                report.input_version = input_version;
+                report.output_version = output_version;
+            }
+            None => probe_result = Err("no common ABI version".to_string()),
+        }
+    }
+    report.checks.push(check_json(&probe, probe_result));
+
+    let position_qty = sample_input(report.input_version).position_qty;
+    for check in checklist(report.input_version) {
+        let outcome = invoke(&mut context, program_id, compute_unit_limit, &check).await;
+        let result = verify(&check.expect, &outcome, report.output_version, position_qty);
+        report.checks.push(check_json(&check, result));
+    }
+    report.passed = report.checks.iter().all(|check| check.passed);
+    Ok(report)
+}
+
+/// The §3 cases other than the ABI probe, built from the shared sample input
+/// at the program's negotiated input version.
+fn checklist(input_version: u8) -> Vec<Check> {
+    let encode = |input: &crate::abi::EvalInputV1| input.to_bytes().unwrap_or_default();
+    let valid = encode(&sample_input(input_version));
+
+    let mut bad_version = sample_input(1);
+    bad_version.version = 0;
+    let mut lookback_mismatch = sample_input(input_version);
+    lookback_mismatch.lookback_len += 1;
+    let mut empty_ohlcv = sample_input(input_version);
+    empty_ohlcv.ohlcv.clear();
+    empty_ohlcv.lookback_len = 0;
+
+    let check = |name, input: Option<Vec<u8>>, output_len, instruction_data, expect| Check {
+        name,
+        input,
+        output_len,
+        instruction_data,
+        expect,
+    };
+    let full = Some(OUTPUT_V3_LEN);
+    vec![
+        check(
+            "empty_instruction_data",
+            Some(valid.clone()),
+            full,
+            &[],
+            Expect::ValidOutput,
+        ),
+        check(
+            "non_empty_instruction_data",
+            Some(valid.clone()),
+            full,
+            &[1],
+            Expect::Hold(ERR_INVALID_INSTRUCTION_DATA),
+        ),
+        check(
+            "bad_version",
+            Some(encode(&bad_version)),
+            full,
+            &[],
+            Expect::Hold(ERR_INVALID_INPUT_VERSION),
+        ),
+        check(
+            "lookback_len_mismatch",
+            Some(encode(&lookback_mismatch)),
+            full,
+            &[],
+            Expect::Hold(ERR_INVALID_LOOKBACK_LEN),
+        ),
+        check(
+            "truncated_input",
+            Some(valid[..valid.len() / 2].to_vec()),
+            full,
+            &[],
+            Expect::Hold(ERR_INPUT_DESER_FAIL),
+        ),
+        check(
+            "garbage_input",
+            Some(vec![0xff; 64]),
+            full,
+            &[],
+            Expect::Hold(ERR_INPUT_DESER_FAIL),
+        ),
+        check(
+            "empty_input",
+            Some(Vec::new()),
+            full,
+            &[],
+            Expect::Hold(ERR_INPUT_DESER_FAIL),
+        ),
+        check(
+            "empty_ohlcv",
+            Some(encode(&empty_ohlcv)),
+            full,
+            &[],
+            Expect::ValidOutput,
+        ),
+        check(
+            "short_output_account",
+            Some(valid.clone()),
+            Some(OUTPUT_LEN / 2),
+            &[],
+            Expect::ReturnsOk,
+        ),
+        check(
+            "missing_output_account",
+            Some(valid),
+            None,
+            &[],
+            Expect::ReturnsOk,
+        ),
+        check("missing_accounts", None, None, &[], Expect::ReturnsOk),
+    ]
+}
+
+/// Runs one case and returns the output account data, if the case passes
+/// one. Any transaction failure or cross-program invocation is an error.
+async fn invoke(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    compute_unit_limit: u32,
+    check: &Check,
+) -> Result<Option<Vec<u8>>, String> {
+    let rent = solana_sdk::rent::Rent::default();
+    let mut accounts = Vec::new();
+    if let Some(input) = &check.input {
+        let pubkey = Pubkey::new_unique();
+        let mut account =
+            AccountSharedData::new(rent.minimum_balance(input.len()), input.len(), &program_id);
+        account.set_data_from_slice(input);
+        context.set_account(&pubkey, &account);
+        accounts.push(AccountMeta::new_readonly(pubkey, false));
+    }
+    let output_pubkey = check.output_len.map(|len| {
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::new(rent.minimum_balance(len), len, &program_id);
+        context.set_account(&pubkey, &account);
+        accounts.push(AccountMeta::new(pubkey, false));
+        pubkey
+    });
+
+    let instructions = [
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+        Instruction {
+            program_id,
+            accounts,
+            data: check.instruction_data.to_vec(),
+        },
+    ];
+    let recent_blockhash = context
+        .banks_client
+        .get_latest_blockhash()
+        .await
+        .map_err(|err| err.to_string())?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    let processed = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await
+        .map_err(|err| err.to_string())?;
+    let logs = processed
+        .metadata
+        .map(|meta| meta.log_messages)
+        .unwrap_or_default();
+    if let Some(reason) = sandbox::check_logs(&logs) {
+        return Err(reason);
+    }
+    processed
+        .result
+        .map_err(|err| format!("returned an error: {err}"))?;
+
+    let Some(pubkey) = output_pubkey else {
+        return Ok(None);
+    };
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(account.map(|account| account.data))
+}
+
+fn verify(
+    expect: &Expect,
+    outcome: &Result<Option<Vec<u8>>, String>,
+    output_version: u8,
+    position_qty: i64,
+) -> Result<(), String> {
+    let data = outcome.clone()?.unwrap_or_default();
+    let valid_output = || {
+        let output = decode_output(&data).map_err(|err| format!("undecodable output: {err}"))?;
+        validation::validate_output(&OutputFields::from(&output), output_version, position_qty)
+            .map_err(|violation| format!("invalid output: {}", violation.reason()))?;
+        Ok::<_, String>(output)
+    };
+    match expect {
+        Expect::ReturnsOk => Ok(()),
+        Expect::Declaration if AbiDeclaration::decode(&data).is_some() => Ok(()),
+        Expect::Declaration => match decode_output(&data) {
+            Ok(output)
+                if output.action_type == ACTION_HOLD
+                    && output.err_code == ERR_INVALID_INSTRUCTION_DATA =>
+            {
+                Ok(())
+            }
+            _ => Err("wrote neither an AbiDeclaration nor a v1 HOLD".to_string()),
+        },
+        Expect::ValidOutput => valid_output().map(|_| ()),
+        Expect::Hold(err_code) => {
+            let output = valid_output()?;
+            if output.action_type == ACTION_HOLD && output.err_code == *err_code {
+                Ok(())
+            } else {
+                Err(format!(
+                    "wrote action_type={} err_code={}",
+                    output.action_type, output.err_code
+                ))
+            }
+        }
+    }
+}
+
+fn check_json(check: &Check, result: Result<(), String>) -> ConformanceCheckJson {
+    ConformanceCheckJson {
+        name: check.name.to_string(),
+        passed: result.is_ok(),
+        expected: check.expect.describe(),
+        detail: result.err(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{EvalInputV1, INPUT_VERSION_DEPTH};
+
+    #[test]
+    fn checklist_inputs_hit_their_documented_rule() {
+        for input_version in [1, INPUT_VERSION_DEPTH] {
+            for check in checklist(input_version) {
+                let decoded = check.input.as_deref().map(EvalInputV1::from_bytes);
+                match (&check.expect, decoded) {
+                    (Expect::Hold(ERR_INPUT_DESER_FAIL), Some(decoded)) => {
+                        assert!(decoded.is_err(), "{}", check.name)
+                    }
+                    (Expect::Hold(ERR_INVALID_INPUT_VERSION), Some(decoded)) => {
+                        let version = decoded.expect(check.name).version;
+                        assert!(
+                            version == 0 || version > INPUT_VERSION_MAX,
+                            "{}",
+                            check.name
+                        );
+                    }
+                    (Expect::Hold(ERR_INVALID_LOOKBACK_LEN), Some(decoded)) => {
+                        let input = decoded.expect(check.name);
+                        assert_ne!(input.lookback_len as usize, input.ohlcv.len());
+                    }
+                    (Expect::Hold(ERR_INVALID_INSTRUCTION_DATA), Some(decoded)) => {
+                        assert!(decoded.is_ok() && !check.instruction_data.is_empty());
+                    }
+                    (Expect::ValidOutput | Expect::ReturnsOk, Some(decoded)) => {
+                        let input = decoded.expect(check.name);
+                        assert_eq!(input.version, input_version);
+                        assert_eq!(input.lookback_len as usize, input.ohlcv.len());
+                    }
+                    (_, decoded) => assert!(decoded.is_none(), "{}", check.name),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hold_expectation_checks_err_code() {
+        let hold = |err_code| {
+            let output = crate::abi::EvalOutputV1::hold(err_code);
+            Ok(Some(borsh::to_vec(&output).unwrap()))
+        };
+        let expect = Expect::Hold(ERR_INVALID_LOOKBACK_LEN);
+        assert!(verify(&expect, &hold(ERR_INVALID_LOOKBACK_LEN), 1, 0).is_ok());
+        assert!(verify(&expect, &hold(ERR_INPUT_DESER_FAIL), 1, 0).is_err());
+        assert!(verify(&expect, &Ok(None), 1, 0).is_err());
+        assert!(verify(&expect, &Err("panicked".to_string()), 1, 0).is_err());
+        assert!(verify(&Expect::ReturnsOk, &Ok(None), 1, 0).is_ok());
+    }
+}
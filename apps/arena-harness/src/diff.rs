@@ -0,0 +1,377 @@
+//! `solclash-harness diff <a> <b>`: evaluates two programs on one identical
+//! stream of inputs and reports every step where their decisions differ,
+//! with action counts and compute deltas. Inputs are every step of a tape
+//! on a flat account, or the eval inputs of a recorded serve transcript.
+
+use crate::abi::{
+    EvalInputV1, EvalOutputV3, ACTION_BUY, ACTION_BUY_NOTIONAL, ACTION_CLOSE,
+    ACTION_FLAG_REDUCE_ONLY, ACTION_HOLD, ACTION_SELL, ACTION_SELL_NOTIONAL,
+    ACTION_TARGET_POSITION,
+};
+use crate::artifact::DEFAULT_MAX_PROGRAM_BYTES;
+use crate::bench::stats;
+use crate::cli::DiffOptions;
+use crate::protocol::{BenchStatsJson, EvalOutputJson, ProgramRejectionJson, ProgramSpec, Request};
+use crate::replay::{load_tape, DEFAULT_LOOKBACK_LEN};
+use crate::status::{self, Failure};
+use crate::tape::WindowTape;
+use crate::{
+    convert_input, evaluate_output, init_programs, invoke_program_metered, output_json,
+    LookbackBounds, ProgramInfo,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use solana_program_test::ProgramTestContext;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const IDS: [&str; 2] = ["a", "b"];
+
+/// One program's answer to one input.
+struct Decision {
+    status: &'static str,
+    output: EvalOutputV3,
+    reason: Option<String>,
+    compute_units: Option<u64>,
+}
+
+impl Decision {
+    /// What the two programs are compared on: the order they place, or a
+    /// failure. Err codes and annotations are not compared.
+    fn key(&self) -> (&'static str, u8, i64, u8, i64, u16) {
+        let o = &self.output;
+        (
+            self.status,
+            o.action_type,
+            o.order_qty,
+            o.order_type,
+            o.trigger_price,
+            o.time_in_force_bars,
+        )
+    }
+
+    fn json(self) -> DiffSideJson {
+        DiffSideJson {
+            status: self.status.to_string(),
+            action: action_name(self.output.action_type),
+            output: output_json(&self.output),
+            reason: self.reason,
+            compute_units: self.compute_units,
+        }
+    }
+}
+
+/// Report printed by `solclash-harness diff`. Program `a` is evaluated
+/// before `b` on every input; deltas are `b - a`.
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    pub a: String,
+    pub b: String,
+    /// The tape or transcript the inputs came from.
+    pub inputs: String,
+    pub steps: usize,
+    /// False when a program failed to load or any step differed.
+    pub passed: bool,
+    pub rejected: Vec<ProgramRejectionJson>,
+    pub differing_steps: usize,
+    /// Decisions by action name (`BUY`, `SELL_REDUCE_ONLY`, ...); failed
+    /// evals count under their status (`ERR`, `SANDBOX_VIOLATION`).
+    pub actions: BTreeMap<String, DiffCountsJson>,
+    pub compute_units: DiffUnitsJson,
+    pub differences: Vec<DiffStepJson>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DiffCountsJson {
+    pub a: u32,
+    pub b: u32,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DiffUnitsJson {
+    pub a: BenchStatsJson,
+    pub b: BenchStatsJson,
+    /// Over the steps both programs completed.
+    pub delta_total: i64,
+    pub delta_min: i64,
+    pub delta_max: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffStepJson {
+    /// Position in the input stream.
+    pub step: usize,
+    pub step_index: u32,
+    pub a: DiffSideJson,
+    pub b: DiffSideJson,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffSideJson {
+    /// `OK`, `ERR` or `SANDBOX_VIOLATION`.
+    pub status: String,
+    pub action: String,
+    pub output: EvalOutputJson,
+    /// The validation or failure reason, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_units: Option<u64>,
+}
+
+pub async fn run(options: &DiffOptions) -> Result<DiffReport> {
+    let (source, inputs) = match (&options.data_path, &options.transcript_path) {
+        (Some(path), None) => (
+            path.display().to_string(),
+            tape_inputs(path, options.lookback_len)?,
+        ),
+        (None, Some(path)) => (path.display().to_string(), transcript_inputs(path)?),
+        _ => return Err(anyhow!("set exactly one of --data and --transcript")),
+    };
+    let mut report = DiffReport {
+        a: options.a.source(),
+        b: options.b.source(),
+        inputs: source,
+        steps: inputs.len(),
+        passed: false,
+        rejected: Vec::new(),
+        differing_steps: 0,
+        actions: BTreeMap::new(),
+        compute_units: DiffUnitsJson::default(),
+        differences: Vec::new(),
+    };
+    let specs = [&options.a, &options.b]
+        .into_iter()
+        .zip(IDS)
+        .map(|(spec, id)| ProgramSpec {
+            id: id.to_string(),
+            ..spec.clone()
+        })
+        .collect();
+    let (mut context, programs, rejected) = init_programs(
+        specs,
+        &[],
+        options.compute_unit_limit,
+        LookbackBounds {
+            min: 1,
+            max: u16::MAX,
+        },
+        DEFAULT_MAX_PROGRAM_BYTES,
+    )
+    .await?;
+    if !rejected.is_empty() {
+        report.rejected = rejected;
+        return Ok(report);
+    }
+
+    // As in serve mode, programs that declare a lookback see exactly that
+    // many bars of what the stream has shown so far.
+    let retain = programs.values().filter_map(|p| p.lookback_len).max();
+    let mut tape = WindowTape::new(retain.unwrap_or(0));
+    let mut units: [Vec<u64>; 2] = Default::default();
+    let mut deltas = Vec::new();
+    for (step, input) in inputs.iter().enumerate() {
+        tape.record(input);
+        let mut decisions = Vec::with_capacity(IDS.len());
+        for (side, id) in IDS.iter().enumerate() {
+            let decision = decide(
+                &mut context,
+                &programs[*id],
+                options.compute_unit_limit,
+                &tape,
+                input,
+            )
+            .await?;
+            // Failed evals are counted by status, not by their HOLD.
+            let action = match decision.status {
+                status::OK => action_name(decision.output.action_type),
+                status => status.to_string(),
+            };
+            let counts = report.actions.entry(action).or_default();
+            match side {
+                0 => counts.a += 1,
+                _ => counts.b += 1,
+            }
+            if let Some(cu) = decision.compute_units {
+                units[side].push(cu);
+            }
+            decisions.push(decision);
+        }
+        let b = decisions.pop().expect("decision b");
+        let a = decisions.pop().expect("decision a");
+        if let (Some(a), Some(b)) = (a.compute_units, b.compute_units) {
+            deltas.push(b as i64 - a as i64);
+        }
+        if a.key() != b.key() {
+            report.differences.push(DiffStepJson {
+                step,
+                step_index: input.step_index,
+                a: a.json(),
+                b: b.json(),
+            });
+        }
+    }
+    report.differing_steps = report.differences.len();
+    report.compute_units = DiffUnitsJson {
+        a: stats(&units[0]),
+        b: stats(&units[1]),
+        delta_total: deltas.iter().sum(),
+        delta_min: deltas.iter().copied().min().unwrap_or(0),
+        delta_max: deltas.iter().copied().max().unwrap_or(0),
+    };
+    report.passed = report.differences.is_empty();
+    Ok(report)
+}
+
+/// Evaluates one input like a serve-mode eval: inputs newer than the
+/// program's declared version are downgraded, invalid outputs become HOLDs,
+/// sandbox violations are HOLDs with err code 13 and other failures `ERR`.
+async fn decide(
+    context: &mut ProgramTestContext,
+    program: &ProgramInfo,
+    compute_unit_limit: u32,
+    tape: &WindowTape,
+    input: &EvalInputV1,
+) -> Result<Decision> {
+    let mut input = input.clone();
+    if input.version > program.input_version {
+        input.version = program.input_version;
+        input.depth = None;
+    }
+    if let Some(len) = program.lookback_len {
+        input.ohlcv = tape.lookback(input.step_index, len);
+        input.lookback_len = input.ohlcv.len() as u16;
+    }
+    let input_bytes = input.to_bytes()?;
+    let invocation = invoke_program_metered(
+        context,
+        program.id,
+        compute_unit_limit,
+        &input_bytes,
+        None,
+        &[],
+    )
+    .await;
+    let (data, compute_units) = match invocation {
+        Ok(invocation) => (invocation.data, invocation.compute_units),
+        Err(err) => {
+            let failure = Failure::of(&err);
+            return Ok(Decision {
+                status: failure.status,
+                output: failure.hold(),
+                reason: Some(err.to_string()),
+                compute_units: None,
+            });
+        }
+    };
+    let evaluated = evaluate_output(&data, program.output_version, input.position_qty)?;
+    Ok(Decision {
+        status: status::OK,
+        output: evaluated.output,
+        reason: evaluated.validation_reason.map(str::to_string),
+        compute_units: Some(compute_units),
+    })
+}
+
+/// Every step of the tape, each seeing up to `lookback_len` bars.
+fn tape_inputs(path: &Path, lookback_len: Option<usize>) -> Result<Vec<EvalInputV1>> {
+    let tape = load_tape(path)?;
+    let lookback_len = lookback_len.unwrap_or(DEFAULT_LOOKBACK_LEN).max(1);
+    Ok((0..tape.bars.len())
+        .map(|step| tape.flat_input(step, lookback_len.min(step + 1)))
+        .collect())
+}
+
+/// The `input` of every `eval` request in a recorded serve-protocol
+/// transcript, in order; other requests are skipped.
+fn transcript_inputs(path: &Path) -> Result<Vec<EvalInputV1>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read transcript {}", path.display()))?;
+    let mut inputs = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = serde_json::from_str(line)
+            .with_context(|| format!("invalid request on line {}", index + 1))?;
+        if let Request::Eval(eval) = request {
+            inputs.push(
+                convert_input(eval.input)
+                    .with_context(|| format!("invalid input on line {}", index + 1))?,
+            );
+        }
+    }
+    Ok(inputs)
+}
+
+/// `BUY`, `SELL_NOTIONAL`, ...; reduce-only orders get a `_REDUCE_ONLY`
+/// suffix and unknown codes are `UNKNOWN_<code>`.
+fn action_name(action_type: u8) -> String {
+    let name = match action_type & !ACTION_FLAG_REDUCE_ONLY {
+        ACTION_HOLD => "HOLD",
+        ACTION_BUY => "BUY",
+        ACTION_SELL => "SELL",
+        ACTION_CLOSE => "CLOSE",
+        ACTION_TARGET_POSITION => "TARGET_POSITION",
+        ACTION_BUY_NOTIONAL => "BUY_NOTIONAL",
+        ACTION_SELL_NOTIONAL => "SELL_NOTIONAL",
+        _ => return format!("UNKNOWN_{action_type}"),
+    };
+    if action_type & ACTION_FLAG_REDUCE_ONLY != 0 {
+        format!("{name}_REDUCE_ONLY")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_names_cover_flags_and_unknown_codes() {
+        assert_eq!(action_name(ACTION_HOLD), "HOLD");
+        assert_eq!(
+            action_name(ACTION_SELL | ACTION_FLAG_REDUCE_ONLY),
+            "SELL_REDUCE_ONLY"
+        );
+        assert_eq!(action_name(42), "UNKNOWN_42");
+    }
+
+    #[test]
+    fn transcripts_yield_eval_inputs_in_order() {
+        let path = std::env::temp_dir().join(format!(
+            "solclash-diff-transcript-{}.jsonl",
+            std::process::id()
+        ));
+        let eval = |request_id: u64, step: u32| {
+            serde_json::json!({
+                "type": "eval", "request_id": request_id, "agent_id": "x",
+                "input": {
+                    "version": 1, "window_id": "w0", "step_index": step,
+                    "bar_interval_seconds": 60, "price_scale": 1, "volume_scale": 1,
+                    "cash_balance": "10000", "position_qty": "0", "avg_entry_price": "0",
+                    "max_leverage_bps": 10000, "initial_margin_bps": 1000,
+                    "maintenance_margin_bps": 500, "lookback_len": 0, "ohlcv": []
+                }
+            })
+            .to_string()
+        };
+        let lines = [
+            r#"{"type":"init","request_id":1,"programs":[]}"#.to_string(),
+            eval(2, 7),
+            String::new(),
+            eval(3, 8),
+            r#"{"type":"shutdown","request_id":4}"#.to_string(),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let inputs = transcript_inputs(&path).unwrap();
+        let steps: Vec<u32> = inputs.iter().map(|i| i.step_index).collect();
+        assert_eq!(steps, [7, 8]);
+
+        std::fs::write(&path, "{\"type\":\"eval\"}").unwrap();
+        let err = transcript_inputs(&path).unwrap_err();
+        assert_eq!(err.to_string(), "invalid request on line 1");
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -6,4 +6,11 @@ pub enum HarnessError {
     ProgramNotFound(String),
     #[error("eval failed: {0}")]
     EvalFailed(String),
+    /// A client input broke a harness-side rule; reported as `INVALID_INPUT`.
+    #[error("invalid input: {field}: {reason}")]
+    InvalidInput { field: String, reason: String },
+    #[error("compute budget exceeded: {0} units")]
+    ComputeExceeded(u32),
+    #[error("sandbox violation: {0}")]
+    SandboxViolation(String),
 }
@@ -0,0 +1,180 @@
+use crate::abi::{
+    Bar, FeatureValue, FeaturesV1, FEATURES_VERSION, FEATURE_ATR, FEATURE_EMA,
+    FEATURE_REALIZED_VOL, FEATURE_SMA,
+};
+use crate::protocol::{FeatureKindJson, FeatureSpecJson};
+
+/// Fixed-point scale for return-based features (parts per million).
+const RETURN_SCALE: i128 = 1_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeatureSpec {
+    pub kind: u8,
+    pub period: u16,
+}
+
+impl FeatureSpec {
+    pub fn from_json(spec: &FeatureSpecJson) -> Self {
+        let kind = match spec.kind {
+            FeatureKindJson::Sma => FEATURE_SMA,
+            FeatureKindJson::Ema => FEATURE_EMA,
+            FeatureKindJson::Atr => FEATURE_ATR,
+            FeatureKindJson::RealizedVol => FEATURE_REALIZED_VOL,
+        };
+        Self {
+            kind,
+            period: spec.period,
+        }
+    }
+
+    /// Bars the feature reads, ending at the current step.
+    pub fn history(&self) -> u16 {
+        match self.kind {
+            FEATURE_EMA => self.period.saturating_mul(2),
+            FEATURE_ATR | FEATURE_REALIZED_VOL => self.period.saturating_add(1),
+            _ => self.period,
+        }
+    }
+
+    fn compute(&self, bars: &[Bar]) -> Option<i64> {
+        let period = usize::from(self.period);
+        if period == 0 {
+            return None;
+        }
+        let history = usize::from(self.history());
+        let bars = &bars[bars.len().saturating_sub(history)..];
+        match self.kind {
+            FEATURE_SMA => sma(bars, period),
+            FEATURE_EMA => ema(bars, period),
+            FEATURE_ATR => atr(bars, period),
+            FEATURE_REALIZED_VOL => realized_vol(bars, period),
+            _ => None,
+        }
+    }
+}
+
+/// Computes every configured feature over `bars` (oldest first, ending at
+/// `step_index`). Features without enough history are `None`.
+pub fn compute_features(specs: &[FeatureSpec], step_index: u32, bars: &[Bar]) -> FeaturesV1 {
+    FeaturesV1 {
+        version: FEATURES_VERSION,
+        step_index,
+        values: specs
+            .iter()
+            .map(|spec| FeatureValue {
+                kind: spec.kind,
+                period: spec.period,
+                value: spec.compute(bars),
+            })
+            .collect(),
+    }
+}
+
+fn sma(bars: &[Bar], period: usize) -> Option<i64> {
+    if bars.len() < period {
+        return None;
+    }
+    let sum: i128 = bars[bars.len() - period..]
+        .iter()
+        .map(|bar| i128::from(bar.close))
+        .sum();
+    i64::try_from(sum / period as i128).ok()
+}
+
+/// EMA with `alpha = 2 / (period + 1)`, seeded with the SMA of the oldest
+/// `period` bars available (up to `2 * period` bars back).
+fn ema(bars: &[Bar], period: usize) -> Option<i64> {
+    if bars.len() < period {
+        return None;
+    }
+    let mut ema = i128::from(sma(&bars[..period], period)?);
+    for bar in &bars[period..] {
+        ema += (i128::from(bar.close) - ema) * 2 / (period as i128 + 1);
+    }
+    i64::try_from(ema).ok()
+}
+
+fn atr(bars: &[Bar], period: usize) -> Option<i64> {
+    if bars.len() < period + 1 {
+        return None;
+    }
+    let sum: i128 = bars
+        .windows(2)
+        .map(|pair| {
+            let prev_close = i128::from(pair[0].close);
+            let high = i128::from(pair[1].high);
+            let low = i128::from(pair[1].low);
+            (high - low)
+                .max((high - prev_close).abs())
+                .max((low - prev_close).abs())
+        })
+        .sum();
+    i64::try_from(sum / period as i128).ok()
+}
+
+/// Population standard deviation of close-to-close returns, in ppm.
+fn realized_vol(bars: &[Bar], period: usize) -> Option<i64> {
+    if bars.len() < period + 1 {
+        return None;
+    }
+    let mut returns = Vec::with_capacity(period);
+    for pair in bars.windows(2) {
+        let prev = i128::from(pair[0].close);
+        if prev <= 0 {
+            return None;
+        }
+        returns.push((i128::from(pair[1].close) - prev) * RETURN_SCALE / prev);
+    }
+    let n = returns.len() as i128;
+    let mean = returns.iter().sum::<i128>() / n;
+    let variance = returns
+        .iter()
+        .map(|r| (r - mean) * (r - mean))
+        .sum::<i128>()
+        / n;
+    i64::try_from(isqrt(variance as u128)).ok()
+}
+
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(json: &str) -> FeatureSpec {
+        FeatureSpec::from_json(&serde_json::from_str(json).expect("feature spec"))
+    }
+
+    #[test]
+    fn computed_from_tape_bars() {
+        let bar = |close: i64| Bar {
+            open: close,
+            high: close + 2,
+            low: close - 2,
+            close,
+            volume: 1,
+        };
+        let bars: Vec<Bar> = [100, 102, 104, 106].into_iter().map(bar).collect();
+        let specs = [
+            spec(r#"{"kind":"sma","period":2}"#),
+            spec(r#"{"kind":"atr","period":3}"#),
+            spec(r#"{"kind":"ema","period":8}"#),
+        ];
+        let features = compute_features(&specs, 3, &bars);
+        assert_eq!(features.values[0].value, Some(105));
+        // Each true range is 4: high - low equals high - previous close.
+        assert_eq!(features.values[1].value, Some(4));
+        assert_eq!(features.values[2].value, None);
+    }
+}
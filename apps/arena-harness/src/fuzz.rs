@@ -0,0 +1,520 @@
+//! `solclash-harness fuzz <path.so>`: evaluates a program on randomized
+//! inputs and reports every distinct failure (abort, compute exhaustion,
+//! sandbox violation, invalid output) with a minimized reproducing input.
+//!
+//! Most cases are schema-valid `EvalInputV1` values biased towards edge
+//! cases: extreme and zero prices, zero volume, `i64` limits on balances and
+//! positions, and lookbacks up to `--max-lookback`. The rest are mutated raw
+//! input bytes, which a conforming program answers with a HOLD.
+
+use crate::abi::fixtures::sample_input;
+use crate::abi::{
+    Bar, DepthLevel, DepthSnapshot, EvalInputV1, INPUT_VERSION_DEPTH, MAX_DEPTH_LEVELS,
+};
+use crate::artifact::DEFAULT_MAX_PROGRAM_BYTES;
+use crate::cli::FuzzOptions;
+use crate::error::HarnessError;
+use crate::rng::Rng;
+use crate::{
+    check_artifact, evaluate_output, invoke_program, negotiate_abi, start_program, ProgramInfo,
+};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use solana_program_test::ProgramTestContext;
+use std::collections::HashMap;
+
+const PROGRAM_NAME: &str = "solclash-fuzz";
+/// Program evals spent minimizing each distinct failure.
+const MAX_SHRINK_EVALS: u32 = 500;
+/// Offset of the `ohlcv` length prefix in the input encoding.
+const OHLCV_LEN_OFFSET: usize = 87;
+
+const PRICE_EDGES: &[i64] = &[0, 1, -1, i64::MAX, i64::MAX / 2, i64::MIN, i64::MIN + 1];
+const QTY_EDGES: &[i64] = &[0, 1, -1, i64::MAX, i64::MIN, i64::MIN + 1];
+const U32_EDGES: &[u32] = &[0, 1, 500, 10_000, 1_000_000, u32::MAX];
+
+type Failure = (FuzzFailureKind, String);
+
+enum Case {
+    Schema(EvalInputV1),
+    Mutated(Vec<u8>),
+}
+
+impl Case {
+    fn label(&self) -> &'static str {
+        match self {
+            Case::Schema(_) => "schema",
+            Case::Mutated(_) => "mutated",
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Case::Schema(input) => input.to_bytes().unwrap_or_default(),
+            Case::Mutated(bytes) => bytes.clone(),
+        }
+    }
+
+    /// The position the output rules check reduce-only orders against.
+    fn position_qty(&self) -> i64 {
+        match self {
+            Case::Schema(input) => input.position_qty,
+            Case::Mutated(bytes) => EvalInputV1::from_bytes(bytes)
+                .map(|input| input.position_qty)
+                .unwrap_or(0),
+        }
+    }
+
+    fn shrink_candidates(&self) -> Vec<Case> {
+        match self {
+            Case::Schema(input) => shrink_input(input).into_iter().map(Case::Schema).collect(),
+            Case::Mutated(bytes) => shrink_bytes(bytes).into_iter().map(Case::Mutated).collect(),
+        }
+    }
+}
+
+struct Fuzzer {
+    context: ProgramTestContext,
+    program: ProgramInfo,
+    compute_unit_limit: u32,
+}
+
+impl Fuzzer {
+    async fn check(&mut self, case: &Case) -> Option<Failure> {
+        let data = invoke_program(
+            &mut self.context,
+            self.program.id,
+            self.compute_unit_limit,
+            &case.bytes(),
+            None,
+            &[],
+        )
+        .await;
+        let data = match data {
+            Ok(data) => data,
+            Err(err) => {
+                let kind = match err.downcast_ref::<HarnessError>() {
+                    Some(HarnessError::SandboxViolation(_)) => FuzzFailureKind::SandboxViolation,
+                    Some(HarnessError::ComputeExceeded(_)) => FuzzFailureKind::ComputeExceeded,
+                    _ => FuzzFailureKind::Abort,
+                };
+                return Some((kind, err.to_string()));
+            }
+        };
+        match evaluate_output(&data, self.program.output_version, case.position_qty()) {
+            Ok(evaluated) => evaluated
+                .validation_reason
+                .map(|reason| (FuzzFailureKind::InvalidOutput, reason.to_string())),
+            Err(err) => Some((FuzzFailureKind::InvalidOutput, err.to_string())),
+        }
+    }
+
+    /// Greedily takes the first smaller candidate that fails the same way,
+    /// until none does or the eval budget runs out.
+    async fn minimize(&mut self, mut case: Case, failure: &Failure) -> Case {
+        let mut evals = 0;
+        'shrink: while evals < MAX_SHRINK_EVALS {
+            for candidate in case.shrink_candidates() {
+                if evals == MAX_SHRINK_EVALS {
+                    break 'shrink;
+                }
+                evals += 1;
+                if self.check(&candidate).await.as_ref() == Some(failure) {
+                    case = candidate;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        case
+    }
+}
+
+/// Report printed by `solclash-harness fuzz <path.so>`.
+#[derive(Debug, Serialize)]
+pub struct FuzzReport {
+    pub program: String,
+    pub seed: u64,
+    pub iterations: u32,
+    pub passed: bool,
+    /// Artifact validation failure; the program was not loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<String>,
+    pub input_version: u8,
+    pub output_version: u8,
+    /// One entry per distinct failure, in order of first occurrence.
+    pub findings: Vec<FuzzFindingJson>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FuzzFindingJson {
+    pub kind: FuzzFailureKind,
+    pub detail: String,
+    /// Iterations that failed the same way.
+    pub count: u32,
+    pub first_iteration: u32,
+    /// `schema` for generated `EvalInputV1` values, `mutated` for raw bytes.
+    pub input: String,
+    /// Minimized input account bytes that reproduce the failure.
+    pub repro_path: String,
+    pub repro_len: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzFailureKind {
+    /// The transaction failed, e.g. the program panicked or overflowed.
+    Abort,
+    ComputeExceeded,
+    SandboxViolation,
+    /// The program returned `Ok` with an output that breaks the ABI rules.
+    InvalidOutput,
+}
+
+impl FuzzFailureKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FuzzFailureKind::Abort => "abort",
+            FuzzFailureKind::ComputeExceeded => "compute_exceeded",
+            FuzzFailureKind::SandboxViolation => "sandbox_violation",
+            FuzzFailureKind::InvalidOutput => "invalid_output",
+        }
+    }
+}
+
+pub async fn run(options: &FuzzOptions) -> Result<FuzzReport> {
+    let mut report = FuzzReport {
+        program: options.so_path.display().to_string(),
+        seed: options.seed,
+        iterations: options.iterations,
+        passed: false,
+        rejected: None,
+        input_version: 1,
+        output_version: 1,
+        findings: Vec::new(),
+    };
+    if let Err(reason) = check_artifact(&options.so_path, DEFAULT_MAX_PROGRAM_BYTES) {
+        report.rejected = Some(reason);
+        return Ok(report);
+    }
+    let (mut context, program_id) = start_program(&options.so_path, PROGRAM_NAME).await?;
+    let mut program = ProgramInfo {
+        id: program_id,
+        input_version: 1,
+        output_version: 1,
+        lookback_len: None,
+        so_path: Some(options.so_path.clone()),
+    };
+    negotiate_abi(&mut context, &mut program, options.compute_unit_limit)
+        .await
+        .ok_or_else(|| anyhow!("no common ABI version for program"))?;
+    report.input_version = program.input_version;
+    report.output_version = program.output_version;
+
+    let mut fuzzer = Fuzzer {
+        context,
+        program,
+        compute_unit_limit: options.compute_unit_limit,
+    };
+    let mut rng = Rng::new(options.seed);
+    let mut seen: HashMap<Failure, usize> = HashMap::new();
+    for iteration in 0..options.iterations {
+        let input = gen_input(&mut rng, report.input_version, options.max_lookback);
+        let case = if rng.one_in(4) {
+            Case::Mutated(mutate(&mut rng, input.to_bytes()?))
+        } else {
+            Case::Schema(input)
+        };
+        let Some(failure) = fuzzer.check(&case).await else {
+            continue;
+        };
+        if let Some(&index) = seen.get(&failure) {
+            report.findings[index].count += 1;
+            continue;
+        }
+
+        let input = case.label();
+        let repro = fuzzer.minimize(case, &failure).await.bytes();
+        std::fs::create_dir_all(&options.out_dir)?;
+        let repro_path = options
+            .out_dir
+            .join(format!("{}-{iteration}.bin", failure.0.as_str()));
+        std::fs::write(&repro_path, &repro)?;
+
+        seen.insert(failure.clone(), report.findings.len());
+        report.findings.push(FuzzFindingJson {
+            kind: failure.0,
+            detail: failure.1,
+            count: 1,
+            first_iteration: iteration,
+            input: input.to_string(),
+            repro_path: repro_path.display().to_string(),
+            repro_len: repro.len(),
+        });
+    }
+    report.passed = report.findings.is_empty();
+    Ok(report)
+}
+
+/// A schema-valid input at `version`: `lookback_len` matches `ohlcv`, and
+/// depth only appears from version 2 on.
+fn gen_input(rng: &mut Rng, version: u8, max_lookback: u16) -> EvalInputV1 {
+    let base = edge_or(rng, &[1, 100, i64::MAX / 2], 50_000_000_000);
+    let extreme = rng.one_in(3);
+    let lookback = match rng.below(6) {
+        0 => 0,
+        1 => max_lookback,
+        2 => max_lookback.min(1),
+        _ => rng.below(max_lookback.min(64) as u64 + 1) as u16,
+    };
+    let ohlcv: Vec<Bar> = (0..lookback).map(|_| gen_bar(rng, base, extreme)).collect();
+
+    let mut window_id = [0u8; 32];
+    window_id
+        .iter_mut()
+        .for_each(|byte| *byte = rng.next_u64() as u8);
+    let depth = (version >= INPUT_VERSION_DEPTH && !rng.one_in(4)).then(|| DepthSnapshot {
+        bids: gen_levels(rng, base),
+        asks: gen_levels(rng, base),
+    });
+    let cash = rng_range(rng, 0, 10_000_000_000_000);
+    let position = rng_range(rng, -1_000_000_000, 1_000_000_000);
+    EvalInputV1 {
+        version,
+        window_id,
+        step_index: rng.next_u64() as u32,
+        bar_interval_seconds: rng.pick(&[1, 60, 3_600, 86_400, u32::MAX]),
+        price_scale: rng.pick(&[1, 1_000_000, 100_000_000, u32::MAX]),
+        volume_scale: rng.pick(&[1, 1_000_000, 100_000_000, u32::MAX]),
+        cash_balance: edge_or(rng, QTY_EDGES, cash),
+        position_qty: edge_or(rng, QTY_EDGES, position),
+        avg_entry_price: edge_or(rng, PRICE_EDGES, base),
+        max_leverage_bps: rng.pick(U32_EDGES),
+        initial_margin_bps: rng.pick(U32_EDGES),
+        maintenance_margin_bps: rng.pick(U32_EDGES),
+        lookback_len: ohlcv.len() as u16,
+        ohlcv,
+        depth,
+        features: None,
+    }
+}
+
+fn gen_bar(rng: &mut Rng, base: i64, extreme: bool) -> Bar {
+    let price = |rng: &mut Rng| {
+        if extreme && rng.one_in(3) {
+            rng.pick(PRICE_EDGES)
+        } else {
+            base.saturating_add(rng_range(rng, -base / 50, base / 50))
+        }
+    };
+    let (open, close) = (price(rng), price(rng));
+    let (high, low) = if extreme {
+        (price(rng), price(rng))
+    } else {
+        (
+            open.max(close)
+                .saturating_add(rng_range(rng, 0, base / 100)),
+            open.min(close)
+                .saturating_sub(rng_range(rng, 0, base / 100)),
+        )
+    };
+    let volume = match rng.below(4) {
+        0 => 0,
+        1 if extreme => i64::MAX,
+        _ => rng_range(rng, 0, 1_000_000_000_000),
+    };
+    Bar {
+        open,
+        high,
+        low,
+        close,
+        volume,
+    }
+}
+
+fn gen_levels(rng: &mut Rng, base: i64) -> Vec<DepthLevel> {
+    (0..rng.below(MAX_DEPTH_LEVELS as u64 + 1))
+        .map(|_| {
+            let size = rng_range(rng, 0, 1_000_000_000);
+            DepthLevel {
+                price: edge_or(rng, PRICE_EDGES, base),
+                size: edge_or(rng, &[0, i64::MAX], size),
+            }
+        })
+        .collect()
+}
+
+/// One of `edges` a third of the time, otherwise `normal`.
+fn edge_or(rng: &mut Rng, edges: &[i64], normal: i64) -> i64 {
+    if rng.one_in(3) {
+        rng.pick(edges)
+    } else {
+        normal
+    }
+}
+
+fn rng_range(rng: &mut Rng, lo: i64, hi: i64) -> i64 {
+    rng.range_i64(lo.min(hi), hi.max(lo))
+}
+
+/// Applies one to four byte-level mutations to an encoded input.
+fn mutate(rng: &mut Rng, mut bytes: Vec<u8>) -> Vec<u8> {
+    for _ in 0..=rng.below(4) {
+        let len = bytes.len() as u64;
+        match rng.below(5) {
+            0 if len > 0 => bytes[rng.below(len) as usize] ^= 1 << rng.below(8),
+            1 if len > 0 => {
+                let random = rng.next_u64() as u8;
+                let value = rng.pick(&[0, 0x7f, 0x80, 0xff, random]);
+                bytes[rng.below(len) as usize] = value;
+            }
+            2 => bytes.truncate(rng.below(len + 1) as usize),
+            3 => bytes.extend((0..=rng.below(16)).map(|_| rng.next_u64() as u8)),
+            _ if bytes.len() >= OHLCV_LEN_OFFSET + 4 => {
+                let bars = (len.saturating_sub(OHLCV_LEN_OFFSET as u64 + 4) / 40) as u32;
+                let random = rng.next_u64() as u32;
+                let claimed = rng.pick(&[0, bars + 1, u32::MAX, random]);
+                bytes[OHLCV_LEN_OFFSET..OHLCV_LEN_OFFSET + 4]
+                    .copy_from_slice(&claimed.to_le_bytes());
+            }
+            _ => {}
+        }
+    }
+    bytes
+}
+
+/// Smaller or plainer variants of a schema input, largest reductions first:
+/// fewer bars, no depth, then single fields reset to the sample input's.
+fn shrink_input(input: &EvalInputV1) -> Vec<EvalInputV1> {
+    let mut out = Vec::new();
+    let n = input.ohlcv.len();
+    let with_bars = |bars: &[Bar]| {
+        let mut candidate = input.clone();
+        candidate.ohlcv = bars.to_vec();
+        candidate.lookback_len = bars.len() as u16;
+        candidate
+    };
+    if n > 0 {
+        out.push(with_bars(&input.ohlcv[..n / 2]));
+        if n > 1 {
+            out.push(with_bars(&input.ohlcv[n / 2..]));
+            out.push(with_bars(&input.ohlcv[1..]));
+            out.push(with_bars(&input.ohlcv[..n - 1]));
+        }
+    }
+    if input.depth.is_some() {
+        let mut candidate = input.clone();
+        candidate.depth = None;
+        out.push(candidate);
+    }
+
+    let plain = sample_input(input.version);
+    macro_rules! reset {
+        ($($field:ident),*) => {$(
+            if input.$field != plain.$field {
+                let mut candidate = input.clone();
+                candidate.$field = plain.$field;
+                out.push(candidate);
+            }
+        )*};
+    }
+    reset!(
+        window_id,
+        step_index,
+        bar_interval_seconds,
+        price_scale,
+        volume_scale,
+        cash_balance,
+        position_qty,
+        avg_entry_price,
+        max_leverage_bps,
+        initial_margin_bps,
+        maintenance_margin_bps
+    );
+    if n <= 16 {
+        for (i, bar) in input.ohlcv.iter().enumerate() {
+            if *bar != plain.ohlcv[0] {
+                let mut candidate = input.clone();
+                candidate.ohlcv[i] = plain.ohlcv[0].clone();
+                out.push(candidate);
+            }
+        }
+    }
+    out
+}
+
+/// Copies of `bytes` with one chunk removed, halving the chunk size down to
+/// single bytes.
+fn shrink_bytes(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut chunk = bytes.len() / 2;
+    while chunk > 0 {
+        for start in (0..bytes.len()).step_by(chunk) {
+            let mut candidate = bytes.to_vec();
+            candidate.drain(start..(start + chunk).min(bytes.len()));
+            out.push(candidate);
+        }
+        chunk /= 2;
+    }
+    if bytes.len() == 1 {
+        out.push(Vec::new());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_inputs_are_schema_valid() {
+        let mut rng = Rng::new(42);
+        for version in [1, INPUT_VERSION_DEPTH] {
+            for _ in 0..200 {
+                let input = gen_input(&mut rng, version, 300);
+                assert_eq!(input.lookback_len as usize, input.ohlcv.len());
+                assert!(input.ohlcv.len() <= 300);
+                let bytes = input.to_bytes().expect("encode");
+                assert_eq!(EvalInputV1::from_bytes(&bytes).expect("decode"), input);
+                assert_eq!(
+                    bytes[OHLCV_LEN_OFFSET..OHLCV_LEN_OFFSET + 4],
+                    (input.ohlcv.len() as u32).to_le_bytes()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn sbf_programs_stuck_in_a_loop_are_compute_exceeded() {
+        let dir = std::env::temp_dir().join(format!("solclash-fuzz-loop-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let options = FuzzOptions {
+            so_path: crate::sbf_fixtures::looping_program(&dir, None),
+            compute_unit_limit: 20_000,
+            iterations: 3,
+            seed: 1,
+            out_dir: dir.join("repro"),
+            max_lookback: 8,
+        };
+        let report = run(&options).await.expect("fuzz");
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].kind.as_str(), "compute_exceeded");
+        assert_eq!(report.findings[0].count, 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn shrink_candidates_stay_valid_and_get_smaller() {
+        let mut rng = Rng::new(7);
+        let input = gen_input(&mut rng, INPUT_VERSION_DEPTH, 64);
+        for candidate in shrink_input(&input) {
+            assert_eq!(candidate.lookback_len as usize, candidate.ohlcv.len());
+            assert!(candidate.ohlcv.len() <= input.ohlcv.len());
+            assert_ne!(candidate, input);
+        }
+        let bytes = input.to_bytes().expect("encode");
+        assert!(shrink_bytes(&bytes)
+            .iter()
+            .all(|candidate| candidate.len() < bytes.len()));
+    }
+}
@@ -0,0 +1,206 @@
+//! Harness-side checks on client eval inputs, applied before any account is
+//! built so a client bug surfaces as `INVALID_INPUT` on the offending field
+//! rather than as a HOLD from the program. Bars follow the data-ops
+//! integrity rules (data-ops spec §1.3).
+
+use crate::abi::INPUT_VERSION_MAX;
+use crate::error::HarnessError;
+use crate::protocol::EvalInputJson;
+
+/// Longest request line the serve loop reads; longer lines are rejected
+/// unparsed.
+pub const MAX_REQUEST_LINE_BYTES: usize = 4 << 20;
+/// Most bars one input may carry.
+pub const MAX_OHLCV_BARS: usize = 8192;
+
+/// The first rule `input` breaks, as an `InvalidInput` error.
+pub fn check(input: &EvalInputJson) -> Result<(), HarnessError> {
+    let invalid = |field: &str, reason: String| {
+        Err(HarnessError::InvalidInput {
+            field: field.to_string(),
+            reason,
+        })
+    };
+    if input.version == 0 || input.version > INPUT_VERSION_MAX {
+        return invalid(
+            "version",
+            format!("must be between 1 and {INPUT_VERSION_MAX}"),
+        );
+    }
+    for (field, value) in [
+        ("bar_interval_seconds", input.bar_interval_seconds),
+        ("price_scale", input.price_scale),
+        ("volume_scale", input.volume_scale),
+    ] {
+        if value == 0 {
+            return invalid(field, "must be > 0".to_string());
+        }
+    }
+    check_account(input.initial_margin_bps, input.maintenance_margin_bps)?;
+    if input.ohlcv.len() > MAX_OHLCV_BARS {
+        return invalid("ohlcv", format!("more than {MAX_OHLCV_BARS} bars"));
+    }
+    if usize::from(input.lookback_len) != input.ohlcv.len() {
+        return invalid(
+            "lookback_len",
+            format!("must equal ohlcv length {}", input.ohlcv.len()),
+        );
+    }
+    for (index, bar) in input.ohlcv.iter().enumerate() {
+        let rule = |field: &str, reason: &str| {
+            invalid(&format!("ohlcv[{index}].{field}"), reason.to_string())
+        };
+        for (field, value) in [
+            ("open", bar.open),
+            ("high", bar.high),
+            ("low", bar.low),
+            ("close", bar.close),
+        ] {
+            if value <= 0 {
+                return rule(field, "must be > 0");
+            }
+        }
+        if bar.volume < 0 {
+            return rule("volume", "must be >= 0");
+        }
+        if bar.low > bar.open.min(bar.close) {
+            return rule("low", "must be <= open and close");
+        }
+        if bar.high < bar.open.max(bar.close) {
+            return rule("high", "must be >= open and close");
+        }
+    }
+    if let Some(depth) = &input.depth {
+        for (side, levels) in [("bids", &depth.bids), ("asks", &depth.asks)] {
+            for (index, level) in levels.iter().enumerate() {
+                let field = |name: &str| format!("depth.{side}[{index}].{name}");
+                if level.price <= 0 {
+                    return invalid(&field("price"), "must be > 0".to_string());
+                }
+                if level.size < 0 {
+                    return invalid(&field("size"), "must be >= 0".to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The first rule an account's margins break; sweeps check each grid cell
+/// with it, since a cell can combine valid axis values into an invalid one.
+pub fn check_account(
+    initial_margin_bps: u32,
+    maintenance_margin_bps: u32,
+) -> Result<(), HarnessError> {
+    if maintenance_margin_bps > initial_margin_bps {
+        return Err(HarnessError::InvalidInput {
+            field: "maintenance_margin_bps".to_string(),
+            reason: "must be <= initial_margin_bps".to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    /// The shared fixture at step 1 with a second, zero-volume bar.
+    fn input(edit: impl FnOnce(&mut Value)) -> EvalInputJson {
+        let mut input = crate::test_support::eval_input();
+        input["step_index"] = json!(1);
+        input["lookback_len"] = json!(2);
+        input["ohlcv"].as_array_mut().expect("ohlcv").push(
+            json!({ "open": "100", "high": "102", "low": "98", "close": "101", "volume": "0" }),
+        );
+        edit(&mut input);
+        serde_json::from_value(input).expect("input json")
+    }
+
+    fn rejected(edit: impl FnOnce(&mut Value)) -> (String, String) {
+        match check(&input(edit)) {
+            Err(HarnessError::InvalidInput { field, reason }) => (field, reason),
+            other => panic!("expected invalid input, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_consistent_inputs() {
+        assert!(check(&input(|_| {})).is_ok());
+        assert!(check(&input(|i| {
+            i["lookback_len"] = json!(0);
+            i["ohlcv"] = json!([]);
+        }))
+        .is_ok());
+    }
+
+    #[test]
+    fn names_the_offending_field() {
+        let cases = [
+            ("/version", json!(0), "version", "must be between 1 and 2"),
+            ("/price_scale", json!(0), "price_scale", "must be > 0"),
+            (
+                "/maintenance_margin_bps",
+                json!(1500),
+                "maintenance_margin_bps",
+                "must be <= initial_margin_bps",
+            ),
+            (
+                "/lookback_len",
+                json!(5),
+                "lookback_len",
+                "must equal ohlcv length 2",
+            ),
+            ("/ohlcv/1/low", json!("0"), "ohlcv[1].low", "must be > 0"),
+            (
+                "/ohlcv/0/high",
+                json!("98"),
+                "ohlcv[0].high",
+                "must be >= open and close",
+            ),
+            (
+                "/ohlcv/1/volume",
+                json!("-1"),
+                "ohlcv[1].volume",
+                "must be >= 0",
+            ),
+        ];
+        for (pointer, value, field, reason) in cases {
+            let edit = |i: &mut Value| *i.pointer_mut(pointer).expect("field") = value;
+            assert_eq!(rejected(edit), (field.to_string(), reason.to_string()));
+        }
+    }
+
+    #[test]
+    fn checks_depth_levels() {
+        let depth = |bid: &'static str, ask_size: &'static str| {
+            move |i: &mut Value| {
+                i["version"] = json!(2);
+                i["depth"] = json!({
+                    "bids": [{ "price": "99", "size": "1" }, { "price": bid, "size": "2" }],
+                    "asks": [{ "price": "101", "size": ask_size }]
+                });
+            }
+        };
+        assert!(check(&input(depth("98", "0"))).is_ok());
+        assert_eq!(
+            rejected(depth("0", "1")),
+            ("depth.bids[1].price".to_string(), "must be > 0".to_string())
+        );
+        assert_eq!(
+            rejected(depth("98", "-1")),
+            ("depth.asks[0].size".to_string(), "must be >= 0".to_string())
+        );
+    }
+
+    #[test]
+    fn caps_bar_count() {
+        let bar = json!({ "open": "1", "high": "1", "low": "1", "close": "1", "volume": "1" });
+        let (field, _) = rejected(|i| {
+            i["lookback_len"] = json!(MAX_OHLCV_BARS + 1);
+            i["ohlcv"] = Value::Array(vec![bar; MAX_OHLCV_BARS + 1]);
+        });
+        assert_eq!(field, "ohlcv");
+    }
+}
@@ -0,0 +1,175 @@
+//! Diagnostic tracing, kept off stdout. Spans cover serve requests,
+//! `init_programs` and `handle_eval`, so a stalled harness shows whether it
+//! is reading, executing or writing a response. Flags override the
+//! `SOLCLASH_LOG*` environment variables.
+
+use anyhow::{anyhow, Context, Result};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_FILTER: &str = "warn";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!(
+                "unknown log format: {value} (expected human or json)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl FromStr for LogRotation {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "minutely" => Ok(Self::Minutely),
+            "hourly" => Ok(Self::Hourly),
+            "daily" => Ok(Self::Daily),
+            "never" => Ok(Self::Never),
+            _ => Err(anyhow!(
+                "unknown log rotation: {value} (expected minutely, hourly, daily or never)"
+            )),
+        }
+    }
+}
+
+/// `--log`, `--log-format`, `--log-file` and `--log-rotation`.
+#[derive(Debug, Default)]
+pub struct LogOptions {
+    /// `EnvFilter` directives, e.g. `debug` or `solclash_harness=trace`.
+    pub filter: Option<String>,
+    pub format: Option<LogFormat>,
+    /// Written instead of stderr; rotated files get a date suffix.
+    pub file: Option<PathBuf>,
+    pub rotation: Option<LogRotation>,
+}
+
+impl LogOptions {
+    /// Fills unset options from `SOLCLASH_LOG`, `SOLCLASH_LOG_FORMAT`,
+    /// `SOLCLASH_LOG_FILE` and `SOLCLASH_LOG_ROTATION`.
+    fn with_env(self, var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        Ok(Self {
+            filter: self.filter.or_else(|| var("SOLCLASH_LOG")),
+            format: or_env(self.format, var("SOLCLASH_LOG_FORMAT"))?,
+            file: self
+                .file
+                .or_else(|| var("SOLCLASH_LOG_FILE").map(PathBuf::from)),
+            rotation: or_env(self.rotation, var("SOLCLASH_LOG_ROTATION"))?,
+        })
+    }
+}
+
+fn or_env<T: FromStr<Err = anyhow::Error>>(
+    flag: Option<T>,
+    env: Option<String>,
+) -> Result<Option<T>> {
+    match flag {
+        Some(value) => Ok(Some(value)),
+        None => env.map(|value| value.parse()).transpose(),
+    }
+}
+
+/// Installs the global subscriber: human-readable or JSON lines, on stderr
+/// or in a file rotated daily unless set otherwise, at `warn` by default.
+pub fn init(options: LogOptions) -> Result<()> {
+    let options = options.with_env(|name| std::env::var(name).ok())?;
+    let filter = options.filter.as_deref().unwrap_or(DEFAULT_FILTER);
+    let filter =
+        EnvFilter::try_new(filter).with_context(|| format!("invalid log filter: {filter}"))?;
+    let (writer, ansi) = match &options.file {
+        Some(path) => {
+            let rotation = options.rotation.unwrap_or(LogRotation::Daily);
+            (BoxMakeWriter::new(rolling_file(path, rotation)?), false)
+        }
+        None => (
+            BoxMakeWriter::new(std::io::stderr),
+            std::io::stderr().is_terminal(),
+        ),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(ansi);
+    let installed = match options.format.unwrap_or(LogFormat::Human) {
+        LogFormat::Human => builder.try_init(),
+        LogFormat::Json => builder.json().with_current_span(true).try_init(),
+    };
+    installed.map_err(|err| anyhow!("cannot install logging: {err}"))
+}
+
+fn rolling_file(path: &Path, rotation: LogRotation) -> Result<RollingFileAppender> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("log file has no name: {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let rotation = match rotation {
+        LogRotation::Minutely => Rotation::MINUTELY,
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    RollingFileAppender::builder()
+        .rotation(rotation)
+        .filename_prefix(name.to_string_lossy())
+        .build(dir)
+        .with_context(|| format!("cannot open log file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_override_environment() {
+        let env = |name: &str| match name {
+            "SOLCLASH_LOG" => Some("debug".to_string()),
+            "SOLCLASH_LOG_FORMAT" => Some("json".to_string()),
+            "SOLCLASH_LOG_ROTATION" => Some("hourly".to_string()),
+            _ => None,
+        };
+        let options = LogOptions {
+            format: Some(LogFormat::Human),
+            ..LogOptions::default()
+        }
+        .with_env(env)
+        .unwrap();
+        assert_eq!(options.filter.as_deref(), Some("debug"));
+        assert_eq!(options.format, Some(LogFormat::Human));
+        assert_eq!(options.rotation, Some(LogRotation::Hourly));
+        assert_eq!(options.file, None);
+
+        let bad = |name: &str| (name == "SOLCLASH_LOG_FORMAT").then(|| "xml".to_string());
+        let err = LogOptions::default().with_env(bad).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown log format: xml (expected human or json)"
+        );
+    }
+}
@@ -1,182 +1,986 @@
 mod abi;
+mod accounting;
+mod artifact;
+mod backtest;
+mod baselines;
+mod bench;
+#[cfg(any(debug_assertions, feature = "test-programs"))]
+mod builtins;
+mod cli;
+mod config;
+mod conformance;
+mod diff;
 mod error;
+mod features;
+mod fuzz;
+mod input_rules;
+mod logging;
+mod policy_log;
+mod profile;
 mod protocol;
+mod replay;
+mod rng;
+mod sandbox;
+#[cfg(test)]
+mod sbf_fixtures;
+mod stats;
+mod status;
+mod sweep;
+mod synth;
+mod tape;
+#[cfg(test)]
+mod test_support;
 
-use abi::{EvalInputV1, EvalOutputV1, Bar, OUTPUT_LEN};
-use anyhow::{anyhow, Result};
+use abi::validation::{self, OutputFields};
+use abi::{
+    highest_common_version, output_len, AbiDeclaration, Bar, DepthLevel, DepthSnapshot,
+    EvalInputV1, EvalOutputV1, EvalOutputV2, EvalOutputV3, ABI_PROBE_IX, ERR_OUTPUT_SER_FAIL,
+    INPUT_VERSION_DEPTH, INPUT_VERSION_MAX, MAX_DEPTH_LEVELS, OUTPUT_V3_LEN,
+    OUTPUT_VERSION_ANNOTATED, OUTPUT_VERSION_MAX, OUTPUT_VERSION_ORDERS,
+};
+use anyhow::{anyhow, Context, Result};
+use artifact::{validate_artifact, DEFAULT_MAX_PROGRAM_BYTES};
 use borsh::BorshDeserialize;
+use cli::Command;
 use error::HarnessError;
+use features::{compute_features, FeatureSpec};
+use input_rules::MAX_REQUEST_LINE_BYTES;
+use logging::LogOptions;
+use policy_log::{LoggedEval, PolicyLog};
 use protocol::{
-    EvalInputJson, EvalOutputJson, Request, Response, ResultResponse,
+    AgentStatsJson, EvalInputJson, EvalOutputJson, ProfileJson, ProgramAbiJson,
+    ProgramRejectionJson, Request, Response, ResultResponse, SweepCellJson, SweepGridJson,
+    SweepResponse,
 };
 use sha2::{Digest, Sha256};
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program_test::{ProgramTest, ProgramTestContext};
 use solana_sdk::account::AccountSharedData;
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::keypair::read_keypair_file;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
-use std::collections::HashMap;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use stats::AgentStats;
+use status::Failure;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::Write;
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
-use tokio::io::{self, AsyncBufReadExt};
+use std::time::{Duration, Instant};
+use tape::WindowTape;
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+use tracing::Instrument;
 
 struct ProgramInfo {
     pub id: Pubkey,
+    pub input_version: u8,
+    pub output_version: u8,
+    /// Per-program `ohlcv` length; `None` passes the client's bars through.
+    pub lookback_len: Option<u16>,
+    /// The loaded artifact, for profiling; `None` for native programs.
+    pub so_path: Option<PathBuf>,
 }
 
 struct HarnessState {
     pub context: ProgramTestContext,
     pub programs: HashMap<String, ProgramInfo>,
     pub compute_unit_limit: u32,
+    pub tape: WindowTape,
+    pub features: Vec<FeatureSpec>,
+    /// Per loaded program, since `init`.
+    pub stats: BTreeMap<String, AgentStats>,
 }
 
+impl HarnessState {
+    fn stats_json(&self) -> BTreeMap<String, AgentStatsJson> {
+        self.stats
+            .iter()
+            .map(|(id, stats)| (id.clone(), stats.json()))
+            .collect()
+    }
+}
+
+/// Arena bounds applied to per-program lookback requests.
+#[derive(Clone, Copy)]
+struct LookbackBounds {
+    pub min: u16,
+    pub max: u16,
+}
+
+const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let stdin = io::BufReader::new(io::stdin());
-    let mut lines = stdin.lines();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut command = cli::parse(&args, DEFAULT_COMPUTE_UNIT_LIMIT)?;
+    logging::init(match &mut command {
+        Command::Serve(options) => std::mem::take(&mut options.log),
+        _ => LogOptions::default(),
+    })?;
+    let serve = match command {
+        Command::Serve(options) => options,
+        Command::Conformance(options) => {
+            let report = conformance::run(&options).await?;
+            return print_report(&report, report.passed);
+        }
+        Command::Fuzz(options) => {
+            let report = fuzz::run(&options).await?;
+            return print_report(&report, report.passed);
+        }
+        Command::Backtest(options) => {
+            let report = backtest::run(&options).await?;
+            return print_report(&report, report.passed);
+        }
+        Command::Bench(options) => {
+            let report = bench::run(&options).await?;
+            return print_report(&report, report.passed);
+        }
+        Command::Diff(options) => {
+            let report = diff::run(&options).await?;
+            return print_report(&report, report.passed);
+        }
+        Command::Profile(options) => {
+            let report = profile::run(&options)?;
+            return print_report(&report, report.passed);
+        }
+        Command::GenerateTape(options) => {
+            return match synth::run(&options)? {
+                Some(report) => print_report(&report, true),
+                None => Ok(()),
+            };
+        }
+    };
+
+    let mut policy_log = serve
+        .policy_log
+        .as_deref()
+        .map(PolicyLog::open)
+        .transpose()?;
+    let mut stdin = io::BufReader::new(io::stdin());
 
     let mut state: Option<HarnessState> = None;
 
-    while let Some(line) = lines.next_line().await? {
+    loop {
+        let line = match read_request_line(&mut stdin).await? {
+            RequestLine::Line(line) => line,
+            RequestLine::TooLong => {
+                tracing::warn!(limit = MAX_REQUEST_LINE_BYTES, "request line too long");
+                write_response(Response::Error(protocol::ErrorResponse {
+                    code: Some("INVALID_INPUT".to_string()),
+                    field: Some("line".to_string()),
+                    ..protocol::ErrorResponse::new(
+                        0,
+                        format!("request line exceeds {MAX_REQUEST_LINE_BYTES} bytes"),
+                    )
+                }))?;
+                continue;
+            }
+            RequestLine::Eof => break,
+        };
         if line.trim().is_empty() {
             continue;
         }
+        tracing::debug!(bytes = line.len(), "read request");
         let request: Request = match serde_json::from_str(&line) {
             Ok(req) => req,
             Err(err) => {
-                write_response(Response::Error(protocol::ErrorResponse {
-                    request_id: 0,
-                    message: format!("invalid request: {err}"),
-                }))?;
+                tracing::warn!(%err, "invalid request");
+                write_response(Response::Error(protocol::ErrorResponse::new(
+                    0,
+                    format!("invalid request: {err}"),
+                )))?;
                 continue;
             }
         };
 
-        match request {
-            Request::Init(req) => {
-                let compute_limit = req.compute_unit_limit.unwrap_or(200_000);
-                match init_programs(req.programs).await {
-                    Ok((context, programs)) => {
-                        state = Some(HarnessState {
-                            context,
-                            programs,
-                            compute_unit_limit: compute_limit,
-                        });
-                        write_response(Response::Ok(protocol::OkResponse {
-                            request_id: req.request_id,
-                        }))?;
-                    }
-                    Err(err) => {
-                        write_response(Response::Error(protocol::ErrorResponse {
-                            request_id: req.request_id,
-                            message: err.to_string(),
-                        }))?;
-                    }
+        let span = tracing::info_span!(
+            "request",
+            request_id = request.request_id(),
+            kind = request.kind()
+        );
+        let profile_dir = serve.profile_dir.as_deref();
+        if serve_request(request, &mut state, policy_log.as_mut(), profile_dir)
+            .instrument(span)
+            .await?
+            .is_break()
+        {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Answers one parsed request; breaks after `shutdown`.
+async fn serve_request(
+    request: Request,
+    state: &mut Option<HarnessState>,
+    policy_log: Option<&mut PolicyLog>,
+    profile_dir: Option<&Path>,
+) -> Result<ControlFlow<()>> {
+    match request {
+        Request::Init(req) => {
+            let compute_limit = req.compute_unit_limit.unwrap_or(DEFAULT_COMPUTE_UNIT_LIMIT);
+            let bounds = LookbackBounds {
+                min: req.min_lookback_len.unwrap_or(1),
+                max: req.max_lookback_len.unwrap_or(u16::MAX),
+            };
+            let max_program_bytes = req.max_program_bytes.unwrap_or(DEFAULT_MAX_PROGRAM_BYTES);
+            // Request order without repeats; later copies of an id are rejected.
+            let mut seen = HashSet::new();
+            let ids: Vec<String> = req
+                .programs
+                .iter()
+                .map(|p| p.id.clone())
+                .chain(req.baselines.iter().cloned())
+                .filter(|id| seen.insert(id.clone()))
+                .collect();
+            match init_programs(
+                req.programs,
+                &req.baselines,
+                compute_limit,
+                bounds,
+                max_program_bytes,
+            )
+            .await
+            {
+                Ok((context, programs, rejected)) => {
+                    let abi = ids
+                        .into_iter()
+                        .filter(|id| programs.contains_key(id))
+                        .map(|id| {
+                            let info = &programs[&id];
+                            ProgramAbiJson {
+                                input_version: info.input_version,
+                                output_version: info.output_version,
+                                lookback_len: info.lookback_len,
+                                id,
+                            }
+                        })
+                        .collect();
+                    let features: Vec<FeatureSpec> =
+                        req.features.iter().map(FeatureSpec::from_json).collect();
+                    let retain = programs
+                        .values()
+                        .filter_map(|p| p.lookback_len)
+                        .chain(features.iter().map(FeatureSpec::history))
+                        .max();
+                    let stats = programs
+                        .keys()
+                        .map(|id| (id.clone(), AgentStats::default()))
+                        .collect();
+                    *state = Some(HarnessState {
+                        context,
+                        programs,
+                        compute_unit_limit: compute_limit,
+                        tape: WindowTape::new(retain.unwrap_or(0)),
+                        features,
+                        stats,
+                    });
+                    write_response(Response::Ok(protocol::OkResponse {
+                        request_id: req.request_id,
+                        programs: Some(abi),
+                        rejected: (!rejected.is_empty()).then_some(rejected),
+                        agents: None,
+                    }))?;
+                }
+                Err(err) => {
+                    write_response(error_response(req.request_id, &err))?;
                 }
             }
-            Request::Eval(req) => {
-                let st = match state.as_mut() {
-                    Some(state) => state,
-                    None => {
-                        write_response(Response::Error(protocol::ErrorResponse {
-                            request_id: req.request_id,
-                            message: "not initialized".to_string(),
-                        }))?;
-                        continue;
-                    }
-                };
-                match handle_eval(
-                    &mut st.context,
-                    &st.programs,
-                    st.compute_unit_limit,
-                    &req.agent_id,
-                    req.input,
-                )
-                .await
-                {
-                    Ok(output) => {
-                        let response = Response::Result(ResultResponse {
+        }
+        Request::Eval(req) => {
+            let st = match state.as_mut() {
+                Some(state) => state,
+                None => {
+                    write_response(Response::Error(protocol::ErrorResponse::new(
+                        req.request_id,
+                        "not initialized".to_string(),
+                    )))?;
+                    return Ok(ControlFlow::Continue(()));
+                }
+            };
+            let profile = req
+                .profile
+                .as_deref()
+                .map(|path| profile::serve_out_path(profile_dir, path))
+                .transpose();
+            let evaluated = match profile {
+                Ok(profile) => {
+                    handle_eval(st, &req.agent_id, req.input, profile.as_deref(), policy_log).await
+                }
+                Err(err) => Err(anyhow!(err)),
+            };
+            match evaluated {
+                Ok((evaluated, profile)) => {
+                    let response = Response::Result(ResultResponse {
+                        request_id: req.request_id,
+                        agent_id: req.agent_id,
+                        status: status::OK.to_string(),
+                        output: output_json(&evaluated.output),
+                        reason: None,
+                        validation_reason: evaluated.validation_reason.map(str::to_string),
+                        profile: profile.map(Box::new),
+                    });
+                    write_response(response)?;
+                }
+                Err(err) => match err.downcast_ref::<HarnessError>() {
+                    Some(HarnessError::SandboxViolation(reason)) => {
+                        let failure = Failure::of(&err);
+                        write_response(Response::Result(ResultResponse {
                             request_id: req.request_id,
                             agent_id: req.agent_id,
-                            status: "OK".to_string(),
-                            output: EvalOutputJson {
-                                version: output.version,
-                                action_type: output.action_type,
-                                order_qty: output.order_qty,
-                                err_code: output.err_code,
-                            },
-                        });
-                        write_response(response)?;
-                    }
-                    Err(err) => {
-                        write_response(Response::Error(protocol::ErrorResponse {
-                            request_id: req.request_id,
-                            message: err.to_string(),
+                            status: failure.status.to_string(),
+                            output: output_json(&failure.hold()),
+                            reason: Some(reason.clone()),
+                            validation_reason: None,
+                            profile: None,
                         }))?;
                     }
-                }
+                    _ => {
+                        write_response(error_response(req.request_id, &err))?;
+                    }
+                },
             }
-            Request::Shutdown(req) => {
-                write_response(Response::Ok(protocol::OkResponse {
+        }
+        Request::Sweep(req) => {
+            let st = match state.as_mut() {
+                Some(state) => state,
+                None => {
+                    write_response(Response::Error(protocol::ErrorResponse::new(
+                        req.request_id,
+                        "not initialized".to_string(),
+                    )))?;
+                    return Ok(ControlFlow::Continue(()));
+                }
+            };
+            match handle_sweep(st, &req.agent_id, req.input, &req.grid, policy_log).await {
+                Ok(cells) => write_response(Response::SweepResult(SweepResponse {
                     request_id: req.request_id,
-                }))?;
-                break;
+                    agent_id: req.agent_id,
+                    cells,
+                }))?,
+                Err(err) => write_response(error_response(req.request_id, &err))?,
+            }
+        }
+        Request::Stats(req) => match state.as_ref() {
+            Some(st) => write_response(Response::Stats(protocol::StatsResponse {
+                request_id: req.request_id,
+                agents: st.stats_json(),
+            }))?,
+            None => write_response(Response::Error(protocol::ErrorResponse::new(
+                req.request_id,
+                "not initialized".to_string(),
+            )))?,
+        },
+        Request::Shutdown(req) => {
+            let agents = state
+                .as_ref()
+                .map(HarnessState::stats_json)
+                .unwrap_or_default();
+            if let Some(path) = &req.report {
+                let written = serde_json::to_string_pretty(&agents)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| {
+                        std::fs::write(path, json)
+                            .with_context(|| format!("cannot write stats report {path}"))
+                    });
+                if let Err(err) = written {
+                    write_response(error_response(req.request_id, &err))?;
+                    return Ok(ControlFlow::Break(()));
+                }
             }
+            write_response(Response::Ok(protocol::OkResponse {
+                request_id: req.request_id,
+                programs: None,
+                rejected: None,
+                agents: Some(agents),
+            }))?;
+            return Ok(ControlFlow::Break(()));
         }
     }
-
-    Ok(())
+    Ok(ControlFlow::Continue(()))
 }
 
+/// Loads every program that passes artifact validation, plus the requested
+/// baselines; the rest are returned as rejections and are not loaded.
+#[tracing::instrument(skip_all, fields(programs = programs.len(), baselines = baselines.len()))]
 async fn init_programs(
     programs: Vec<protocol::ProgramSpec>,
-) -> Result<(ProgramTestContext, HashMap<String, ProgramInfo>)> {
+    baselines: &[String],
+    compute_unit_limit: u32,
+    bounds: LookbackBounds,
+    max_program_bytes: u64,
+) -> Result<(
+    ProgramTestContext,
+    HashMap<String, ProgramInfo>,
+    Vec<ProgramRejectionJson>,
+)> {
+    if bounds.min > bounds.max {
+        return Err(anyhow!(
+            "min_lookback_len {} exceeds max_lookback_len {}",
+            bounds.min,
+            bounds.max
+        ));
+    }
+    let staging_dir = staging_dir()?;
+    let mut program_test = ProgramTest::default();
+    let mut program_map = HashMap::new();
+    // Loaded ids in request order, so negotiation does not depend on the
+    // map's iteration order.
+    let mut loaded_ids = Vec::new();
+    let mut rejected = Vec::new();
+
+    for prog in &programs {
+        let loaded = match (&prog.so_path, &prog.builtin) {
+            _ if program_map.contains_key(&prog.id) => Err("duplicate program id".to_string()),
+            (Some(so_path), None) => {
+                let so_path = PathBuf::from(so_path);
+                match check_artifact(&so_path, max_program_bytes) {
+                    Ok(()) => {
+                        let program_id =
+                            read_program_id(&so_path).unwrap_or_else(Pubkey::new_unique);
+                        let staged = staging_dir.join(format!("{}.so", prog.id));
+                        std::fs::copy(&so_path, &staged)?;
+                        program_test.add_program(&prog.id, program_id, None);
+                        Ok((program_id, Some(so_path)))
+                    }
+                    Err(reason) => Err(reason),
+                }
+            }
+            (None, Some(name)) => add_builtin(&mut program_test, &prog.id, name),
+            _ => Err("set exactly one of so_path and builtin".to_string()),
+        };
+        match loaded {
+            Ok((program_id, so_path)) => {
+                tracing::info!(id = %prog.id, source = %prog.source(), %program_id, "program loaded");
+                let info = ProgramInfo {
+                    id: program_id,
+                    input_version: 1,
+                    output_version: 1,
+                    lookback_len: prog.lookback_len,
+                    so_path,
+                };
+                program_map.insert(prog.id.clone(), info);
+                loaded_ids.push(prog.id.clone());
+            }
+            Err(reason) => {
+                tracing::warn!(id = %prog.id, %reason, "program rejected");
+                rejected.push(ProgramRejectionJson {
+                    id: prog.id.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+    for id in baselines {
+        match baselines::lookup(id) {
+            Some(_) if program_map.contains_key(id) => rejected.push(ProgramRejectionJson {
+                id: id.clone(),
+                reason: "duplicate program id".to_string(),
+            }),
+            Some(baseline) => {
+                let program_id = Pubkey::new_unique();
+                program_test.add_builtin_program(id, program_id, baseline);
+                program_map.insert(
+                    id.clone(),
+                    ProgramInfo {
+                        id: program_id,
+                        input_version: 1,
+                        output_version: 1,
+                        lookback_len: None,
+                        so_path: None,
+                    },
+                );
+                loaded_ids.push(id.clone());
+            }
+            None => rejected.push(ProgramRejectionJson {
+                id: id.clone(),
+                reason: format!(
+                    "unknown baseline: {id} (one of {})",
+                    baselines::IDS.join(", ")
+                ),
+            }),
+        }
+    }
+
+    let mut context = program_test.start_with_context().await;
+    for agent_id in &loaded_ids {
+        let Some(info) = program_map.get_mut(agent_id) else {
+            continue;
+        };
+        if negotiate_abi(&mut context, info, compute_unit_limit)
+            .await
+            .is_none()
+        {
+            tracing::warn!(id = %agent_id, "program rejected: no common ABI version");
+            program_map.remove(agent_id);
+            rejected.push(ProgramRejectionJson {
+                id: agent_id.clone(),
+                reason: "no common ABI version".to_string(),
+            });
+            continue;
+        }
+        info.lookback_len = info
+            .lookback_len
+            .map(|len| len.clamp(bounds.min, bounds.max));
+        tracing::debug!(
+            id = %agent_id,
+            input_version = info.input_version,
+            output_version = info.output_version,
+            lookback_len = info.lookback_len,
+            "abi negotiated"
+        );
+    }
+    Ok((context, program_map, rejected))
+}
+
+/// Registers a native test program from the builtin catalog under `id`.
+#[cfg(any(debug_assertions, feature = "test-programs"))]
+fn add_builtin(
+    program_test: &mut ProgramTest,
+    id: &str,
+    name: &str,
+) -> Result<(Pubkey, Option<PathBuf>), String> {
+    let builtin = builtins::lookup(name).ok_or_else(|| {
+        format!(
+            "unknown builtin: {name} (one of {})",
+            builtins::CATALOG.join(", ")
+        )
+    })?;
+    let program_id = Pubkey::new_unique();
+    program_test.add_builtin_program(id, program_id, builtin);
+    Ok((program_id, None))
+}
+
+#[cfg(not(any(debug_assertions, feature = "test-programs")))]
+fn add_builtin(
+    _program_test: &mut ProgramTest,
+    _id: &str,
+    name: &str,
+) -> Result<(Pubkey, Option<PathBuf>), String> {
+    Err(format!(
+        "builtin test programs are not in this build: {name} (enable the test-programs feature)"
+    ))
+}
+
+/// Reads and validates a `.so`, returning the rejection reason on failure.
+fn check_artifact(so_path: &Path, max_program_bytes: u64) -> Result<(), String> {
+    let bytes = std::fs::read(so_path)
+        .map_err(|err| format!("cannot read {}: {err}", so_path.display()))?;
+    validate_artifact(&bytes, max_program_bytes).map_err(|v| v.to_string())
+}
+
+/// Starts a context with a single program loaded under `name`, for the
+/// offline subcommands. The artifact must already have passed
+/// `check_artifact`.
+async fn start_program(so_path: &Path, name: &str) -> Result<(ProgramTestContext, Pubkey)> {
+    let staging_dir = staging_dir()?;
+    std::fs::copy(so_path, staging_dir.join(format!("{name}.so")))?;
+    let program_id = read_program_id(so_path).unwrap_or_else(Pubkey::new_unique);
+    let mut program_test = ProgramTest::default();
+    program_test.add_program(name, program_id, None);
+    Ok((program_test.start_with_context().await, program_id))
+}
+
+/// Creates the directory `ProgramTest` loads `.so` files from and points it
+/// there; programs are copied in under their agent id before start.
+fn staging_dir() -> Result<PathBuf> {
     let staging_dir = std::env::temp_dir().join("solclash-harness-bpf");
     std::fs::create_dir_all(&staging_dir)?;
 
     // ProgramTest reads these env vars at construction, so set them before start.
     std::env::set_var("SBF_OUT_DIR", &staging_dir);
     std::env::set_var("BPF_OUT_DIR", &staging_dir);
+    Ok(staging_dir)
+}
 
-    let mut program_test = ProgramTest::default();
-    let mut program_map = HashMap::new();
-
-    for prog in &programs {
-        let so_path = PathBuf::from(&prog.so_path);
-        let program_id = read_program_id(&so_path).unwrap_or_else(Pubkey::new_unique);
-
-        let staged = staging_dir.join(format!("{}.so", prog.id));
-        std::fs::copy(&so_path, &staged)?;
-
-        program_map.insert(prog.id.clone(), ProgramInfo { id: program_id });
+/// Probes the program for an `AbiDeclaration` and picks the highest input and
+/// output versions both sides support. Programs that fail the probe or answer
+/// with anything else are treated as v1-only. A declared lookback applies
+/// unless the program spec already set one.
+async fn negotiate_abi(
+    context: &mut ProgramTestContext,
+    info: &mut ProgramInfo,
+    compute_unit_limit: u32,
+) -> Option<()> {
+    let declared = invoke_program(
+        context,
+        info.id,
+        compute_unit_limit,
+        &[],
+        None,
+        ABI_PROBE_IX,
+    )
+    .await
+    .ok()
+    .and_then(|data| AbiDeclaration::decode(&data));
+    let Some(decl) = declared else {
+        return Some(());
+    };
+    info.input_version = highest_common_version(decl.input_versions, INPUT_VERSION_MAX)?;
+    info.output_version = highest_common_version(decl.output_versions, OUTPUT_VERSION_MAX)?;
+    if info.lookback_len.is_none() && decl.lookback_len > 0 {
+        info.lookback_len = Some(decl.lookback_len);
     }
+    Some(())
+}
 
-    for prog in &programs {
-        let info = &program_map[&prog.id];
-        program_test.add_program(&prog.id, info.id, None);
+/// A validated eval output. Rejected outputs are HOLDs carrying the rule's
+/// err code and a reason.
+struct Evaluated {
+    output: EvalOutputV3,
+    validation_reason: Option<&'static str>,
+}
+
+impl Evaluated {
+    fn rejected(err_code: u16, reason: &'static str) -> Self {
+        Self {
+            output: EvalOutputV3::hold(err_code),
+            validation_reason: Some(reason),
+        }
     }
+}
 
-    let context = program_test.start_with_context().await;
-    Ok((context, program_map))
+/// One eval as it ran: its validated output or why it failed, the compute
+/// units its transaction consumed (failed ones included) and how long it
+/// took.
+struct EvalRun {
+    result: Result<Evaluated>,
+    /// Unset when no transaction ran.
+    compute_units: Option<u64>,
+    duration: Duration,
 }
 
+/// Evaluates one agent; with a `profile` path the eval is also traced
+/// first, so a failing eval still leaves its profile. Evals that reach the
+/// program count in the agent's stats and go to the policy log, if any.
+#[tracing::instrument(
+    skip_all,
+    fields(%agent_id, window_id = %input_json.window_id, step = input_json.step_index)
+)]
 async fn handle_eval(
+    state: &mut HarnessState,
+    agent_id: &str,
+    input_json: EvalInputJson,
+    profile: Option<&Path>,
+    policy_log: Option<&mut PolicyLog>,
+) -> Result<(Evaluated, Option<ProfileJson>)> {
+    let program = state
+        .programs
+        .get(agent_id)
+        .ok_or_else(|| anyhow!(HarnessError::ProgramNotFound(agent_id.to_string())))?;
+
+    let window_id = input_json.window_id.clone();
+    let (input, features_bytes) =
+        program_view(&mut state.tape, &state.features, program, input_json)?;
+    let input_bytes = input.to_bytes()?;
+    let profile = match profile {
+        Some(out) => {
+            let so_path = program
+                .so_path
+                .as_deref()
+                .ok_or_else(|| anyhow!("only .so programs can be profiled: {agent_id}"))?;
+            Some(profile::profile_eval(
+                so_path,
+                &input_bytes,
+                features_bytes.as_deref(),
+                state.compute_unit_limit,
+                out,
+            )?)
+        }
+        None => None,
+    };
+
+    let run = timed_eval(
+        &mut state.context,
+        program,
+        state.compute_unit_limit,
+        &input_bytes,
+        features_bytes.as_deref(),
+        input.position_qty,
+    )
+    .await;
+    let duration_us = run.duration.as_micros() as u64;
+    match &run.result {
+        Ok(evaluated) => tracing::debug!(
+            compute_units = run.compute_units,
+            duration_us,
+            err_code = evaluated.output.err_code,
+            validation_reason = evaluated.validation_reason,
+            "eval completed"
+        ),
+        Err(err) => tracing::warn!(duration_us, %err, "eval failed"),
+    }
+    state
+        .stats
+        .entry(agent_id.to_string())
+        .or_default()
+        .record(&run);
+    if let Some(log) = policy_log {
+        let eval = LoggedEval {
+            window_id: &window_id,
+            agent_id,
+            input: &input,
+            input_bytes: &input_bytes,
+            sweep_cell: None,
+        };
+        log.record(&eval, &run)?;
+    }
+    run.result.map(|evaluated| (evaluated, profile))
+}
+
+/// Invokes `program` on one input and validates its output.
+async fn timed_eval(
     context: &mut ProgramTestContext,
-    programs: &HashMap<String, ProgramInfo>,
+    program: &ProgramInfo,
     compute_unit_limit: u32,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    position_qty: i64,
+) -> EvalRun {
+    let started = Instant::now();
+    let (invocation, compute_units) = invoke_program_counted(
+        context,
+        program.id,
+        compute_unit_limit,
+        input_bytes,
+        features_bytes,
+        &[],
+    )
+    .await;
+    EvalRun {
+        result: invocation.and_then(|invocation| {
+            evaluate_output(&invocation.data, program.output_version, position_qty)
+        }),
+        compute_units,
+        duration: started.elapsed(),
+    }
+}
+
+/// Records an eval input on the tape and returns it as `program` sees it,
+/// with the features account if the arena computes any.
+fn program_view(
+    tape: &mut WindowTape,
+    features: &[FeatureSpec],
+    program: &ProgramInfo,
+    input_json: EvalInputJson,
+) -> Result<(EvalInputV1, Option<Vec<u8>>)> {
+    let mut input = convert_input(input_json)?;
+    // Programs only see input versions they declared; newer trailers are dropped.
+    if input.version > program.input_version {
+        input.version = program.input_version;
+        input.depth = None;
+    }
+    tape.record(&input);
+    if let Some(len) = program.lookback_len {
+        input.ohlcv = tape.lookback(input.step_index, len);
+        input.lookback_len = input.ohlcv.len() as u16;
+    }
+    let features_bytes = if features.is_empty() {
+        None
+    } else {
+        let history = features.iter().map(FeatureSpec::history).max();
+        let bars = tape.lookback(input.step_index, history.unwrap_or(0));
+        let features = compute_features(features, input.step_index, &bars);
+        Some(borsh::to_vec(&features)?)
+    };
+    Ok((input, features_bytes))
+}
+
+/// Evaluates one agent on one market input under every account state of
+/// the grid. A grid with a cell that breaks the input rules is refused
+/// whole; past that, failures are per cell: a cell that breaks the sandbox
+/// or fails outright holds, and the sweep goes on. Every cell counts in the
+/// agent's stats and goes to the policy log, if any.
+async fn handle_sweep(
+    state: &mut HarnessState,
     agent_id: &str,
     input_json: EvalInputJson,
-) -> Result<EvalOutputV1> {
-    let program = programs
+    grid: &SweepGridJson,
+    mut policy_log: Option<&mut PolicyLog>,
+) -> Result<Vec<SweepCellJson>> {
+    let program = state
+        .programs
         .get(agent_id)
         .ok_or_else(|| anyhow!(HarnessError::ProgramNotFound(agent_id.to_string())))?;
+    sweep::check_size(grid)?;
+    let window_id = input_json.window_id.clone();
+    let (input, features_bytes) =
+        program_view(&mut state.tape, &state.features, program, input_json)?;
 
-    let input = convert_input(input_json)?;
-    let input_bytes = borsh::to_vec(&input)?;
+    let accounts = sweep::accounts(grid, &input);
+    sweep::check_cells(&accounts)?;
+    let mut cells = Vec::with_capacity(accounts.len());
+    for (index, account) in accounts.into_iter().enumerate() {
+        let mut input = input.clone();
+        sweep::apply(&account, &mut input);
+        let input_bytes = input.to_bytes()?;
+        let run = timed_eval(
+            &mut state.context,
+            program,
+            state.compute_unit_limit,
+            &input_bytes,
+            features_bytes.as_deref(),
+            input.position_qty,
+        )
+        .await;
+        state
+            .stats
+            .entry(agent_id.to_string())
+            .or_default()
+            .record(&run);
+        if let Some(log) = policy_log.as_deref_mut() {
+            let eval = LoggedEval {
+                window_id: &window_id,
+                agent_id,
+                input: &input,
+                input_bytes: &input_bytes,
+                sweep_cell: Some(index),
+            };
+            log.record(&eval, &run)?;
+        }
+        let (status, evaluated, reason) = match run.result {
+            Ok(evaluated) => (status::OK, evaluated, None),
+            Err(err) => {
+                let failure = Failure::of(&err);
+                let hold = Evaluated {
+                    output: failure.hold(),
+                    validation_reason: None,
+                };
+                (failure.status, hold, Some(err.to_string()))
+            }
+        };
+        cells.push(SweepCellJson {
+            account,
+            status: status.to_string(),
+            output: output_json(&evaluated.output),
+            reason,
+            validation_reason: evaluated.validation_reason.map(str::to_string),
+        });
+    }
+    Ok(cells)
+}
+
+/// Decodes and validates the output account data of one eval.
+fn evaluate_output(data: &[u8], max_version: u8, position_qty: i64) -> Result<Evaluated> {
+    let version = data.first().copied().unwrap_or(0);
+    if data.len() < output_len(version) {
+        return Ok(Evaluated::rejected(
+            ERR_OUTPUT_SER_FAIL,
+            "output account shorter than the output layout",
+        ));
+    }
+
+    let output = decode_output(data)?;
+    Ok(validate_output(output, max_version, position_qty))
+}
+
+/// Runs one instruction against fresh input/output accounts (plus a features
+/// account when given) and returns the output account data. Touching anything
+/// beyond those accounts fails with `HarnessError::SandboxViolation`.
+async fn invoke_program(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    compute_unit_limit: u32,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    instruction_data: &[u8],
+) -> Result<Vec<u8>> {
+    invoke_program_metered(
+        context,
+        program_id,
+        compute_unit_limit,
+        input_bytes,
+        features_bytes,
+        instruction_data,
+    )
+    .await
+    .map(|invocation| invocation.data)
+}
+
+/// Logged when SBF code runs past its budget (`EbpfError::ExceededMaxInstructions`).
+const COMPUTE_EXHAUSTED_LOG: &str = "failed: exceeded CUs meter";
 
+/// Budget exhaustion shows as `ComputationalBudgetExceeded` when a builtin
+/// or syscall charges past the limit, but SBF code that runs out fails with
+/// the generic `ProgramFailedToComplete`, told apart only by its log line.
+fn ran_out_of_compute(err: &TransactionError, logs: &[String]) -> bool {
+    match err {
+        TransactionError::InstructionError(_, InstructionError::ComputationalBudgetExceeded) => {
+            true
+        }
+        TransactionError::InstructionError(_, InstructionError::ProgramFailedToComplete) => {
+            logs.iter().any(|line| line.contains(COMPUTE_EXHAUSTED_LOG))
+        }
+        _ => false,
+    }
+}
+
+/// Output of a successful `invoke_program_metered`.
+struct Invocation {
+    data: Vec<u8>,
+    /// Consumed by the whole transaction, including the compute budget
+    /// instruction.
+    compute_units: u64,
+}
+
+/// `invoke_program` that also reports the compute units consumed.
+async fn invoke_program_metered(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    compute_unit_limit: u32,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    instruction_data: &[u8],
+) -> Result<Invocation> {
+    invoke_program_counted(
+        context,
+        program_id,
+        compute_unit_limit,
+        input_bytes,
+        features_bytes,
+        instruction_data,
+    )
+    .await
+    .0
+}
+
+/// `invoke_program_metered` that also counts the compute units of a failed
+/// transaction; they are unset only when the transaction never ran.
+async fn invoke_program_counted(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    compute_unit_limit: u32,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    instruction_data: &[u8],
+) -> (Result<Invocation>, Option<u64>) {
+    let mut compute_units = None;
+    let invocation = run_invocation(
+        context,
+        program_id,
+        compute_unit_limit,
+        input_bytes,
+        features_bytes,
+        instruction_data,
+        &mut compute_units,
+    )
+    .await;
+    (invocation, compute_units)
+}
+
+async fn run_invocation(
+    context: &mut ProgramTestContext,
+    program_id: Pubkey,
+    compute_unit_limit: u32,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    instruction_data: &[u8],
+    consumed: &mut Option<u64>,
+) -> Result<Invocation> {
     let input_pubkey = Pubkey::new_unique();
     let output_pubkey = Pubkey::new_unique();
 
@@ -184,26 +988,40 @@ async fn handle_eval(
     let mut input_account = AccountSharedData::new(
         rent.minimum_balance(input_bytes.len()),
         input_bytes.len(),
-        &program.id,
+        &program_id,
     );
-    input_account.set_data_from_slice(&input_bytes);
+    input_account.set_data_from_slice(input_bytes);
+    // Sized for the largest output version; v1 programs use the first 20 bytes.
     let output_account = AccountSharedData::new(
-        rent.minimum_balance(OUTPUT_LEN),
-        OUTPUT_LEN,
-        &program.id,
+        rent.minimum_balance(OUTPUT_V3_LEN),
+        OUTPUT_V3_LEN,
+        &program_id,
     );
 
     context.set_account(&input_pubkey, &input_account);
     context.set_account(&output_pubkey, &output_account);
 
+    let mut accounts = vec![
+        AccountMeta::new_readonly(input_pubkey, false),
+        AccountMeta::new(output_pubkey, false),
+    ];
+    let features_pubkey = features_bytes.map(|_| Pubkey::new_unique());
+    if let (Some(features_bytes), Some(features_pubkey)) = (features_bytes, features_pubkey) {
+        let mut features_account = AccountSharedData::new(
+            rent.minimum_balance(features_bytes.len()),
+            features_bytes.len(),
+            &program_id,
+        );
+        features_account.set_data_from_slice(features_bytes);
+        context.set_account(&features_pubkey, &features_account);
+        accounts.push(AccountMeta::new_readonly(features_pubkey, false));
+    }
+
     let compute_ix = ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit);
     let eval_ix = Instruction {
-        program_id: program.id,
-        accounts: vec![
-            AccountMeta::new_readonly(input_pubkey, false),
-            AccountMeta::new(output_pubkey, false),
-        ],
-        data: vec![],
+        program_id,
+        accounts,
+        data: instruction_data.to_vec(),
     };
 
     let recent_blockhash = context.banks_client.get_latest_blockhash().await?;
@@ -214,34 +1032,87 @@ async fn handle_eval(
         recent_blockhash,
     );
 
-    context.banks_client.process_transaction(tx).await?;
+    let processed = context
+        .banks_client
+        .process_transaction_with_metadata(tx)
+        .await?;
+    let (logs, compute_units) = processed
+        .metadata
+        .map(|meta| (meta.log_messages, meta.compute_units_consumed))
+        .unwrap_or_default();
+    *consumed = Some(compute_units);
+    if let Some(reason) = sandbox::check_logs(&logs) {
+        return Err(anyhow!(HarnessError::SandboxViolation(reason)));
+    }
+    if let Err(err) = processed.result {
+        return Err(match sandbox::classify_error(&err) {
+            Some(reason) => anyhow!(HarnessError::SandboxViolation(reason)),
+            None if ran_out_of_compute(&err, &logs) => {
+                anyhow!(HarnessError::ComputeExceeded(compute_unit_limit))
+            }
+            None => anyhow!(HarnessError::EvalFailed(err.to_string())),
+        });
+    }
 
     let output_account = context
         .banks_client
         .get_account(output_pubkey)
         .await?
         .ok_or_else(|| anyhow!(HarnessError::EvalFailed("missing output account".into())))?;
-
-    if output_account.data.len() < OUTPUT_LEN {
-        return Ok(EvalOutputV1::hold(7));
+    let mut readonly = vec![(
+        input_bytes,
+        context.banks_client.get_account(input_pubkey).await?,
+    )];
+    if let (Some(bytes), Some(pubkey)) = (features_bytes, features_pubkey) {
+        readonly.push((bytes, context.banks_client.get_account(pubkey).await?));
+    }
+    if let Some(reason) =
+        sandbox::check_accounts(&program_id, &readonly, &output_account, OUTPUT_V3_LEN)
+    {
+        return Err(anyhow!(HarnessError::SandboxViolation(reason)));
     }
+    Ok(Invocation {
+        data: output_account.data,
+        compute_units,
+    })
+}
 
-    let mut output = EvalOutputV1::try_from_slice(&output_account.data)?;
-    output = validate_output(output);
-    Ok(output)
+fn decode_output(data: &[u8]) -> Result<EvalOutputV3> {
+    let mut rest = data;
+    match data.first() {
+        Some(&OUTPUT_VERSION_ANNOTATED) => Ok(EvalOutputV3::deserialize(&mut rest)?),
+        Some(&OUTPUT_VERSION_ORDERS) => Ok(EvalOutputV2::deserialize(&mut rest)?.into()),
+        _ => Ok(EvalOutputV2::from(EvalOutputV1::deserialize(&mut rest)?).into()),
+    }
 }
 
-fn validate_output(output: EvalOutputV1) -> EvalOutputV1 {
-    if output.version != 1 {
-        return EvalOutputV1::hold(6);
+fn output_json(output: &EvalOutputV3) -> EvalOutputJson {
+    let orders = output.version >= OUTPUT_VERSION_ORDERS;
+    let annotated = output.version >= OUTPUT_VERSION_ANNOTATED;
+    EvalOutputJson {
+        version: output.version,
+        action_type: output.action_type,
+        order_qty: output.order_qty,
+        err_code: output.err_code,
+        order_type: orders.then_some(output.order_type),
+        trigger_price: orders.then_some(output.trigger_price),
+        time_in_force_bars: orders.then_some(output.time_in_force_bars),
+        annotation: annotated.then(|| hex::encode(output.annotation)),
     }
-    if (output.action_type == 1 || output.action_type == 2) && output.order_qty <= 0 {
-        return EvalOutputV1::hold(6);
+}
+
+fn validate_output(output: EvalOutputV3, max_version: u8, position_qty: i64) -> Evaluated {
+    match validation::validate_output(&OutputFields::from(&output), max_version, position_qty) {
+        Ok(()) => Evaluated {
+            output,
+            validation_reason: None,
+        },
+        Err(violation) => Evaluated::rejected(violation.err_code(), violation.reason()),
     }
-    output
 }
 
 fn convert_input(input: EvalInputJson) -> Result<EvalInputV1> {
+    input_rules::check(&input)?;
     let window_id = parse_window_id(&input.window_id)?;
     let mut bars = Vec::with_capacity(input.ohlcv.len());
     for bar in input.ohlcv {
@@ -253,6 +1124,10 @@ fn convert_input(input: EvalInputJson) -> Result<EvalInputV1> {
             volume: bar.volume,
         });
     }
+    let depth = match input.depth {
+        Some(depth) => Some(convert_depth(input.version, depth)?),
+        None => None,
+    };
 
     Ok(EvalInputV1 {
         version: input.version,
@@ -269,6 +1144,37 @@ fn convert_input(input: EvalInputJson) -> Result<EvalInputV1> {
         maintenance_margin_bps: input.maintenance_margin_bps,
         lookback_len: input.lookback_len,
         ohlcv: bars,
+        depth,
+        // Features travel in their own account, not in the input bytes.
+        features: None,
+    })
+}
+
+fn convert_depth(version: u8, depth: protocol::DepthSnapshotJson) -> Result<DepthSnapshot> {
+    if version < INPUT_VERSION_DEPTH {
+        return Err(anyhow!(HarnessError::InvalidInput {
+            field: "depth".to_string(),
+            reason: format!("requires input version {INPUT_VERSION_DEPTH}, got {version}"),
+        }));
+    }
+    if depth.bids.len() > MAX_DEPTH_LEVELS || depth.asks.len() > MAX_DEPTH_LEVELS {
+        return Err(anyhow!(HarnessError::InvalidInput {
+            field: "depth".to_string(),
+            reason: format!("more than {MAX_DEPTH_LEVELS} levels per side"),
+        }));
+    }
+    let to_levels = |levels: Vec<protocol::DepthLevelJson>| {
+        levels
+            .into_iter()
+            .map(|level| DepthLevel {
+                price: level.price,
+                size: level.size,
+            })
+            .collect()
+    };
+    Ok(DepthSnapshot {
+        bids: to_levels(depth.bids),
+        asks: to_levels(depth.asks),
     })
 }
 
@@ -298,18 +1204,91 @@ fn read_program_id(so_path: &Path) -> Option<Pubkey> {
     read_keypair_file(&keypair_path).ok().map(|kp| kp.pubkey())
 }
 
+/// Prints a subcommand's JSON report; a failed run exits with status 1.
+fn print_report<T: serde::Serialize>(report: &T, passed: bool) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(report)?);
+    if !passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+enum RequestLine {
+    Line(String),
+    TooLong,
+    Eof,
+}
+
+/// Reads one request line of at most `MAX_REQUEST_LINE_BYTES`. The rest of
+/// a longer line is skipped without being buffered.
+async fn read_request_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<RequestLine> {
+    let mut line = Vec::new();
+    let limit = MAX_REQUEST_LINE_BYTES as u64 + 1;
+    if (&mut *reader)
+        .take(limit)
+        .read_until(b'\n', &mut line)
+        .await?
+        == 0
+    {
+        return Ok(RequestLine::Eof);
+    }
+    if line.last() != Some(&b'\n') && line.len() > MAX_REQUEST_LINE_BYTES {
+        loop {
+            let buf = reader.fill_buf().await?;
+            let Some(end) = buf.iter().position(|&b| b == b'\n') else {
+                let len = buf.len();
+                reader.consume(len);
+                if len == 0 {
+                    break;
+                }
+                continue;
+            };
+            reader.consume(end + 1);
+            break;
+        }
+        return Ok(RequestLine::TooLong);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    }
+    Ok(RequestLine::Line(String::from_utf8(line)?))
+}
+
+/// An error response; input rule failures carry `INVALID_INPUT` and the field.
+fn error_response(request_id: u64, err: &anyhow::Error) -> Response {
+    let mut response = protocol::ErrorResponse::new(request_id, err.to_string());
+    if let Some(HarnessError::InvalidInput { field, .. }) = err.downcast_ref() {
+        response.code = Some("INVALID_INPUT".to_string());
+        response.field = Some(field.clone());
+    }
+    Response::Error(response)
+}
+
+/// Logs before and after the write, so a client not draining stdout shows
+/// as a write that never completes.
 fn write_response(response: Response) -> Result<()> {
     let mut stdout = std::io::stdout();
     let line = serde_json::to_string(&response)?;
+    tracing::debug!(bytes = line.len() + 1, "writing response");
     stdout.write_all(line.as_bytes())?;
     stdout.write_all(b"\n")?;
     stdout.flush()?;
+    tracing::debug!("wrote response");
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use abi::{
+        ABI_DECLARATION_TAG, ACTION_BUY, ACTION_BUY_NOTIONAL, ACTION_FLAG_REDUCE_ONLY, ACTION_HOLD,
+        ACTION_SELL, ACTION_TARGET_POSITION, ERR_ACTION_TYPE_INVALID, ERR_NOTIONAL_INVALID,
+        ERR_ORDER_QTY_INVALID, ERR_OUTPUT_INVALID, ERR_REDUCE_ONLY_INVALID, ERR_RESERVED_NONZERO,
+        ERR_TIME_IN_FORCE_INVALID, ERR_TRIGGER_PRICE_INVALID, ORDER_STOP, OUTPUT_V2_LEN,
+    };
     use borsh::BorshDeserialize;
     use protocol::EvalInputJson;
 
@@ -328,6 +1307,56 @@ mod tests {
         assert_eq!(decoded.action_type, 0);
     }
 
+    #[tokio::test]
+    async fn request_lines_over_the_cap_are_skipped_whole() {
+        let long = "x".repeat(MAX_REQUEST_LINE_BYTES + 1);
+        let data = format!(
+            "{{}}\r\n{long}\n{}\nlast",
+            "y".repeat(MAX_REQUEST_LINE_BYTES)
+        );
+        let mut reader = data.as_bytes();
+        let mut lines = Vec::new();
+        loop {
+            match read_request_line(&mut reader).await.expect("read") {
+                RequestLine::Line(line) => lines.push(line.len().to_string()),
+                RequestLine::TooLong => lines.push("too long".to_string()),
+                RequestLine::Eof => break,
+            }
+        }
+        let max = MAX_REQUEST_LINE_BYTES.to_string();
+        assert_eq!(lines, ["2", "too long", max.as_str(), "4"]);
+    }
+
+    #[tokio::test]
+    async fn sbf_programs_that_run_out_of_budget_exceed_compute() {
+        let dir = std::env::temp_dir().join(format!("solclash-sbf-budget-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("dir");
+        let looping = sbf_fixtures::looping_program(&dir, None);
+        let (mut context, program_id) = start_program(&looping, "loop-forever")
+            .await
+            .expect("start");
+        let Err(err) =
+            invoke_program_metered(&mut context, program_id, 50_000, &[], None, &[]).await
+        else {
+            panic!("a program stuck in a loop completed");
+        };
+        assert!(
+            matches!(
+                err.downcast_ref(),
+                Some(HarnessError::ComputeExceeded(50_000))
+            ),
+            "{err}"
+        );
+
+        let bounded = sbf_fixtures::looping_program(&dir, Some(100));
+        let (mut context, program_id) = start_program(&bounded, "loop-100").await.expect("start");
+        let invocation = invoke_program_metered(&mut context, program_id, 50_000, &[], None, &[])
+            .await
+            .expect("completes");
+        assert!(invocation.compute_units > 100);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn window_id_hashing() {
         let id = "test-window";
@@ -357,4 +1386,212 @@ mod tests {
         assert_eq!(parsed.cash_balance, 10000);
         assert_eq!(parsed.ohlcv.len(), 1);
     }
+
+    /// The shared fixture at `version` with no bars and the given depth.
+    fn depth_input(version: u8, depth: serde_json::Value) -> EvalInputJson {
+        let mut input = test_support::eval_input();
+        input["version"] = version.into();
+        input["lookback_len"] = 0.into();
+        input["ohlcv"] = serde_json::json!([]);
+        input["depth"] = depth;
+        serde_json::from_value(input).expect("parse")
+    }
+
+    #[test]
+    fn depth_snapshot_encodes_as_v2_trailer() {
+        let parsed = depth_input(
+            2,
+            serde_json::json!({
+                "bids": [{ "price": "99", "size": "5" }],
+                "asks": [{ "price": "101", "size": "7" }]
+            }),
+        );
+        let input = convert_input(parsed).expect("convert");
+        let bytes = input.to_bytes().expect("encode");
+
+        let mut rest = bytes.as_slice();
+        let prefix = EvalInputV1::deserialize(&mut rest).expect("prefix");
+        assert_eq!(prefix.version, 2);
+        let depth = Option::<DepthSnapshot>::deserialize(&mut rest)
+            .expect("trailer")
+            .expect("depth present");
+        assert!(rest.is_empty());
+        assert_eq!(depth.bids[0].size, 5);
+        assert_eq!(depth.asks[0].price, 101);
+    }
+
+    #[test]
+    fn depth_rejected_on_v1_input() {
+        let parsed = depth_input(1, serde_json::json!({ "bids": [], "asks": [] }));
+        assert!(convert_input(parsed).is_err());
+    }
+
+    #[test]
+    fn extended_actions_validate() {
+        let out = |action_type: u8, order_qty: i64| {
+            EvalOutputV3::from(EvalOutputV2::from(EvalOutputV1 {
+                version: 1,
+                action_type,
+                order_qty,
+                err_code: 0,
+                reserved: [0u8; 8],
+            }))
+        };
+        let target = validate_output(out(ACTION_TARGET_POSITION, -3), 1, 0).output;
+        assert_eq!(target.action_type, ACTION_TARGET_POSITION);
+        assert_eq!(target.order_qty, -3);
+
+        let notional = validate_output(out(ACTION_BUY_NOTIONAL, 0), 1, 0).output;
+        assert_eq!(notional.err_code, ERR_NOTIONAL_INVALID);
+
+        // A reduce-only flip is accepted and clipped to a close of the 2 held.
+        let flip = validate_output(
+            out(ACTION_TARGET_POSITION | ACTION_FLAG_REDUCE_ONLY, -1),
+            1,
+            2,
+        )
+        .output;
+        assert_eq!(flip.err_code, 0);
+        assert_eq!(
+            flip.action_type,
+            ACTION_TARGET_POSITION | ACTION_FLAG_REDUCE_ONLY
+        );
+        assert_eq!(backtest::clip_reduce_only(flip.order_qty - 2, 2), -2);
+
+        let grow = validate_output(
+            out(ACTION_TARGET_POSITION | ACTION_FLAG_REDUCE_ONLY, 3),
+            1,
+            2,
+        )
+        .output;
+        assert_eq!(grow.err_code, ERR_REDUCE_ONLY_INVALID);
+
+        let trim = validate_output(out(ACTION_SELL | ACTION_FLAG_REDUCE_ONLY, 5), 1, 2).output;
+        assert_eq!(trim.err_code, 0);
+        assert_eq!(trim.action_type, ACTION_SELL | ACTION_FLAG_REDUCE_ONLY);
+    }
+
+    #[test]
+    fn strict_rules_report_validation_reason() {
+        let out = |action_type: u8, order_qty: i64| {
+            EvalOutputV3::from(EvalOutputV2::from(EvalOutputV1 {
+                version: 1,
+                action_type,
+                order_qty,
+                err_code: 0,
+                reserved: [0u8; 8],
+            }))
+        };
+        let unknown = validate_output(out(9, 1), 1, 0);
+        assert_eq!(unknown.output.err_code, ERR_ACTION_TYPE_INVALID);
+        assert_eq!(unknown.validation_reason, Some("unknown action_type"));
+
+        let mut reserved = out(ACTION_BUY, 1);
+        reserved.reserved[0] = 1;
+        assert_eq!(
+            validate_output(reserved, 1, 0).output.err_code,
+            ERR_RESERVED_NONZERO
+        );
+
+        let negative_hold = validate_output(out(ACTION_HOLD, -1), 1, 0);
+        assert_eq!(negative_hold.output.err_code, ERR_ORDER_QTY_INVALID);
+
+        // Versions beyond the negotiated one are rejected.
+        let mut v2 = out(ACTION_HOLD, 0);
+        v2.version = OUTPUT_VERSION_ORDERS;
+        assert_eq!(
+            validate_output(v2.clone(), 1, 0).output.err_code,
+            ERR_OUTPUT_INVALID
+        );
+        assert!(validate_output(v2, 2, 0).validation_reason.is_none());
+    }
+
+    #[test]
+    fn v2_stop_order_decodes_and_validates() {
+        let stop = EvalOutputV2 {
+            version: OUTPUT_VERSION_ORDERS,
+            action_type: ACTION_SELL | ACTION_FLAG_REDUCE_ONLY,
+            order_qty: 1,
+            err_code: 0,
+            order_type: ORDER_STOP,
+            trigger_price: 49_000_000_000,
+            time_in_force_bars: 10,
+            reserved: [0u8; 8],
+        };
+        let bytes = borsh::to_vec(&stop).expect("serialize");
+        assert_eq!(bytes.len(), OUTPUT_V2_LEN);
+
+        let decoded = validate_output(decode_output(&bytes).expect("decode"), 2, 1).output;
+        assert_eq!(decoded.err_code, 0);
+        let json = serde_json::to_value(output_json(&decoded)).expect("json");
+        assert_eq!(json["order_type"], ORDER_STOP);
+        assert_eq!(json["trigger_price"], "49000000000");
+        assert_eq!(json["time_in_force_bars"], 10);
+
+        let mut bad = EvalOutputV3::from(stop.clone());
+        bad.trigger_price = 0;
+        assert_eq!(
+            validate_output(bad, 2, 1).output.err_code,
+            ERR_TRIGGER_PRICE_INVALID
+        );
+
+        let mut no_time_in_force = EvalOutputV3::from(stop);
+        no_time_in_force.time_in_force_bars = 0;
+        assert_eq!(
+            validate_output(no_time_in_force, 2, 1).output.err_code,
+            ERR_TIME_IN_FORCE_INVALID
+        );
+    }
+
+    #[test]
+    fn v1_output_decodes_from_padded_account() {
+        let mut data = borsh::to_vec(&EvalOutputV1::hold(0)).expect("serialize");
+        data.resize(OUTPUT_V3_LEN, 0);
+        let decoded = decode_output(&data).expect("decode");
+        assert_eq!(decoded.version, 1);
+        let json = serde_json::to_value(output_json(&decoded)).expect("json");
+        assert!(json.get("order_type").is_none());
+    }
+
+    #[test]
+    fn v3_annotation_returned_as_hex() {
+        let mut output = EvalOutputV3::from(EvalOutputV2::from(EvalOutputV1::hold(0)));
+        output.version = OUTPUT_VERSION_ANNOTATED;
+        output.annotation[..4].copy_from_slice(b"trnd");
+        let bytes = borsh::to_vec(&output).expect("serialize");
+        assert_eq!(bytes.len(), OUTPUT_V3_LEN);
+
+        let decoded = validate_output(decode_output(&bytes).expect("decode"), 3, 0).output;
+        let json = serde_json::to_value(output_json(&decoded)).expect("json");
+        let annotation = json["annotation"].as_str().expect("annotation");
+        assert!(annotation.starts_with("74726e64"));
+        assert_eq!(annotation.len(), 64);
+    }
+
+    #[test]
+    fn abi_declaration_picks_highest_common_version() {
+        let decl = AbiDeclaration {
+            tag: ABI_DECLARATION_TAG,
+            input_versions: 0b11,
+            output_versions: 0b1111_0001,
+            lookback_len: 0,
+        };
+        let bytes = borsh::to_vec(&decl).expect("serialize");
+        let decoded = AbiDeclaration::decode(&bytes).expect("declaration");
+        assert_eq!(
+            highest_common_version(decoded.input_versions, INPUT_VERSION_MAX),
+            Some(2)
+        );
+        assert_eq!(
+            highest_common_version(decoded.output_versions, OUTPUT_VERSION_MAX),
+            Some(1)
+        );
+        assert_eq!(
+            highest_common_version(0b1000_0000, OUTPUT_VERSION_MAX),
+            None
+        );
+
+        let legacy = borsh::to_vec(&EvalOutputV1::hold(1)).expect("serialize");
+        assert!(AbiDeclaration::decode(&legacy).is_none());
+    }
 }
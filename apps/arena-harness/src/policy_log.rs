@@ -0,0 +1,186 @@
+//! `--policy-log <path>`: serve mode appends one `policy_log.jsonl` record
+//! (data-ops spec §5.1) per eval and per sweep cell, straight from the
+//! execution layer. Records extend the spec's fields with the failure class,
+//! compute units, a hash of the input account and the eval's duration.
+
+use crate::abi::{EvalInputV1, OUTPUT_VERSION_ANNOTATED};
+use crate::status::{self, Failure};
+use crate::EvalRun;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+struct PolicyLogRecord<'a> {
+    window_id: &'a str,
+    step_index: u32,
+    agent_id: &'a str,
+    action_type: u8,
+    order_qty: i64,
+    /// `OK`, `SANDBOX_VIOLATION` or `ERR` (see `status`); failed evals hold.
+    status: &'static str,
+    err_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    annotation: Option<String>,
+    /// `invalid_output`, `sandbox_violation`, `compute_exceeded` or `abort`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// Failed transactions included; unset when none ran.
+    compute_units: Option<u64>,
+    /// SHA-256 of the input account bytes the program saw.
+    input_sha256: String,
+    duration_us: u64,
+    /// Index of the grid cell, for sweep evals.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sweep_cell: Option<usize>,
+}
+
+/// One eval as the program saw it, for its log record.
+pub struct LoggedEval<'a> {
+    /// As the client sent it; the input carries only its hash.
+    pub window_id: &'a str,
+    pub agent_id: &'a str,
+    pub input: &'a EvalInputV1,
+    pub input_bytes: &'a [u8],
+    pub sweep_cell: Option<usize>,
+}
+
+pub struct PolicyLog {
+    file: File,
+}
+
+impl PolicyLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("cannot open policy log {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    /// Appends the record of one eval. Each record is one write.
+    pub fn record(&mut self, eval: &LoggedEval, run: &EvalRun) -> Result<()> {
+        let mut line = serde_json::to_vec(&entry(eval, run))?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Failed evals are logged as the HOLD `Failure` stands in for them.
+fn entry<'a>(eval: &LoggedEval<'a>, run: &EvalRun) -> PolicyLogRecord<'a> {
+    let (status, output, failure, reason) = match &run.result {
+        Ok(evaluated) => (
+            status::OK,
+            evaluated.output.clone(),
+            evaluated.validation_reason.map(|_| "invalid_output"),
+            evaluated.validation_reason.map(str::to_string),
+        ),
+        Err(err) => {
+            let failure = Failure::of(err);
+            (
+                failure.status,
+                failure.hold(),
+                Some(failure.class),
+                Some(err.to_string()),
+            )
+        }
+    };
+    PolicyLogRecord {
+        window_id: eval.window_id,
+        step_index: eval.input.step_index,
+        agent_id: eval.agent_id,
+        action_type: output.action_type,
+        order_qty: output.order_qty,
+        status,
+        err_code: output.err_code,
+        annotation: (output.version >= OUTPUT_VERSION_ANNOTATED)
+            .then(|| hex::encode(output.annotation)),
+        failure,
+        reason,
+        compute_units: run.compute_units,
+        input_sha256: hex::encode(Sha256::digest(eval.input_bytes)),
+        duration_us: run.duration.as_micros() as u64,
+        sweep_cell: eval.sweep_cell,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{ERR_POLICY_ERR, ERR_SANDBOX_VIOLATION};
+    use crate::error::HarnessError;
+    use crate::replay::tests::flat_bars;
+    use crate::replay::Tape;
+    use crate::Evaluated;
+    use anyhow::anyhow;
+    use std::time::Duration;
+
+    fn run(result: Result<Evaluated>, compute_units: Option<u64>) -> EvalRun {
+        EvalRun {
+            result,
+            compute_units,
+            duration: Duration::from_micros(42),
+        }
+    }
+
+    #[test]
+    fn records_extend_the_data_ops_fields() {
+        let tape = Tape {
+            instrument: None,
+            bars: flat_bars(2, 100.0),
+        };
+        let input = tape.flat_input(1, 2);
+        let input_bytes = input.to_bytes().unwrap();
+        let eval = LoggedEval {
+            window_id: "w0",
+            agent_id: "agent",
+            input: &input,
+            input_bytes: &input_bytes,
+            sweep_cell: None,
+        };
+
+        let ok = run(
+            Ok(Evaluated::rejected(15, "order_qty must be > 0")),
+            Some(1234),
+        );
+        let record = serde_json::to_value(entry(&eval, &ok)).unwrap();
+        assert_eq!(record["step_index"], 1);
+        assert_eq!(
+            (&record["status"], &record["err_code"]),
+            (&"OK".into(), &15.into())
+        );
+        assert_eq!(record["failure"], "invalid_output");
+        assert_eq!(record["compute_units"], 1234);
+        assert_eq!(record["duration_us"], 42);
+        assert_eq!(record["input_sha256"].as_str().unwrap().len(), 64);
+        assert!(record.get("annotation").is_none());
+
+        let failed = run(
+            Err(anyhow!(HarnessError::ComputeExceeded(200_000))),
+            Some(200_000),
+        );
+        let record = serde_json::to_value(entry(&eval, &failed)).unwrap();
+        assert_eq!(record["status"], "ERR");
+        assert_eq!(record["err_code"], ERR_POLICY_ERR);
+        assert_eq!(record["failure"], "compute_exceeded");
+        assert_eq!(record["compute_units"], 200_000);
+
+        let sandbox = run(
+            Err(anyhow!(HarnessError::SandboxViolation("cpi".into()))),
+            None,
+        );
+        let record = serde_json::to_value(entry(&eval, &sandbox)).unwrap();
+        assert_eq!(record["status"], "SANDBOX_VIOLATION");
+        assert_eq!(record["err_code"], ERR_SANDBOX_VIOLATION);
+        assert_eq!(record["failure"], "sandbox_violation");
+        assert_eq!(record["compute_units"], serde_json::Value::Null);
+        assert!(record.get("sweep_cell").is_none());
+    }
+}
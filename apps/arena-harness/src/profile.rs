@@ -0,0 +1,670 @@
+//! Instruction-level profiles of single evals. The program runs once more in
+//! a standalone interpreter VM, stepped one instruction at a time like
+//! `solana-ledger-tool program run --trace`, so every compute unit, syscall
+//! costs included, lands on the call stack that spent it. Stacks are named
+//! from the `.so` debug info, or its symbol table for builds without one.
+//!
+//! `solclash-harness profile` profiles one input; serve-mode evals take a
+//! `profile` path under the `--profile-dir` instead. Both write a
+//! folded-stacks file (`a;b;c 123` per line) that `flamegraph.pl`,
+//! `inferno-flamegraph` and speedscope read.
+
+use crate::abi::{EvalInputV1, OUTPUT_V3_LEN};
+use crate::cli::ProfileOptions;
+use crate::error::HarnessError;
+use crate::protocol::{ProfileFunctionJson, ProfileJson};
+use crate::replay::{load_tape, DEFAULT_LOOKBACK_LEN};
+use anyhow::{anyhow, Context, Result};
+use gimli::{EndianSlice, LittleEndian, Reader};
+use goblin::elf::Elf;
+use serde::Serialize;
+use solana_bpf_loader_program::serialization::serialize_parameters;
+use solana_bpf_loader_program::syscalls::create_program_runtime_environment_v1;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_rbpf::ebpf;
+use solana_rbpf::elf::Executable;
+use solana_rbpf::error::ProgramResult;
+use solana_rbpf::interpreter::Interpreter;
+use solana_rbpf::vm::{ContextObject, EbpfVm};
+use solana_sdk::account::{AccountSharedData, WritableAccount};
+use solana_sdk::bpf_loader;
+use solana_sdk::feature_set::bpf_account_data_direct_mapping;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction_context::InstructionAccount;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+
+/// Origin links followed from a subprogram to the entry that names it.
+const MAX_ORIGIN_DEPTH: usize = 4;
+
+/// A folded trace. Stacks are counted in compute units, each syscall's cost
+/// on the function that made it, so they add up to `compute_units`.
+struct Profile {
+    stacks: BTreeMap<String, u64>,
+    instructions: u64,
+    compute_units: u64,
+    symbolized: bool,
+    error: Option<String>,
+}
+
+/// Report printed by `solclash-harness profile`.
+#[derive(Debug, Serialize)]
+pub struct ProfileReport {
+    pub program: String,
+    /// The input file, or the tape and step the input was built from.
+    pub input: String,
+    pub compute_unit_limit: u32,
+    /// False when the traced eval failed.
+    pub passed: bool,
+    pub profile: ProfileJson,
+}
+
+pub fn run(options: &ProfileOptions) -> Result<ProfileReport> {
+    let (input_bytes, input) = match (&options.input_path, &options.data_path) {
+        (Some(path), None) => (
+            std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?,
+            path.display().to_string(),
+        ),
+        (None, Some(path)) => {
+            let input = step_input(path, options.step, options.lookback_len)?;
+            let described = format!("{} step {}", path.display(), input.step_index);
+            (input.to_bytes()?, described)
+        }
+        _ => return Err(anyhow!("set exactly one of --input and --data")),
+    };
+    let profile = profile_eval(
+        &options.so_path,
+        &input_bytes,
+        None,
+        options.compute_unit_limit,
+        &options.out,
+    )?;
+    Ok(ProfileReport {
+        program: options.so_path.display().to_string(),
+        input,
+        compute_unit_limit: options.compute_unit_limit,
+        passed: profile.error.is_none(),
+        profile,
+    })
+}
+
+/// Where a serve-mode `profile` path is written: inside `dir`, which must
+/// be configured, and never outside it.
+pub fn serve_out_path(dir: Option<&Path>, requested: &str) -> Result<PathBuf, HarnessError> {
+    let invalid = |reason: &str| HarnessError::InvalidInput {
+        field: "profile".to_string(),
+        reason: reason.to_string(),
+    };
+    let dir = dir.ok_or_else(|| invalid("profiling is disabled; serve with --profile-dir"))?;
+    let path = Path::new(requested);
+    let inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside || path.file_name().is_none() {
+        return Err(invalid(
+            "must be a relative file path inside the profile directory",
+        ));
+    }
+    Ok(dir.join(path))
+}
+
+/// Traces one eval of the `.so` at `so_path` and writes its folded stacks
+/// to `out`.
+pub fn profile_eval(
+    so_path: &Path,
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    compute_unit_limit: u32,
+    out: &Path,
+) -> Result<ProfileJson> {
+    let so_bytes =
+        std::fs::read(so_path).with_context(|| format!("cannot read {}", so_path.display()))?;
+    let profile = trace_eval(&so_bytes, input_bytes, features_bytes, compute_unit_limit)?;
+    let mut folded = String::new();
+    for (stack, count) in &profile.stacks {
+        folded.push_str(&format!("{stack} {count}\n"));
+    }
+    if let Some(dir) = out.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(out, folded).with_context(|| format!("cannot write {}", out.display()))?;
+    Ok(ProfileJson {
+        folded_path: out.display().to_string(),
+        compute_units: profile.compute_units,
+        instructions: profile.instructions,
+        syscall_units: profile.compute_units.saturating_sub(profile.instructions),
+        symbolized: profile.symbolized,
+        error: profile.error,
+        functions: functions(&profile.stacks),
+    })
+}
+
+/// Runs the program against input and output accounts laid out as in
+/// `invoke_program`, with the features account when given.
+fn trace_eval(
+    so_bytes: &[u8],
+    input_bytes: &[u8],
+    features_bytes: Option<&[u8]>,
+    compute_unit_limit: u32,
+) -> Result<Profile> {
+    let program_id = Pubkey::new_unique();
+    let rent = solana_sdk::rent::Rent::default();
+    let account = |data: &[u8]| {
+        let mut account =
+            AccountSharedData::new(rent.minimum_balance(data.len()), data.len(), &program_id);
+        account.set_data_from_slice(data);
+        account
+    };
+    let mut program_account = AccountSharedData::new(0, 0, &bpf_loader::id());
+    program_account.set_executable(true);
+    let mut transaction_accounts = vec![
+        (program_id, program_account),
+        (Pubkey::new_unique(), account(input_bytes)),
+        (Pubkey::new_unique(), account(&[0; OUTPUT_V3_LEN])),
+    ];
+    if let Some(features_bytes) = features_bytes {
+        transaction_accounts.push((Pubkey::new_unique(), account(features_bytes)));
+    }
+    let instruction_accounts: Vec<InstructionAccount> = (1..transaction_accounts.len() as u16)
+        .map(|index| InstructionAccount {
+            index_in_transaction: index,
+            index_in_caller: index,
+            index_in_callee: index - 1,
+            is_signer: false,
+            is_writable: index == 2,
+        })
+        .collect();
+
+    solana_program_runtime::with_mock_invoke_context!(
+        invoke_context,
+        transaction_context,
+        transaction_accounts
+    );
+    invoke_context
+        .transaction_context
+        .get_next_instruction_context()?
+        .configure(&[0], &instruction_accounts, &[]);
+    invoke_context.push()?;
+    let environment = create_program_runtime_environment_v1(
+        &invoke_context.feature_set,
+        invoke_context.get_compute_budget(),
+        false,
+        true, // symbol names for the loader's function registry
+    )
+    .map_err(|err| anyhow!("cannot create SBF runtime: {err}"))?;
+    let executable = Executable::load(so_bytes, std::sync::Arc::new(environment))
+        .map_err(|err| anyhow!("cannot load program: {err}"))?;
+    let names = symbols(&executable, Some(so_bytes));
+    execute_traced(&executable, &mut invoke_context, compute_unit_limit, names)
+}
+
+fn execute_traced<'a>(
+    executable: &Executable<InvokeContext<'a>>,
+    invoke_context: &mut InvokeContext<'a>,
+    compute_unit_limit: u32,
+    (names, symbolized): (BTreeMap<usize, String>, bool),
+) -> Result<Profile> {
+    let direct_mapping = invoke_context
+        .feature_set
+        .is_active(&bpf_account_data_direct_mapping::id());
+    let instruction_context = invoke_context
+        .transaction_context
+        .get_current_instruction_context()?;
+    // Regions point into the parameter bytes, which must outlive the VM.
+    let (_parameter_bytes, regions, accounts_metadata) = serialize_parameters(
+        invoke_context.transaction_context,
+        instruction_context,
+        !direct_mapping,
+    )?;
+    invoke_context.mock_set_remaining(compute_unit_limit as u64);
+    let (steps, compute_units, result) = {
+        solana_bpf_loader_program::create_vm!(
+            vm,
+            executable,
+            regions,
+            accounts_metadata,
+            invoke_context
+        );
+        let mut vm = vm.map_err(|err| anyhow!("cannot create SBF VM: {err}"))?;
+        execute_stepped(&mut vm, executable)
+    };
+    let error = match Result::from(result) {
+        Ok(0) => None,
+        Ok(status) => Some(format!("program returned error {status:#x}")),
+        Err(err) => Some(err.to_string()),
+    };
+    Ok(Profile {
+        stacks: fold(&steps, executable.get_text_bytes().1, &names),
+        instructions: steps.len() as u64,
+        compute_units,
+        symbolized,
+        error,
+    })
+}
+
+/// One executed instruction and the compute units it consumed: one, plus
+/// the syscall's own cost when it called one.
+struct Step {
+    pc: usize,
+    units: u64,
+}
+
+/// Runs the program in the interpreter as `EbpfVm::execute_program` does,
+/// but one instruction at a time, reading the meter after each. Returns
+/// the steps, the units consumed in all, and the program result.
+fn execute_stepped<C: ContextObject>(
+    vm: &mut EbpfVm<C>,
+    executable: &Executable<C>,
+) -> (Vec<Step>, u64, ProgramResult) {
+    vm.registers[1] = ebpf::MM_INPUT_START;
+    vm.registers[ebpf::FRAME_PTR_REG] = vm.stack_pointer;
+    vm.registers[11] = executable.get_entrypoint_instruction_offset() as u64;
+    let initial = vm.context_object_pointer.get_remaining();
+    vm.previous_instruction_meter = initial;
+    vm.due_insn_count = 0;
+    vm.program_result = ProgramResult::Ok(0);
+    // The VM charges instructions in batches, before each syscall and at
+    // the end; `due_insn_count` holds the ones not charged yet.
+    let used = |vm: &EbpfVm<C>| {
+        initial.saturating_sub(vm.context_object_pointer.get_remaining()) + vm.due_insn_count
+    };
+    let mut registers = vm.registers;
+    let mut steps = Vec::new();
+    loop {
+        let before = used(vm);
+        let mut interpreter = Interpreter::new(vm, executable, registers);
+        let running = interpreter.step();
+        let pc = registers[11] as usize;
+        registers = interpreter.reg;
+        steps.push(Step {
+            pc,
+            units: used(vm).saturating_sub(before),
+        });
+        if !running {
+            break;
+        }
+    }
+    vm.context_object_pointer.consume(vm.due_insn_count);
+    let compute_units = initial.saturating_sub(vm.context_object_pointer.get_remaining());
+    let result = std::mem::replace(&mut vm.program_result, ProgramResult::Ok(0));
+    (steps, compute_units, result)
+}
+
+/// Function names by first instruction: the loader's function registry,
+/// overlaid with the subprograms of the ELF's DWARF debug info or, for
+/// builds without any, the `FUNC` symbols of its symbol table. Only
+/// functions in `.text` count. Names are demangled. The flag is true when
+/// debug info or the symbol table contributed.
+fn symbols<C: ContextObject>(
+    executable: &Executable<C>,
+    so_bytes: Option<&[u8]>,
+) -> (BTreeMap<usize, String>, bool) {
+    let mut names: BTreeMap<usize, String> = executable
+        .get_function_registry()
+        .iter()
+        .map(|(_, (name, pc))| (pc, String::from_utf8_lossy(name).into_owned()))
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    let mut symbolized = false;
+    let elf = so_bytes.and_then(|bytes| Some((bytes, Elf::parse(bytes).ok()?)));
+    if let Some((bytes, elf)) = elf {
+        let text = elf
+            .section_headers
+            .iter()
+            .find(|header| elf.shdr_strtab.get_at(header.sh_name) == Some(".text"));
+        if let Some(text) = text {
+            let range = text.sh_addr..text.sh_addr + text.sh_size;
+            let mut found = debug_info_names(&elf, bytes);
+            if found.is_empty() {
+                found = elf
+                    .syms
+                    .iter()
+                    .filter(|sym| sym.is_function())
+                    .filter_map(|sym| Some((sym.st_value, elf.strtab.get_at(sym.st_name)?)))
+                    .filter(|(_, name)| !name.is_empty())
+                    .map(|(addr, name)| (addr, name.to_string()))
+                    .collect();
+            }
+            for (addr, name) in found {
+                if !range.contains(&addr) {
+                    continue;
+                }
+                let pc = ((addr - text.sh_addr) / ebpf::INSN_SIZE as u64) as usize;
+                names.insert(pc, name);
+                symbolized = true;
+            }
+        }
+    }
+    for name in names.values_mut() {
+        // `;` separates frames in the folded format.
+        *name = format!("{:#}", rustc_demangle::demangle(name)).replace(';', ",");
+    }
+    (names, symbolized)
+}
+
+/// Subprogram names by address from the ELF's DWARF sections; empty when it
+/// has none or they do not parse.
+fn debug_info_names(elf: &Elf, bytes: &[u8]) -> BTreeMap<u64, String> {
+    let section = |id: gimli::SectionId| {
+        let data = elf
+            .section_headers
+            .iter()
+            .find(|header| elf.shdr_strtab.get_at(header.sh_name) == Some(id.name()))
+            .and_then(|header| {
+                let start = header.sh_offset as usize;
+                bytes.get(start..start.checked_add(header.sh_size as usize)?)
+            })
+            .unwrap_or_default();
+        Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+    };
+    gimli::Dwarf::load(section)
+        .and_then(|dwarf| dwarf_names(&dwarf))
+        .unwrap_or_default()
+}
+
+/// The address and name of every subprogram with code. Names are linkage
+/// names where the entry, or the declaration or abstract instance it points
+/// to, has one; otherwise plain names.
+fn dwarf_names<R: Reader>(dwarf: &gimli::Dwarf<R>) -> gimli::Result<BTreeMap<u64, String>> {
+    let mut names = BTreeMap::new();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let Some(low_pc) = entry.attr_value(gimli::DW_AT_low_pc)? else {
+                continue;
+            };
+            let Some(addr) = dwarf.attr_address(&unit, low_pc)? else {
+                continue;
+            };
+            if let Some(name) = subprogram_name(dwarf, &unit, entry)? {
+                names.insert(addr, name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn subprogram_name<R: Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> gimli::Result<Option<String>> {
+    for attr in [
+        gimli::DW_AT_linkage_name,
+        gimli::DW_AT_MIPS_linkage_name,
+        gimli::DW_AT_name,
+    ] {
+        let mut entry = entry.clone();
+        for _ in 0..MAX_ORIGIN_DEPTH {
+            if let Some(value) = entry.attr_value(attr)? {
+                let name = dwarf.attr_string(unit, value)?;
+                return Ok(Some(name.to_string_lossy()?.into_owned()));
+            }
+            let origin = match entry.attr_value(gimli::DW_AT_specification)? {
+                Some(origin) => Some(origin),
+                None => entry.attr_value(gimli::DW_AT_abstract_origin)?,
+            };
+            let Some(gimli::AttributeValue::UnitRef(offset)) = origin else {
+                break;
+            };
+            entry = unit.entry(offset)?;
+        }
+    }
+    Ok(None)
+}
+
+/// Replays the executed program counters through calls and exits, adding
+/// each instruction's units to its call stack. A call is into a function
+/// when the next instruction is not the one after it; otherwise it was a
+/// syscall, and its cost stays with the caller.
+fn fold(steps: &[Step], text: &[u8], names: &BTreeMap<usize, String>) -> BTreeMap<String, u64> {
+    let name_of = |pc: usize| match names.range(..=pc).next_back() {
+        Some((_, name)) => name.clone(),
+        None => format!("fn_{pc:#x}"),
+    };
+    let mut stacks = BTreeMap::new();
+    let mut frames: Vec<String> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        let pc = step.pc;
+        if frames.is_empty() {
+            frames.push(name_of(pc));
+        }
+        *stacks.entry(frames.join(";")).or_insert(0) += step.units;
+        let opcode = text.get(pc * ebpf::INSN_SIZE).copied().unwrap_or(0);
+        match opcode {
+            ebpf::CALL_IMM | ebpf::CALL_REG => {
+                if let Some(next) = steps.get(index + 1) {
+                    if next.pc != pc + 1 {
+                        frames.push(name_of(next.pc));
+                    }
+                }
+            }
+            ebpf::EXIT => {
+                frames.pop();
+            }
+            _ => {}
+        }
+    }
+    stacks
+}
+
+/// Self and inclusive units per function, costliest first.
+fn functions(stacks: &BTreeMap<String, u64>) -> Vec<ProfileFunctionJson> {
+    let mut units: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    for (stack, &count) in stacks {
+        let frames: Vec<&str> = stack.split(';').collect();
+        if let Some(top) = frames.last() {
+            units.entry(top).or_default().0 += count;
+        }
+        for frame in frames.into_iter().collect::<BTreeSet<_>>() {
+            units.entry(frame).or_default().1 += count;
+        }
+    }
+    let mut functions: Vec<ProfileFunctionJson> = units
+        .into_iter()
+        .map(|(name, (self_units, total_units))| ProfileFunctionJson {
+            name: name.to_string(),
+            self_units,
+            total_units,
+        })
+        .collect();
+    functions.sort_by(|a, b| b.self_units.cmp(&a.self_units).then(a.name.cmp(&b.name)));
+    functions
+}
+
+/// The input at `step` (default: the last bar) of a tape, seeing up to
+/// `lookback_len` bars.
+fn step_input(
+    path: &Path,
+    step: Option<usize>,
+    lookback_len: Option<usize>,
+) -> Result<EvalInputV1> {
+    let tape = load_tape(path)?;
+    let last = tape
+        .bars
+        .len()
+        .checked_sub(1)
+        .ok_or_else(|| anyhow!("no bars in {}", path.display()))?;
+    let step = step.unwrap_or(last);
+    if step > last {
+        return Err(anyhow!("--step {step} is past the last bar ({last})"));
+    }
+    let len = lookback_len
+        .unwrap_or(DEFAULT_LOOKBACK_LEN)
+        .clamp(1, step + 1);
+    Ok(tape.flat_input(step, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_rbpf::assembler::assemble;
+
+    /// `entrypoint` calls `function_sum` twice, which loops three times and
+    /// logs through a syscall.
+    const PROGRAM: &str = "
+        mov r6, 0
+        call function_sum
+        call function_sum
+        mov r0, 0
+        exit
+        function_sum:
+        mov r1, 3
+        loop:
+        add r6, r1
+        sub r1, 1
+        jne r1, 0, -3
+        syscall sol_log_compute_units_
+        exit";
+
+    /// Runs `PROGRAM` with no accounts under `compute_unit_limit`.
+    fn trace_program(compute_unit_limit: u32) -> Profile {
+        let environment = create_program_runtime_environment_v1(
+            &solana_sdk::feature_set::FeatureSet::all_enabled(),
+            &solana_program_runtime::compute_budget::ComputeBudget::default(),
+            false,
+            true,
+        )
+        .unwrap();
+        let executable =
+            assemble::<InvokeContext>(PROGRAM, std::sync::Arc::new(environment)).unwrap();
+        let names = symbols(&executable, None);
+        assert!(!names.1);
+
+        solana_program_runtime::with_mock_invoke_context!(
+            invoke_context,
+            transaction_context,
+            vec![(
+                Pubkey::new_unique(),
+                AccountSharedData::new(0, 0, &bpf_loader::id())
+            )]
+        );
+        invoke_context
+            .transaction_context
+            .get_next_instruction_context()
+            .unwrap()
+            .configure(&[0], &[], &[]);
+        invoke_context.push().unwrap();
+        execute_traced(&executable, &mut invoke_context, compute_unit_limit, names).unwrap()
+    }
+
+    #[test]
+    fn folds_calls_and_syscalls_into_stacks() {
+        let profile = trace_program(10_000);
+        assert_eq!(profile.error, None);
+        assert_eq!(profile.instructions, 29);
+        // The log syscall costs more than its call instruction, and that
+        // cost is charged to `function_sum`, which made the calls.
+        let syscall = (profile.compute_units - profile.instructions) / 2;
+        assert!(syscall > 0);
+
+        // entrypoint: 5 instructions; each call: mov, 3 x (add, sub, jne),
+        // syscall, exit.
+        let expected = BTreeMap::from([
+            ("entrypoint".to_string(), 5),
+            ("entrypoint;function_sum".to_string(), 2 * (12 + syscall)),
+        ]);
+        assert_eq!(profile.stacks, expected);
+        assert_eq!(profile.stacks.values().sum::<u64>(), profile.compute_units);
+
+        let functions = functions(&profile.stacks);
+        assert_eq!(functions[0].name, "function_sum");
+        assert_eq!(
+            (functions[0].self_units, functions[0].total_units),
+            (24 + 2 * syscall, 24 + 2 * syscall)
+        );
+        assert_eq!(
+            (functions[1].self_units, functions[1].total_units),
+            (5, profile.compute_units)
+        );
+    }
+
+    #[test]
+    fn names_subprograms_from_debug_info() {
+        use gimli::write::{Address, AttributeValue, DwarfUnit, EndianVec, Sections};
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut unit = DwarfUnit::new(encoding);
+        let root = unit.unit.root();
+        let mut subprogram = |attrs: Vec<(gimli::DwAt, AttributeValue)>| {
+            let id = unit.unit.add(root, gimli::DW_TAG_subprogram);
+            for (name, value) in attrs {
+                unit.unit.get_mut(id).set(name, value);
+            }
+            id
+        };
+        let string = |s: &str| AttributeValue::String(s.as_bytes().to_vec());
+        let low_pc = |addr| AttributeValue::Address(Address::Constant(addr));
+        // A declaration with the linkage name, defined out of line.
+        let decl = subprogram(vec![
+            (gimli::DW_AT_name, string("decide")),
+            (
+                gimli::DW_AT_linkage_name,
+                string("_ZN6policy6decide17h0123456789abcdefE"),
+            ),
+        ]);
+        subprogram(vec![
+            (gimli::DW_AT_low_pc, low_pc(0x140)),
+            (gimli::DW_AT_specification, AttributeValue::UnitRef(decl)),
+        ]);
+        subprogram(vec![
+            (gimli::DW_AT_low_pc, low_pc(0x180)),
+            (gimli::DW_AT_name, string("helper")),
+        ]);
+        // Declarations without code are not functions of the program.
+        subprogram(vec![(gimli::DW_AT_name, string("extern_fn"))]);
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        unit.write(&mut sections).expect("write dwarf");
+        let mut written = BTreeMap::new();
+        sections
+            .for_each(|id, data| {
+                written.insert(id.name(), data.slice().to_vec());
+                Ok::<_, gimli::Error>(())
+            })
+            .expect("sections");
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = written.get(id.name()).map_or(&[][..], Vec::as_slice);
+            Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+        })
+        .expect("load dwarf");
+
+        let names = dwarf_names(&dwarf).expect("names");
+        assert_eq!(
+            names,
+            BTreeMap::from([
+                (0x140, "_ZN6policy6decide17h0123456789abcdefE".to_string()),
+                (0x180, "helper".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn serve_profiles_stay_in_the_profile_directory() {
+        let dir = Path::new("profiles");
+        assert_eq!(
+            serve_out_path(Some(dir), "w1/agent.folded").unwrap(),
+            dir.join("w1/agent.folded")
+        );
+        for path in ["/tmp/x.folded", "../x.folded", "w1/../../x.folded", ""] {
+            assert!(serve_out_path(Some(dir), path).is_err(), "{path}");
+        }
+        assert!(serve_out_path(None, "x.folded").is_err());
+    }
+
+    #[test]
+    fn exhausted_budgets_keep_the_partial_trace() {
+        let profile = trace_program(8);
+        assert!(profile.error.is_some());
+        assert!(profile.instructions > 0 && profile.instructions <= 9);
+    }
+}
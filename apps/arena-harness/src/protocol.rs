@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
@@ -8,15 +9,69 @@ pub enum Request {
     Init(InitRequest),
     #[serde(rename = "eval")]
     Eval(EvalRequest),
+    #[serde(rename = "sweep")]
+    Sweep(SweepRequest),
+    #[serde(rename = "stats")]
+    Stats(StatsRequest),
     #[serde(rename = "shutdown")]
     Shutdown(ShutdownRequest),
 }
 
+impl Request {
+    pub fn request_id(&self) -> u64 {
+        match self {
+            Self::Init(req) => req.request_id,
+            Self::Eval(req) => req.request_id,
+            Self::Sweep(req) => req.request_id,
+            Self::Stats(req) => req.request_id,
+            Self::Shutdown(req) => req.request_id,
+        }
+    }
+
+    /// The request's `type` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Init(_) => "init",
+            Self::Eval(_) => "eval",
+            Self::Sweep(_) => "sweep",
+            Self::Stats(_) => "stats",
+            Self::Shutdown(_) => "shutdown",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct InitRequest {
     pub request_id: u64,
     pub programs: Vec<ProgramSpec>,
     pub compute_unit_limit: Option<u32>,
+    /// Arena bounds for per-program lookback requests.
+    pub min_lookback_len: Option<u16>,
+    pub max_lookback_len: Option<u16>,
+    /// Indicators precomputed into the features account each step.
+    #[serde(default)]
+    pub features: Vec<FeatureSpecJson>,
+    /// Upper bound on each `.so` size; defaults to 10 MiB.
+    pub max_program_bytes: Option<u64>,
+    /// Harness-native baseline policies to load, by id (e.g. `BUY_AND_HOLD`);
+    /// each is evaluated under its id like any program.
+    #[serde(default)]
+    pub baselines: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureSpecJson {
+    pub kind: FeatureKindJson,
+    pub period: u16,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureKindJson {
+    Sma,
+    Ema,
+    Atr,
+    RealizedVol,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,17 +79,73 @@ pub struct EvalRequest {
     pub request_id: u64,
     pub agent_id: String,
     pub input: EvalInputJson,
+    /// Traces this eval of a `.so` program and writes its folded stacks to
+    /// this path under the harness's `--profile-dir`; the result then
+    /// carries a `profile` summary.
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ShutdownRequest {
+pub struct SweepRequest {
+    pub request_id: u64,
+    pub agent_id: String,
+    /// The market input; its account fields are the grid's defaults.
+    pub input: EvalInputJson,
+    pub grid: SweepGridJson,
+}
+
+/// Values to try per account field. Cells are every combination, in field
+/// order with the last field varying fastest; omitted fields keep the
+/// input's value.
+#[serde_as]
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepGridJson {
+    #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
+    #[serde(default)]
+    pub cash_balance: Option<Vec<i64>>,
+    #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
+    #[serde(default)]
+    pub position_qty: Option<Vec<i64>>,
+    #[serde_as(as = "Option<Vec<DisplayFromStr>>")]
+    #[serde(default)]
+    pub avg_entry_price: Option<Vec<i64>>,
+    pub max_leverage_bps: Option<Vec<u32>>,
+    pub initial_margin_bps: Option<Vec<u32>>,
+    pub maintenance_margin_bps: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsRequest {
     pub request_id: u64,
 }
 
 #[derive(Debug, Deserialize)]
+pub struct ShutdownRequest {
+    pub request_id: u64,
+    /// Also writes the per-agent stats summary to this JSON file.
+    pub report: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct ProgramSpec {
     pub id: String,
-    pub so_path: String,
+    /// Path to the SBF `.so`; set exactly one of `so_path` and `builtin`.
+    pub so_path: Option<String>,
+    /// Name of a native test program from the builtin catalog.
+    pub builtin: Option<String>,
+    /// Overrides the lookback length the program declares, if any.
+    pub lookback_len: Option<u16>,
+}
+
+impl ProgramSpec {
+    /// The `.so` path, or `builtin:<name>`, for reports.
+    pub fn source(&self) -> String {
+        match (&self.so_path, &self.builtin) {
+            (Some(so_path), _) => so_path.clone(),
+            (None, builtin) => format!("builtin:{}", builtin.as_deref().unwrap_or_default()),
+        }
+    }
 }
 
 #[serde_as]
@@ -57,6 +168,8 @@ pub struct EvalInputJson {
     pub maintenance_margin_bps: u32,
     pub lookback_len: u16,
     pub ohlcv: Vec<BarJson>,
+    #[serde(default)]
+    pub depth: Option<DepthSnapshotJson>,
 }
 
 #[serde_as]
@@ -74,6 +187,21 @@ pub struct BarJson {
     pub volume: i64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DepthSnapshotJson {
+    pub bids: Vec<DepthLevelJson>,
+    pub asks: Vec<DepthLevelJson>,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct DepthLevelJson {
+    #[serde_as(as = "DisplayFromStr")]
+    pub price: i64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub size: i64,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type")]
 pub enum Response {
@@ -81,6 +209,10 @@ pub enum Response {
     Ok(OkResponse),
     #[serde(rename = "result")]
     Result(ResultResponse),
+    #[serde(rename = "sweep_result")]
+    SweepResult(SweepResponse),
+    #[serde(rename = "stats")]
+    Stats(StatsResponse),
     #[serde(rename = "error")]
     Error(ErrorResponse),
 }
@@ -88,6 +220,59 @@ pub enum Response {
 #[derive(Debug, Serialize)]
 pub struct OkResponse {
     pub request_id: u64,
+    /// Negotiated ABI versions per program, set on `init` responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub programs: Option<Vec<ProgramAbiJson>>,
+    /// Programs refused at load, set on `init` responses when non-empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<Vec<ProgramRejectionJson>>,
+    /// Per-agent stats since `init`, set on `shutdown` responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agents: Option<BTreeMap<String, AgentStatsJson>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    pub request_id: u64,
+    pub agents: BTreeMap<String, AgentStatsJson>,
+}
+
+/// Evals of one agent since `init` that reached its program.
+#[derive(Debug, Serialize)]
+pub struct AgentStatsJson {
+    pub evals: u64,
+    /// By serve status: `OK`, `SANDBOX_VIOLATION`, or `ERR` for evals
+    /// answered with an error.
+    pub statuses: BTreeMap<String, u64>,
+    /// Sandbox violations count as 13 and `ERR` evals as 5.
+    pub err_codes: BTreeMap<u16, u64>,
+    /// Over evals that completed.
+    pub compute_units: BenchStatsJson,
+    /// Non-empty buckets, each counting evals above the previous bound.
+    pub compute_unit_histogram: Vec<HistogramBucketJson>,
+    pub latency_us: BenchStatsJson,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucketJson {
+    /// Inclusive upper bound.
+    pub le: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProgramRejectionJson {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProgramAbiJson {
+    pub id: String,
+    pub input_version: u8,
+    pub output_version: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookback_len: Option<u16>,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,12 +281,103 @@ pub struct ResultResponse {
     pub agent_id: String,
     pub status: String,
     pub output: EvalOutputJson,
+    /// Why the eval was not `OK`, e.g. the sandbox rule a program broke.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The ABI rule an invalid output broke; the output is then a HOLD.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Box<ProfileJson>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SweepResponse {
+    pub request_id: u64,
+    pub agent_id: String,
+    /// One per grid cell, in grid order.
+    pub cells: Vec<SweepCellJson>,
+}
+
+/// The account state a sweep cell was evaluated under.
+#[serde_as]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SweepAccountJson {
+    #[serde_as(as = "DisplayFromStr")]
+    pub cash_balance: i64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub position_qty: i64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub avg_entry_price: i64,
+    pub max_leverage_bps: u32,
+    pub initial_margin_bps: u32,
+    pub maintenance_margin_bps: u32,
+}
+
+/// A cell's account fields, then its result as in a `result` response;
+/// failures other than sandbox violations are `ERR` HOLDs with a `reason`.
+#[derive(Debug, Serialize)]
+pub struct SweepCellJson {
+    #[serde(flatten)]
+    pub account: SweepAccountJson,
+    pub status: String,
+    pub output: EvalOutputJson,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_reason: Option<String>,
+}
+
+/// An instruction-level profile of one eval. Function units are compute
+/// units: one per executed instruction, plus the cost of each syscall the
+/// function made, so they add up to `compute_units`. `syscall_units` is the
+/// syscalls' share.
+#[derive(Debug, Serialize)]
+pub struct ProfileJson {
+    pub folded_path: String,
+    pub compute_units: u64,
+    pub instructions: u64,
+    pub syscall_units: u64,
+    /// Whether names came from the `.so` debug info or symbol table;
+    /// otherwise only functions the loader registered are named.
+    pub symbolized: bool,
+    /// Why the traced run failed; the profile covers it up to the failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// By `self_units`, highest first.
+    pub functions: Vec<ProfileFunctionJson>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileFunctionJson {
+    pub name: String,
+    /// Spent in the function itself, including its syscalls.
+    pub self_units: u64,
+    /// Including its callees.
+    pub total_units: u64,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub request_id: u64,
     pub message: String,
+    /// `INVALID_INPUT` when the client sent an input the harness refuses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// The offending input field, e.g. `ohlcv[3].high`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(request_id: u64, message: String) -> Self {
+        Self {
+            request_id,
+            message,
+            code: None,
+            field: None,
+        }
+    }
 }
 
 #[serde_as]
@@ -112,4 +388,22 @@ pub struct EvalOutputJson {
     #[serde_as(as = "DisplayFromStr")]
     pub order_qty: i64,
     pub err_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<u8>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force_bars: Option<u16>,
+    /// Hex-encoded annotation bytes from version 3 outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BenchStatsJson {
+    pub min: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub max: u64,
 }
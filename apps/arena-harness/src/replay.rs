@@ -0,0 +1,322 @@
+//! Replay data for `backtest`: tape files, bar integrity checks and window
+//! construction as in `packages/data` (data-ops spec §1-2). Only sequential
+//! window sampling is supported.
+
+use crate::abi::{Bar, EvalInputV1};
+use crate::config::{ArenaConfig, SamplingMode};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayBar {
+    pub bar_start_ts_ms: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Instrument {
+    pub price_scale: Option<u32>,
+    pub volume_scale: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Tape {
+    pub instrument: Option<Instrument>,
+    pub bars: Vec<ReplayBar>,
+}
+
+const DEFAULT_SCALE: u32 = 1_000_000;
+/// The default arena config's `lookback_len`, for tape inputs of offline
+/// subcommands.
+pub const DEFAULT_LOOKBACK_LEN: usize = 120;
+
+impl Tape {
+    /// A v1 input at bar `step` seeing the `len` bars that end there, for a
+    /// flat account at the default config's balance and margins. Offline
+    /// subcommands that do not simulate an account (bench, profile, diff)
+    /// feed these.
+    pub fn flat_input(&self, step: usize, len: usize) -> EvalInputV1 {
+        let instrument = self.instrument.as_ref();
+        let bar_interval_ms = match self.bars.as_slice() {
+            [first, second, ..] => second.bar_start_ts_ms - first.bar_start_ts_ms,
+            _ => 60_000,
+        };
+        let ohlcv: Vec<Bar> = self.bars[step + 1 - len..=step]
+            .iter()
+            .map(|bar| Bar {
+                open: bar.open as i64,
+                high: bar.high as i64,
+                low: bar.low as i64,
+                close: bar.close as i64,
+                volume: bar.volume as i64,
+            })
+            .collect();
+        EvalInputV1 {
+            version: 1,
+            window_id: [0; 32],
+            step_index: step as u32,
+            bar_interval_seconds: (bar_interval_ms / 1000) as u32,
+            price_scale: instrument
+                .and_then(|i| i.price_scale)
+                .unwrap_or(DEFAULT_SCALE),
+            volume_scale: instrument
+                .and_then(|i| i.volume_scale)
+                .unwrap_or(DEFAULT_SCALE),
+            cash_balance: 10_000,
+            position_qty: 0,
+            avg_entry_price: 0,
+            max_leverage_bps: 10_000,
+            initial_margin_bps: 1_000,
+            maintenance_margin_bps: 500,
+            lookback_len: ohlcv.len() as u16,
+            ohlcv,
+            depth: None,
+            features: None,
+        }
+    }
+}
+
+/// A contiguous run of bars, `start..=end` in tape indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub id: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug)]
+pub struct BarError {
+    pub index: usize,
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Loads a JSON tape (a bar array or `{instrument, bars}`) or, for `.jsonl`
+/// paths, one bar per line with an optional leading `{"instrument": ...}`.
+pub fn load_tape(path: &Path) -> Result<Tape> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read data {}", path.display()))?;
+    let parse = |text: &str| -> Result<Tape> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum TapeJson {
+            Bars(Vec<ReplayBar>),
+            WithMeta(Tape),
+        }
+        Ok(match serde_json::from_str(text)? {
+            TapeJson::Bars(bars) => Tape {
+                instrument: None,
+                bars,
+            },
+            TapeJson::WithMeta(tape) => tape,
+        })
+    };
+    if path.extension().is_some_and(|ext| ext == "jsonl") {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .peekable();
+        let mut tape = Tape {
+            instrument: None,
+            bars: Vec::new(),
+        };
+        // JSONL may start with a metadata header, or hold a whole JSON tape.
+        if let Some(first) = lines.peek() {
+            let value: serde_json::Value = serde_json::from_str(first)
+                .with_context(|| format!("invalid line in {}", path.display()))?;
+            if let Some(instrument) = value.get("instrument") {
+                if value.get("bars").is_some_and(serde_json::Value::is_array) {
+                    return parse(first);
+                }
+                tape.instrument = Some(serde_json::from_value(instrument.clone())?);
+                lines.next();
+            }
+        }
+        for line in lines {
+            tape.bars.push(
+                serde_json::from_str(line)
+                    .with_context(|| format!("invalid bar in {}", path.display()))?,
+            );
+        }
+        return Ok(tape);
+    }
+    parse(&text).with_context(|| {
+        format!(
+            "expected array of bars or {{instrument, bars}} in {}",
+            path.display()
+        )
+    })
+}
+
+/// Integrity failures of `bars`, in tape order (data-ops spec §1.3).
+pub fn validate_bars(bars: &[ReplayBar], bar_interval_ms: i64) -> Vec<BarError> {
+    let mut errors = Vec::new();
+    let mut push = |index, field, message: String| {
+        errors.push(BarError {
+            index,
+            field,
+            message,
+        })
+    };
+    for (index, bar) in bars.iter().enumerate() {
+        for (field, value) in [
+            ("open", bar.open),
+            ("high", bar.high),
+            ("low", bar.low),
+            ("close", bar.close),
+        ] {
+            if value <= 0.0 {
+                push(index, field, format!("{field} must be > 0"));
+            }
+        }
+        if bar.volume < 0.0 {
+            push(index, "volume", "volume must be >= 0".to_string());
+        }
+        if bar.low > bar.open {
+            push(index, "low", "low must be <= open".to_string());
+        }
+        if bar.low > bar.close {
+            push(index, "low", "low must be <= close".to_string());
+        }
+        if bar.high < bar.open {
+            push(index, "high", "high must be >= open".to_string());
+        }
+        if bar.high < bar.close {
+            push(index, "high", "high must be >= close".to_string());
+        }
+        if let Some(prev) = index.checked_sub(1).map(|i| &bars[i]) {
+            let expected = prev.bar_start_ts_ms + bar_interval_ms;
+            if bar.bar_start_ts_ms != expected {
+                push(
+                    index,
+                    "bar_start_ts_ms",
+                    format!(
+                        "expected {expected}, got {} (non-contiguous)",
+                        bar.bar_start_ts_ms
+                    ),
+                );
+            }
+        }
+    }
+    errors
+}
+
+fn build_windows(bar_count: usize, duration: usize, max_overlap_pct: u32) -> Vec<Window> {
+    if duration == 0 || bar_count < duration {
+        return Vec::new();
+    }
+    let step = ((duration as f64 * (1.0 - max_overlap_pct as f64 / 100.0)).floor() as usize).max(1);
+    (0..=bar_count - duration)
+        .step_by(step)
+        .enumerate()
+        .map(|(n, start)| Window {
+            id: format!("w{n}"),
+            start,
+            end: start + duration - 1,
+        })
+        .collect()
+}
+
+fn invalid_reason(window: &Window, errors: &[BarError]) -> Option<String> {
+    let mut in_window = errors
+        .iter()
+        .filter(|e| e.index >= window.start && e.index <= window.end);
+    let first = in_window.next()?;
+    Some(format!(
+        "bar_validation_failed: index={} field={} message={} (count={})",
+        first.index,
+        first.field,
+        first.message,
+        in_window.count() + 1
+    ))
+}
+
+/// The round's windows, with the same checks and errors as the TS runner:
+/// windows containing bars that fail integrity checks are skipped and the
+/// first `number_of_windows_per_round` valid ones are used.
+pub fn select_windows(config: &ArenaConfig, bars: &[ReplayBar]) -> Result<Vec<Window>> {
+    let errors = validate_bars(bars, config.bar_interval_seconds as i64 * 1000);
+    let windows = build_windows(
+        bars.len(),
+        config.window_duration_bars,
+        config.max_window_overlap_pct,
+    );
+    if windows.is_empty() {
+        return Err(anyhow!(
+            "No valid windows: {} bars < {} window_duration_bars",
+            bars.len(),
+            config.window_duration_bars
+        ));
+    }
+    let valid: Vec<Window> = windows
+        .into_iter()
+        .filter(|window| invalid_reason(window, &errors).is_none())
+        .collect();
+    let total = config.number_of_windows_per_round;
+    if valid.is_empty() {
+        return Err(anyhow!("No valid windows after bar integrity checks"));
+    }
+    if valid.len() < total {
+        return Err(anyhow!(
+            "Not enough valid windows ({}) for number_of_windows_per_round={total}",
+            valid.len()
+        ));
+    }
+    if valid.len() > total && config.window_sampling.mode == SamplingMode::Stratified {
+        return Err(anyhow!(
+            "stratified window sampling is not supported offline; use \"sequential\""
+        ));
+    }
+    Ok(valid.into_iter().take(total).collect())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::config::tests::sample_config;
+
+    pub fn flat_bars(count: usize, price: f64) -> Vec<ReplayBar> {
+        (0..count)
+            .map(|i| ReplayBar {
+                bar_start_ts_ms: i as i64 * 60_000,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 10.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn windows_step_by_overlap_and_skip_invalid_bars() {
+        let ids = |windows: Vec<Window>| -> Vec<(usize, usize)> {
+            windows.iter().map(|w| (w.start, w.end)).collect()
+        };
+        assert_eq!(ids(build_windows(10, 4, 0)), [(0, 3), (4, 7)]);
+        assert_eq!(ids(build_windows(6, 4, 50)), [(0, 3), (2, 5)]);
+        assert!(build_windows(3, 4, 0).is_empty());
+
+        let mut config = sample_config();
+        config.window_duration_bars = 4;
+        config.lookback_len = 2;
+        config.number_of_windows_per_round = 1;
+        let mut bars = flat_bars(8, 100.0);
+        bars[1].low = 101.0;
+        let windows = select_windows(&config, &bars).unwrap();
+        assert_eq!(windows[0].id, "w1");
+
+        bars[5].bar_start_ts_ms += 1;
+        let err = select_windows(&config, &bars).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No valid windows after bar integrity checks"
+        );
+    }
+}
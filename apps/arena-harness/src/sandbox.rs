@@ -0,0 +1,117 @@
+use solana_sdk::account::Account;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+/// Logged by the runtime when a program is entered at depth 2, i.e. through
+/// a cross-program invocation.
+const CPI_LOG_MARKER: &str = " invoke [2]";
+
+/// Maps runtime errors raised when a program touches accounts outside its
+/// input/output pair (or writes the read-only input) to a violation reason.
+pub fn classify_error(err: &TransactionError) -> Option<String> {
+    let TransactionError::InstructionError(_, ix_err) = err else {
+        return None;
+    };
+    let reason = match ix_err {
+        InstructionError::ReadonlyDataModified
+        | InstructionError::ReadonlyLamportChange
+        | InstructionError::ExternalAccountDataModified
+        | InstructionError::ExternalAccountLamportSpend => "modified a read-only account",
+        InstructionError::ModifiedProgramId => "changed an account owner",
+        InstructionError::AccountDataSizeChanged | InstructionError::InvalidRealloc => {
+            "resized an account"
+        }
+        InstructionError::MissingAccount
+        | InstructionError::NotEnoughAccountKeys
+        | InstructionError::UnsupportedProgramId
+        | InstructionError::PrivilegeEscalation
+        | InstructionError::ReentrancyNotAllowed
+        | InstructionError::CallDepth => "accessed an account outside the sandbox",
+        _ => return None,
+    };
+    Some(format!("{reason} ({ix_err})"))
+}
+
+/// Reports the first cross-program invocation in the transaction logs.
+pub fn check_logs(logs: &[String]) -> Option<String> {
+    logs.iter()
+        .find(|line| line.ends_with(CPI_LOG_MARKER))
+        .map(|line| format!("cross-program invocation: {line}"))
+}
+
+/// Verifies the accounts after a successful eval: read-only accounts are
+/// byte-identical, and the output keeps its length and owner.
+pub fn check_accounts(
+    program_id: &Pubkey,
+    readonly: &[(&[u8], Option<Account>)],
+    output: &Account,
+    output_len: usize,
+) -> Option<String> {
+    for (expected, account) in readonly {
+        match account {
+            Some(account) if account.data.as_slice() == *expected => {}
+            Some(_) => return Some("read-only account data changed".to_string()),
+            None => return Some("read-only account removed".to_string()),
+        }
+    }
+    if output.data.len() != output_len {
+        return Some(format!(
+            "output account resized from {output_len} to {} bytes",
+            output.data.len()
+        ));
+    }
+    if output.owner != *program_id {
+        return Some(format!("output account reassigned to {}", output.owner));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_errors_and_cpi_logs_map_to_violations() {
+        let readonly =
+            TransactionError::InstructionError(1, InstructionError::ReadonlyDataModified);
+        assert!(classify_error(&readonly)
+            .unwrap()
+            .starts_with("modified a read-only account"));
+        let custom = TransactionError::InstructionError(1, InstructionError::Custom(1));
+        assert_eq!(classify_error(&custom), None);
+
+        let logs = vec![
+            "Program agent111 invoke [1]".to_string(),
+            "Program 11111111111111111111111111111111 invoke [2]".to_string(),
+        ];
+        assert!(check_logs(&logs).is_some());
+        assert_eq!(check_logs(&logs[..1]), None);
+    }
+
+    #[test]
+    fn account_checks_catch_input_and_output_tampering() {
+        let program_id = Pubkey::new_unique();
+        let account = |data: Vec<u8>, owner: Pubkey| Account {
+            lamports: 1,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let output = account(vec![0; 4], program_id);
+        let input = [1u8, 2, 3];
+
+        let intact = [(&input[..], Some(account(input.to_vec(), program_id)))];
+        assert_eq!(check_accounts(&program_id, &intact, &output, 4), None);
+
+        let tampered = [(&input[..], Some(account(vec![9, 2, 3], program_id)))];
+        assert!(check_accounts(&program_id, &tampered, &output, 4).is_some());
+
+        let resized = account(vec![0; 8], program_id);
+        assert!(check_accounts(&program_id, &intact, &resized, 4).is_some());
+
+        let reassigned = account(vec![0; 4], Pubkey::new_unique());
+        assert!(check_accounts(&program_id, &intact, &reassigned, 4).is_some());
+    }
+}
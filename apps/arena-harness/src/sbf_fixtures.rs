@@ -0,0 +1,149 @@
+//! Minimal SBPFv1 programs written straight to ELF, so tests can run real
+//! SBF code (metered per instruction) without `cargo build-sbf`.
+
+use std::path::{Path, PathBuf};
+
+const HEADER_LEN: usize = 64;
+const PROGRAM_HEADER_LEN: usize = 56;
+const SECTION_HEADER_LEN: usize = 64;
+const SYMBOL_LEN: usize = 24;
+const EM_BPF: u16 = 247;
+const ET_DYN: u16 = 3;
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHF_ALLOC_EXECINSTR: u64 = 0x2 | 0x4;
+const STT_FUNC_GLOBAL: u8 = 0x12;
+
+fn insn(opc: u8, regs: u8, off: i16, imm: i32) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    bytes[0] = opc;
+    bytes[1] = regs;
+    bytes[2..4].copy_from_slice(&off.to_le_bytes());
+    bytes[4..].copy_from_slice(&imm.to_le_bytes());
+    bytes
+}
+
+/// Counts `r1` down from `iterations` and exits with 0; without
+/// `iterations` it jumps to itself until the budget runs out. Either way
+/// the output account is left untouched.
+pub fn looping_program(dir: &Path, iterations: Option<i32>) -> PathBuf {
+    let text: Vec<[u8; 8]> = match iterations {
+        Some(n) => vec![
+            insn(0xb7, 0x01, 0, n),  // mov64 r1, n
+            insn(0x17, 0x01, 0, 1),  // sub64 r1, 1
+            insn(0x55, 0x01, -2, 0), // jne r1, 0, -2
+            insn(0xb7, 0x00, 0, 0),  // mov64 r0, 0
+            insn(0x95, 0x00, 0, 0),  // exit
+        ],
+        None => vec![insn(0x05, 0x00, -1, 0)], // ja -1
+    };
+    let name = match iterations {
+        Some(n) => format!("loop_{n}.so"),
+        None => "loop_forever.so".to_string(),
+    };
+    let path = dir.join(name);
+    std::fs::write(&path, elf(&text.concat())).expect("write program");
+    path
+}
+
+/// A section header; fields left out are zero.
+#[derive(Default)]
+struct Section {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    addr: usize,
+    offset: usize,
+    size: usize,
+    link: u32,
+    info: u32,
+    entsize: usize,
+}
+
+/// A shared object with `.text` at its file offset and an `entrypoint`
+/// symbol at its start.
+fn elf(text: &[u8]) -> Vec<u8> {
+    let text_offset = HEADER_LEN;
+    let strtab = b"\0entrypoint\0";
+    let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+    let symtab_offset = (text_offset + text.len()).next_multiple_of(8);
+    let strtab_offset = symtab_offset + 2 * SYMBOL_LEN;
+    let shstrtab_offset = strtab_offset + strtab.len();
+    let sections_offset = (shstrtab_offset + shstrtab.len()).next_multiple_of(8);
+    let sections = [
+        Section::default(),
+        Section {
+            name: 1,
+            kind: SHT_PROGBITS,
+            flags: SHF_ALLOC_EXECINSTR,
+            addr: text_offset,
+            offset: text_offset,
+            size: text.len(),
+            ..Section::default()
+        },
+        Section {
+            name: 7,
+            kind: SHT_SYMTAB,
+            offset: symtab_offset,
+            size: 2 * SYMBOL_LEN,
+            link: 3,
+            info: 1,
+            entsize: SYMBOL_LEN,
+            ..Section::default()
+        },
+        Section {
+            name: 15,
+            kind: SHT_STRTAB,
+            offset: strtab_offset,
+            size: strtab.len(),
+            ..Section::default()
+        },
+        Section {
+            name: 23,
+            kind: SHT_STRTAB,
+            offset: shstrtab_offset,
+            size: shstrtab.len(),
+            ..Section::default()
+        },
+    ];
+
+    let mut bytes = vec![0u8; sections_offset + sections.len() * SECTION_HEADER_LEN];
+    let mut put = |at: usize, data: &[u8]| bytes[at..at + data.len()].copy_from_slice(data);
+    put(0, b"\x7fELF\x02\x01\x01");
+    put(16, &ET_DYN.to_le_bytes());
+    put(18, &EM_BPF.to_le_bytes());
+    put(20, &1u32.to_le_bytes());
+    put(24, &(text_offset as u64).to_le_bytes());
+    put(40, &(sections_offset as u64).to_le_bytes());
+    put(52, &(HEADER_LEN as u16).to_le_bytes());
+    put(54, &(PROGRAM_HEADER_LEN as u16).to_le_bytes());
+    put(58, &(SECTION_HEADER_LEN as u16).to_le_bytes());
+    put(60, &(sections.len() as u16).to_le_bytes());
+    put(62, &(sections.len() as u16 - 1).to_le_bytes());
+
+    put(text_offset, text);
+    let entrypoint = symtab_offset + SYMBOL_LEN;
+    put(entrypoint, &1u32.to_le_bytes());
+    put(entrypoint + 4, &[STT_FUNC_GLOBAL]);
+    put(entrypoint + 6, &1u16.to_le_bytes());
+    put(entrypoint + 8, &(text_offset as u64).to_le_bytes());
+    put(entrypoint + 16, &(text.len() as u64).to_le_bytes());
+    put(strtab_offset, strtab);
+    put(shstrtab_offset, shstrtab);
+
+    for (index, section) in sections.iter().enumerate() {
+        let at = sections_offset + index * SECTION_HEADER_LEN;
+        put(at, &section.name.to_le_bytes());
+        put(at + 4, &section.kind.to_le_bytes());
+        put(at + 8, &section.flags.to_le_bytes());
+        put(at + 16, &(section.addr as u64).to_le_bytes());
+        put(at + 24, &(section.offset as u64).to_le_bytes());
+        put(at + 32, &(section.size as u64).to_le_bytes());
+        put(at + 40, &section.link.to_le_bytes());
+        put(at + 44, &section.info.to_le_bytes());
+        put(at + 48, &8u64.to_le_bytes());
+        put(at + 56, &(section.entsize as u64).to_le_bytes());
+    }
+    bytes
+}
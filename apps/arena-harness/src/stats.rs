@@ -0,0 +1,126 @@
+//! Per-agent eval statistics since `init`, answered by `stats` requests and
+//! summarized on `shutdown`.
+
+use crate::bench::stats;
+use crate::protocol::{AgentStatsJson, HistogramBucketJson};
+use crate::status::{self, Failure};
+use crate::EvalRun;
+use std::collections::BTreeMap;
+
+/// Upper bounds of the compute unit histogram buckets; the last one is the
+/// runtime maximum.
+const COMPUTE_UNIT_BUCKETS: &[u64] = &[
+    1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000, 200_000, 500_000, 1_000_000, 1_400_000,
+];
+
+#[derive(Default)]
+pub struct AgentStats {
+    evals: u64,
+    statuses: BTreeMap<&'static str, u64>,
+    err_codes: BTreeMap<u16, u64>,
+    compute_units: Vec<u64>,
+    latency_us: Vec<u64>,
+}
+
+impl AgentStats {
+    /// Counts one eval or sweep cell that reached the program by its status
+    /// (see `status`); failed evals count under the err code of their HOLD.
+    pub fn record(&mut self, run: &EvalRun) {
+        let (status, err_code) = match &run.result {
+            Ok(evaluated) => (status::OK, evaluated.output.err_code),
+            Err(err) => {
+                let failure = Failure::of(err);
+                (failure.status, failure.err_code)
+            }
+        };
+        self.compute_units.extend(run.compute_units);
+        self.evals += 1;
+        *self.statuses.entry(status).or_default() += 1;
+        *self.err_codes.entry(err_code).or_default() += 1;
+        self.latency_us.push(run.duration.as_micros() as u64);
+    }
+
+    /// Compute units cover every eval whose transaction ran, failed and
+    /// compute-exceeded ones included; buckets without evals are left out.
+    pub fn json(&self) -> AgentStatsJson {
+        let mut histogram: BTreeMap<u64, u64> = BTreeMap::new();
+        for &cu in &self.compute_units {
+            let le = COMPUTE_UNIT_BUCKETS
+                .iter()
+                .copied()
+                .find(|&le| cu <= le)
+                .unwrap_or(u64::MAX);
+            *histogram.entry(le).or_default() += 1;
+        }
+        AgentStatsJson {
+            evals: self.evals,
+            statuses: self
+                .statuses
+                .iter()
+                .map(|(status, count)| (status.to_string(), *count))
+                .collect(),
+            err_codes: self.err_codes.clone(),
+            compute_units: stats(&self.compute_units),
+            compute_unit_histogram: histogram
+                .into_iter()
+                .map(|(le, count)| HistogramBucketJson { le, count })
+                .collect(),
+            latency_us: stats(&self.latency_us),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HarnessError;
+    use crate::Evaluated;
+    use anyhow::{anyhow, Result};
+    use std::time::Duration;
+
+    fn run(result: Result<Evaluated>, compute_units: Option<u64>, ms: u64) -> EvalRun {
+        EvalRun {
+            result,
+            compute_units,
+            duration: Duration::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn counts_statuses_err_codes_and_compute_buckets() {
+        let mut agent = AgentStats::default();
+        let bad_qty = || Ok(Evaluated::rejected(15, "bad qty"));
+        agent.record(&run(bad_qty(), Some(1_500), 1));
+        agent.record(&run(bad_qty(), Some(2_000), 3));
+        let bad_version = Ok(Evaluated::rejected(6, "bad version"));
+        agent.record(&run(bad_version, Some(300_000), 2));
+        let sandbox = anyhow!(HarnessError::SandboxViolation("cpi".to_string()));
+        agent.record(&run(Err(sandbox), Some(900), 4));
+        let exhausted = anyhow!(HarnessError::ComputeExceeded(200_000));
+        agent.record(&run(Err(exhausted), Some(200_000), 5));
+        let never_ran = anyhow!(HarnessError::EvalFailed("banks client".to_string()));
+        agent.record(&run(Err(never_ran), None, 6));
+
+        let json = serde_json::to_value(agent.json()).unwrap();
+        assert_eq!(json["evals"], 6);
+        assert_eq!(
+            json["statuses"],
+            serde_json::json!({ "ERR": 2, "OK": 3, "SANDBOX_VIOLATION": 1 })
+        );
+        assert_eq!(
+            json["err_codes"],
+            serde_json::json!({ "5": 2, "6": 1, "13": 1, "15": 2 })
+        );
+        assert_eq!(
+            json["compute_unit_histogram"],
+            serde_json::json!([
+                { "le": 1000, "count": 1 },
+                { "le": 2000, "count": 2 },
+                { "le": 200000, "count": 1 },
+                { "le": 500000, "count": 1 }
+            ])
+        );
+        assert_eq!(json["compute_units"]["max"], 300_000);
+        assert_eq!(json["latency_us"]["p50"], 3_000);
+    }
+}
@@ -0,0 +1,50 @@
+//! The statuses of evals that reached the program, shared by serve results,
+//! sweep cells, diff sides, the serve and backtest policy logs and agent
+//! stats, so every report names an outcome the same way.
+
+use crate::abi::{EvalOutputV3, ERR_POLICY_ERR, ERR_SANDBOX_VIOLATION};
+use crate::error::HarnessError;
+
+/// The program answered; its output may still have been rejected as a HOLD.
+pub const OK: &str = "OK";
+pub const SANDBOX_VIOLATION: &str = "SANDBOX_VIOLATION";
+/// The eval failed outright (abort, compute exhausted, ...).
+pub const ERR: &str = "ERR";
+
+/// How a failed eval is reported: its status, the err code of the HOLD that
+/// stands in for its output, and its failure class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Failure {
+    pub status: &'static str,
+    pub err_code: u16,
+    /// `sandbox_violation`, `compute_exceeded` or `abort`.
+    pub class: &'static str,
+}
+
+impl Failure {
+    /// Sandbox violations are `SANDBOX_VIOLATION` HOLDs with err code 13;
+    /// every other failure is an `ERR` HOLD with err code 5.
+    pub fn of(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<HarnessError>() {
+            Some(HarnessError::SandboxViolation(_)) => Self {
+                status: SANDBOX_VIOLATION,
+                err_code: ERR_SANDBOX_VIOLATION,
+                class: "sandbox_violation",
+            },
+            Some(HarnessError::ComputeExceeded(_)) => Self {
+                status: ERR,
+                err_code: ERR_POLICY_ERR,
+                class: "compute_exceeded",
+            },
+            _ => Self {
+                status: ERR,
+                err_code: ERR_POLICY_ERR,
+                class: "abort",
+            },
+        }
+    }
+
+    pub fn hold(&self) -> EvalOutputV3 {
+        EvalOutputV3::hold(self.err_code)
+    }
+}
@@ -0,0 +1,210 @@
+//! Counterfactual `sweep` requests: one market input evaluated under every
+//! account state of a grid, for sensitivity maps such as "what would the
+//! agent do if it were long 2 BTC here".
+
+use crate::abi::EvalInputV1;
+use crate::error::HarnessError;
+use crate::input_rules;
+use crate::protocol::{SweepAccountJson, SweepGridJson};
+use anyhow::{anyhow, Result};
+
+/// Upper bound on cells per request; each cell is one eval.
+pub const MAX_SWEEP_CELLS: usize = 10_000;
+
+/// Rejects grids with an empty axis or more than `MAX_SWEEP_CELLS` cells.
+pub fn check_size(grid: &SweepGridJson) -> Result<()> {
+    let lens = [
+        ("cash_balance", grid.cash_balance.as_ref().map(Vec::len)),
+        ("position_qty", grid.position_qty.as_ref().map(Vec::len)),
+        (
+            "avg_entry_price",
+            grid.avg_entry_price.as_ref().map(Vec::len),
+        ),
+        (
+            "max_leverage_bps",
+            grid.max_leverage_bps.as_ref().map(Vec::len),
+        ),
+        (
+            "initial_margin_bps",
+            grid.initial_margin_bps.as_ref().map(Vec::len),
+        ),
+        (
+            "maintenance_margin_bps",
+            grid.maintenance_margin_bps.as_ref().map(Vec::len),
+        ),
+    ];
+    let mut cells: usize = 1;
+    for (field, len) in lens {
+        match len {
+            Some(0) => return Err(anyhow!("sweep grid has no values for {field}")),
+            Some(len) => cells = cells.saturating_mul(len),
+            None => {}
+        }
+    }
+    if cells > MAX_SWEEP_CELLS {
+        return Err(anyhow!(
+            "sweep grid has {cells} cells; at most {MAX_SWEEP_CELLS} are allowed"
+        ));
+    }
+    Ok(())
+}
+
+/// Every combination of the grid's values, in field order with the last
+/// field varying fastest; omitted fields keep `input`'s value.
+pub fn accounts(grid: &SweepGridJson, input: &EvalInputV1) -> Vec<SweepAccountJson> {
+    let base = SweepAccountJson {
+        cash_balance: input.cash_balance,
+        position_qty: input.position_qty,
+        avg_entry_price: input.avg_entry_price,
+        max_leverage_bps: input.max_leverage_bps,
+        initial_margin_bps: input.initial_margin_bps,
+        maintenance_margin_bps: input.maintenance_margin_bps,
+    };
+    let mut cells = vec![base];
+    expand(&mut cells, &grid.cash_balance, |a, v| a.cash_balance = v);
+    expand(&mut cells, &grid.position_qty, |a, v| a.position_qty = v);
+    expand(&mut cells, &grid.avg_entry_price, |a, v| {
+        a.avg_entry_price = v
+    });
+    expand(&mut cells, &grid.max_leverage_bps, |a, v| {
+        a.max_leverage_bps = v
+    });
+    expand(&mut cells, &grid.initial_margin_bps, |a, v| {
+        a.initial_margin_bps = v
+    });
+    expand(&mut cells, &grid.maintenance_margin_bps, |a, v| {
+        a.maintenance_margin_bps = v
+    });
+    cells
+}
+
+/// Runs every cell through the input rules for account state, so a grid
+/// that pairs e.g. a maintenance margin above an initial margin is refused
+/// as `INVALID_INPUT` on `grid.<field>` before any cell is evaluated.
+pub fn check_cells(cells: &[SweepAccountJson]) -> Result<()> {
+    for (index, cell) in cells.iter().enumerate() {
+        if let Err(HarnessError::InvalidInput { field, reason }) =
+            input_rules::check_account(cell.initial_margin_bps, cell.maintenance_margin_bps)
+        {
+            return Err(anyhow!(HarnessError::InvalidInput {
+                field: format!("grid.{field}"),
+                reason: format!("{reason} (cell {index})"),
+            }));
+        }
+    }
+    Ok(())
+}
+
+fn expand<T: Copy>(
+    cells: &mut Vec<SweepAccountJson>,
+    values: &Option<Vec<T>>,
+    set: impl Fn(&mut SweepAccountJson, T),
+) {
+    let Some(values) = values else {
+        return;
+    };
+    *cells = cells
+        .iter()
+        .flat_map(|cell| {
+            values.iter().map(|&value| {
+                let mut cell = *cell;
+                set(&mut cell, value);
+                cell
+            })
+        })
+        .collect();
+}
+
+pub fn apply(account: &SweepAccountJson, input: &mut EvalInputV1) {
+    input.cash_balance = account.cash_balance;
+    input.position_qty = account.position_qty;
+    input.avg_entry_price = account.avg_entry_price;
+    input.max_leverage_bps = account.max_leverage_bps;
+    input.initial_margin_bps = account.initial_margin_bps;
+    input.maintenance_margin_bps = account.maintenance_margin_bps;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::tests::flat_bars;
+    use crate::replay::Tape;
+
+    fn grid(json: &str) -> SweepGridJson {
+        serde_json::from_str(json).expect("grid")
+    }
+
+    fn input() -> EvalInputV1 {
+        let tape = Tape {
+            instrument: None,
+            bars: flat_bars(3, 100.0),
+        };
+        tape.flat_input(2, 3)
+    }
+
+    #[test]
+    fn cells_cover_every_combination_last_field_fastest() {
+        let input = input();
+        let grid = grid(r#"{"position_qty": ["-2", "0", "2"], "initial_margin_bps": [500, 1000]}"#);
+        check_size(&grid).unwrap();
+        let cells = accounts(&grid, &input);
+        let keys: Vec<(i64, u32)> = cells
+            .iter()
+            .map(|c| (c.position_qty, c.initial_margin_bps))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (-2, 500),
+                (-2, 1000),
+                (0, 500),
+                (0, 1000),
+                (2, 500),
+                (2, 1000)
+            ]
+        );
+        assert!(cells.iter().all(|c| c.cash_balance == input.cash_balance));
+
+        let mut shifted = input.clone();
+        apply(&cells[0], &mut shifted);
+        assert_eq!(
+            (shifted.position_qty, shifted.initial_margin_bps),
+            (-2, 500)
+        );
+        assert_eq!(shifted.ohlcv, input.ohlcv);
+    }
+
+    #[test]
+    fn rejects_empty_axes_oversized_grids_and_unknown_fields() {
+        assert_eq!(accounts(&grid("{}"), &input()).len(), 1);
+        let err = check_size(&grid(r#"{"cash_balance": []}"#)).unwrap_err();
+        assert_eq!(err.to_string(), "sweep grid has no values for cash_balance");
+
+        let values: Vec<String> = (0..101).map(|v| format!("\"{v}\"")).collect();
+        let axis = values.join(",");
+        let big = grid(&format!(
+            r#"{{"cash_balance": [{axis}], "position_qty": [{axis}]}}"#
+        ));
+        assert!(check_size(&big).is_err());
+        assert!(serde_json::from_str::<SweepGridJson>(r#"{"position": ["1"]}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_cells_that_break_the_input_rules() {
+        let input = input();
+        let ok =
+            grid(r#"{"initial_margin_bps": [500, 1000], "maintenance_margin_bps": [250, 500]}"#);
+        check_cells(&accounts(&ok, &input)).unwrap();
+
+        let bad =
+            grid(r#"{"initial_margin_bps": [500, 1000], "maintenance_margin_bps": [500, 750]}"#);
+        let err = check_cells(&accounts(&bad, &input)).unwrap_err();
+        match err.downcast_ref::<HarnessError>() {
+            Some(HarnessError::InvalidInput { field, reason }) => {
+                assert_eq!(field, "grid.maintenance_margin_bps");
+                assert_eq!(reason, "must be <= initial_margin_bps (cell 1)");
+            }
+            other => panic!("expected invalid input, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,598 @@
+//! Synthetic tapes for `tape_source.type = "synthetic"` (microstructure
+//! spec §2). Generators use integer arithmetic only, over `Rng`, so the same
+//! `generator_id`, `seed` and `params` give identical bytes on every build and
+//! platform. Prices and volumes are fixed-point integers at `price_scale` /
+//! `volume_scale`; rates are in bps.
+
+use crate::cli::GenerateTapeOptions;
+use crate::rng::Rng;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::ops::RangeInclusive;
+
+pub const GENERATORS: &[&str] = &[
+    "random_walk_v1",
+    "jump_diffusion_v1",
+    "regime_switching_v1",
+    "flash_crash_v1",
+    "gap_v1",
+];
+
+const BPS: i128 = 10_000;
+/// Fixed-point scale of standard normal draws.
+const Z_SCALE: i128 = 1_000_000;
+/// Per-bar returns are in bps at `Z_SCALE`; this is a return of 100%.
+const RET_ONE: i128 = BPS * Z_SCALE;
+/// Longest tape a generator writes; bounds the up-front allocation.
+pub const MAX_TAPE_BARS: i64 = 1_000_000;
+/// Rate params are capped at 100x so fixed-point returns stay well inside i128.
+const RATE_BPS: RangeInclusive<i64> = -1_000_000..=1_000_000;
+const VOL_BPS: RangeInclusive<i64> = 0..=1_000_000;
+const PROBABILITY_BPS: RangeInclusive<i64> = 0..=10_000;
+const PERCENT: RangeInclusive<i64> = 0..=10_000;
+const POSITIVE: RangeInclusive<i64> = 1..=i64::MAX;
+const NON_NEGATIVE: RangeInclusive<i64> = 0..=i64::MAX;
+/// Scales are `u32` in `EvalInputV1`.
+const SCALE: RangeInclusive<i64> = 1..=u32::MAX as i64;
+
+#[derive(Serialize)]
+struct InstrumentJson {
+    symbol: String,
+    base_mint: String,
+    quote_mint: String,
+    price_scale: i64,
+    volume_scale: i64,
+}
+
+/// A bar in the data-ops replay schema (§1.2).
+#[derive(Debug, Serialize)]
+struct TapeBar {
+    symbol: String,
+    bar_start_ts_ms: i64,
+    bar_end_ts_ms: i64,
+    open: i64,
+    high: i64,
+    low: i64,
+    close: i64,
+    volume: i64,
+}
+
+#[derive(Serialize)]
+struct TapeJson {
+    instrument: InstrumentJson,
+    bars: Vec<TapeBar>,
+}
+
+/// Report printed by `solclash-harness generate-tape --out`.
+#[derive(Debug, Serialize)]
+pub struct GenerateTapeReport {
+    pub generator_id: String,
+    pub seed: u64,
+    pub bars: usize,
+    pub path: String,
+    /// Of the written bytes; equal for equal generator, seed and params.
+    pub sha256: String,
+}
+
+pub fn run(options: &GenerateTapeOptions) -> Result<Option<GenerateTapeReport>> {
+    let params = match &options.params {
+        Some(value) if value.trim_start().starts_with('{') => value.clone(),
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("cannot read params {path}"))?
+        }
+        None => "{}".to_string(),
+    };
+    let params: Map<String, Value> = serde_json::from_str(&params).context("invalid params")?;
+    let tape = generate(&options.generator_id, options.seed, params)?;
+    let jsonl = options
+        .out
+        .as_ref()
+        .is_some_and(|out| out.extension().is_some_and(|ext| ext == "jsonl"));
+    let bytes = encode(&tape, jsonl)?;
+    let Some(out) = &options.out else {
+        print!("{}", String::from_utf8(bytes)?);
+        return Ok(None);
+    };
+    std::fs::write(out, &bytes).with_context(|| format!("cannot write {}", out.display()))?;
+    Ok(Some(GenerateTapeReport {
+        generator_id: options.generator_id.clone(),
+        seed: options.seed,
+        bars: tape.bars.len(),
+        path: out.display().to_string(),
+        sha256: hex::encode(Sha256::digest(&bytes)),
+    }))
+}
+
+/// `{instrument, bars}` JSON, or for JSONL an instrument header line
+/// followed by one bar per line; both load as `--data`.
+fn encode(tape: &TapeJson, jsonl: bool) -> Result<Vec<u8>> {
+    if !jsonl {
+        let mut bytes = serde_json::to_vec(tape)?;
+        bytes.push(b'\n');
+        return Ok(bytes);
+    }
+    let mut bytes = serde_json::to_vec(&serde_json::json!({ "instrument": tape.instrument }))?;
+    bytes.push(b'\n');
+    for bar in &tape.bars {
+        serde_json::to_writer(&mut bytes, bar)?;
+        bytes.push(b'\n');
+    }
+    Ok(bytes)
+}
+
+fn generate(generator_id: &str, seed: u64, params: Map<String, Value>) -> Result<TapeJson> {
+    let mut params = Params::new(params);
+    let total_bars = params.int("total_bars", 0)?;
+    if total_bars <= 0 {
+        return Err(anyhow!("synthetic tape requires params.total_bars > 0"));
+    }
+    if total_bars > MAX_TAPE_BARS {
+        return Err(anyhow!(
+            "params.total_bars must be at most {MAX_TAPE_BARS}, got {total_bars}"
+        ));
+    }
+    let interval_ms = params
+        .range("bar_interval_seconds", 60, POSITIVE)?
+        .checked_mul(1000)
+        .ok_or_else(|| anyhow!("params.bar_interval_seconds is too large"))?;
+    let start_ts_ms = params.int("start_ts_ms", 0)?;
+    interval_ms
+        .checked_mul(total_bars)
+        .and_then(|span| start_ts_ms.checked_add(span))
+        .ok_or_else(|| anyhow!("bar timestamps overflow i64 for these params"))?;
+    let instrument = InstrumentJson {
+        symbol: params.string("symbol", "BTC-PERP")?,
+        base_mint: params.string("base_mint", "BTC")?,
+        quote_mint: params.string("quote_mint", "USDC")?,
+        price_scale: params.range("price_scale", 1_000_000, SCALE)?,
+        volume_scale: params.range("volume_scale", 1_000_000, SCALE)?,
+    };
+    let mut walk = Walk {
+        rng: Rng::new(seed),
+        price: params.range("start_price", 50_000 * instrument.price_scale, POSITIVE)?,
+        wick_bps: params.range("wick_bps", 10, VOL_BPS)?,
+        volume_mean: params.range("volume_mean", 100 * instrument.volume_scale, NON_NEGATIVE)?,
+        volume_std: params.range("volume_std", 20 * instrument.volume_scale, NON_NEGATIVE)?,
+    };
+    let mut scenario = Scenario::new(generator_id, &mut params, total_bars)?;
+    params.finish()?;
+
+    let mut bars = Vec::with_capacity(total_bars as usize);
+    for i in 0..total_bars {
+        let step = scenario.step(i, &mut walk.rng);
+        let start = start_ts_ms + i * interval_ms;
+        let (open, high, low, close, volume) =
+            walk.bar(step).with_context(|| format!("bar {i}"))?;
+        bars.push(TapeBar {
+            symbol: instrument.symbol.clone(),
+            bar_start_ts_ms: start,
+            bar_end_ts_ms: start + interval_ms,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        });
+    }
+    Ok(TapeJson { instrument, bars })
+}
+
+/// One bar's shape as chosen by a scenario.
+struct Step {
+    /// Close-over-open return, in bps at `Z_SCALE`.
+    ret: i128,
+    /// Open-over-previous-close gap, in bps.
+    gap_bps: i64,
+    volume_pct: i64,
+}
+
+enum Scenario {
+    RandomWalk {
+        drift_bps: i64,
+        vol_bps: i64,
+    },
+    JumpDiffusion {
+        drift_bps: i64,
+        vol_bps: i64,
+        jump_prob_bps: i64,
+        jump_mean_bps: i64,
+        jump_std_bps: i64,
+    },
+    RegimeSwitching {
+        regimes: [Regime; 2],
+        switch_prob_bps: i64,
+        current: usize,
+    },
+    FlashCrash {
+        drift_bps: i64,
+        vol_bps: i64,
+        crash_at: i64,
+        crash_bars: i64,
+        crash_bps: i64,
+        recovery_bars: i64,
+        recovery_pct: i64,
+        crash_volume_pct: i64,
+    },
+    Gap {
+        drift_bps: i64,
+        vol_bps: i64,
+        gap_at: i64,
+        gap_every: i64,
+        gap_bps: i64,
+    },
+}
+
+struct Regime {
+    drift_bps: i64,
+    vol_bps: i64,
+    volume_pct: i64,
+}
+
+impl Scenario {
+    fn new(generator_id: &str, params: &mut Params, total_bars: i64) -> Result<Self> {
+        let drift_vol = |params: &mut Params| -> Result<(i64, i64)> {
+            Ok((
+                params.range("drift_bps_per_bar", 0, RATE_BPS)?,
+                params.range("vol_bps_per_sqrt_bar", 50, VOL_BPS)?,
+            ))
+        };
+        Ok(match generator_id {
+            "random_walk_v1" => {
+                let (drift_bps, vol_bps) = drift_vol(params)?;
+                Scenario::RandomWalk { drift_bps, vol_bps }
+            }
+            "jump_diffusion_v1" => {
+                let (drift_bps, vol_bps) = drift_vol(params)?;
+                Scenario::JumpDiffusion {
+                    drift_bps,
+                    vol_bps,
+                    jump_prob_bps: params.range("jump_prob_bps", 50, PROBABILITY_BPS)?,
+                    jump_mean_bps: params.range("jump_mean_bps", -200, RATE_BPS)?,
+                    jump_std_bps: params.range("jump_std_bps", 300, VOL_BPS)?,
+                }
+            }
+            "regime_switching_v1" => Scenario::RegimeSwitching {
+                regimes: [
+                    Regime {
+                        drift_bps: params.range("calm_drift_bps", 0, RATE_BPS)?,
+                        vol_bps: params.range("calm_vol_bps", 20, VOL_BPS)?,
+                        volume_pct: 100,
+                    },
+                    Regime {
+                        drift_bps: params.range("stressed_drift_bps", -5, RATE_BPS)?,
+                        vol_bps: params.range("stressed_vol_bps", 120, VOL_BPS)?,
+                        volume_pct: params.range("stressed_volume_pct", 300, PERCENT)?,
+                    },
+                ],
+                switch_prob_bps: params.range("switch_prob_bps", 100, PROBABILITY_BPS)?,
+                current: 0,
+            },
+            "flash_crash_v1" => {
+                let (drift_bps, vol_bps) = drift_vol(params)?;
+                Scenario::FlashCrash {
+                    drift_bps,
+                    vol_bps,
+                    crash_at: params.range("crash_at", total_bars / 2, NON_NEGATIVE)?,
+                    crash_bars: params.range("crash_bars", 3, 1..=MAX_TAPE_BARS)?,
+                    crash_bps: params.range("crash_bps", 2_000, RATE_BPS)?,
+                    recovery_bars: params.range("recovery_bars", 30, 1..=MAX_TAPE_BARS)?,
+                    recovery_pct: params.range("recovery_pct", 50, PERCENT)?,
+                    crash_volume_pct: params.range("crash_volume_pct", 500, PERCENT)?,
+                }
+            }
+            "gap_v1" => {
+                let (drift_bps, vol_bps) = drift_vol(params)?;
+                Scenario::Gap {
+                    drift_bps,
+                    vol_bps,
+                    gap_at: params.range("gap_at", total_bars / 2, NON_NEGATIVE)?,
+                    gap_every: params.range("gap_every", 0, 0..=MAX_TAPE_BARS)?,
+                    gap_bps: params.range("gap_bps", -500, RATE_BPS)?,
+                }
+            }
+            other => {
+                return Err(anyhow!(
+                    "unsupported generator_id: {other} (one of {})",
+                    GENERATORS.join(", ")
+                ))
+            }
+        })
+    }
+
+    fn step(&mut self, i: i64, rng: &mut Rng) -> Step {
+        let diffusion = |rng: &mut Rng, drift_bps: i64, vol_bps: i64| {
+            drift_bps as i128 * Z_SCALE + vol_bps as i128 * normal(rng)
+        };
+        let mut step = Step {
+            ret: 0,
+            gap_bps: 0,
+            volume_pct: 100,
+        };
+        match self {
+            Scenario::RandomWalk { drift_bps, vol_bps } => {
+                step.ret = diffusion(rng, *drift_bps, *vol_bps);
+            }
+            Scenario::JumpDiffusion {
+                drift_bps,
+                vol_bps,
+                jump_prob_bps,
+                jump_mean_bps,
+                jump_std_bps,
+            } => {
+                step.ret = diffusion(rng, *drift_bps, *vol_bps);
+                if (rng.below(BPS as u64) as i64) < *jump_prob_bps {
+                    step.ret += diffusion(rng, *jump_mean_bps, *jump_std_bps);
+                    step.volume_pct = 200;
+                }
+            }
+            Scenario::RegimeSwitching {
+                regimes,
+                switch_prob_bps,
+                current,
+            } => {
+                if (rng.below(BPS as u64) as i64) < *switch_prob_bps {
+                    *current = 1 - *current;
+                }
+                let regime = &regimes[*current];
+                step.ret = diffusion(rng, regime.drift_bps, regime.vol_bps);
+                step.volume_pct = regime.volume_pct;
+            }
+            Scenario::FlashCrash {
+                drift_bps,
+                vol_bps,
+                crash_at,
+                crash_bars,
+                crash_bps,
+                recovery_bars,
+                recovery_pct,
+                crash_volume_pct,
+            } => {
+                step.ret = diffusion(rng, *drift_bps, *vol_bps);
+                let since = i - *crash_at;
+                if (0..*crash_bars).contains(&since) {
+                    step.ret -= *crash_bps as i128 * Z_SCALE / *crash_bars as i128;
+                    step.volume_pct = *crash_volume_pct;
+                } else if (*crash_bars..*crash_bars + *recovery_bars).contains(&since) {
+                    step.ret += *crash_bps as i128 * Z_SCALE * *recovery_pct as i128
+                        / 100
+                        / *recovery_bars as i128;
+                }
+            }
+            Scenario::Gap {
+                drift_bps,
+                vol_bps,
+                gap_at,
+                gap_every,
+                gap_bps,
+            } => {
+                step.ret = diffusion(rng, *drift_bps, *vol_bps);
+                let since = i - *gap_at;
+                let gaps_here =
+                    since == 0 || (since > 0 && *gap_every > 0 && since % *gap_every == 0);
+                if gaps_here {
+                    step.gap_bps = *gap_bps;
+                    step.volume_pct = 300;
+                }
+            }
+        }
+        step
+    }
+}
+
+struct Walk {
+    rng: Rng,
+    /// Previous close.
+    price: i64,
+    wick_bps: i64,
+    volume_mean: i64,
+    volume_std: i64,
+}
+
+impl Walk {
+    /// Returns `(open, high, low, close, volume)` and advances the price.
+    fn bar(&mut self, step: Step) -> Result<(i64, i64, i64, i64, i64)> {
+        let open = scale(self.price, (BPS + step.gap_bps as i128) * Z_SCALE)?;
+        let close = scale(open, RET_ONE + step.ret)?;
+        let wick = |rng: &mut Rng| self.wick_bps as i128 * normal(rng).abs();
+        let high = scale(open.max(close), RET_ONE + wick(&mut self.rng))?;
+        let low = scale(open.min(close), RET_ONE - wick(&mut self.rng))?;
+        let volume = self.volume_mean as i128 * step.volume_pct as i128 / 100
+            + self.volume_std as i128 * normal(&mut self.rng) / Z_SCALE;
+        self.price = close;
+        Ok((
+            open,
+            high,
+            low,
+            close,
+            volume.clamp(0, i64::MAX as i128) as i64,
+        ))
+    }
+}
+
+/// `value * factor / RET_ONE`, floored at a price of 1. A price beyond `i64`
+/// is an error rather than a clamp.
+fn scale(value: i64, factor: i128) -> Result<i64> {
+    (value as i128)
+        .checked_mul(factor)
+        .map(|scaled| (scaled / RET_ONE).max(1))
+        .and_then(|scaled| i64::try_from(scaled).ok())
+        .ok_or_else(|| anyhow!("price overflows i64; lower start_price or the rate params"))
+}
+
+/// Standard normal draw at `Z_SCALE`: the Irwin-Hall sum of twelve uniforms,
+/// which needs no floating point. Bounded to +-6.
+fn normal(rng: &mut Rng) -> i128 {
+    (0..12)
+        .map(|_| rng.below(Z_SCALE as u64) as i128)
+        .sum::<i128>()
+        - 6 * Z_SCALE
+}
+
+/// Generator params; keys a generator does not read are rejected.
+struct Params(Map<String, Value>);
+
+impl Params {
+    fn new(values: Map<String, Value>) -> Self {
+        Self(values)
+    }
+
+    fn int(&mut self, name: &str, default: i64) -> Result<i64> {
+        match self.0.remove(name) {
+            None => Ok(default),
+            Some(value) => value
+                .as_i64()
+                .ok_or_else(|| anyhow!("params.{name} must be an integer, got {value}")),
+        }
+    }
+
+    fn range(&mut self, name: &str, default: i64, range: RangeInclusive<i64>) -> Result<i64> {
+        let value = self.int(name, default)?;
+        if !range.contains(&value) {
+            return Err(anyhow!(
+                "params.{name} must be in {}..={}, got {value}",
+                range.start(),
+                range.end()
+            ));
+        }
+        Ok(value)
+    }
+
+    fn string(&mut self, name: &str, default: &str) -> Result<String> {
+        match self.0.remove(name) {
+            None => Ok(default.to_string()),
+            Some(Value::String(value)) => Ok(value),
+            Some(value) => Err(anyhow!("params.{name} must be a string, got {value}")),
+        }
+    }
+
+    fn finish(self) -> Result<()> {
+        match self.0.keys().next() {
+            Some(name) => Err(anyhow!("unknown param for this generator: {name}")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{validate_bars, ReplayBar};
+    use serde_json::json;
+
+    fn tape(generator_id: &str, seed: u64, params: Value) -> TapeJson {
+        let Value::Object(params) = params else {
+            panic!("params must be an object");
+        };
+        generate(generator_id, seed, params).unwrap()
+    }
+
+    #[test]
+    fn same_seed_and_params_give_identical_bytes() {
+        let params = json!({ "total_bars": 200, "vol_bps_per_sqrt_bar": 80 });
+        for generator_id in GENERATORS {
+            let params = match *generator_id {
+                "regime_switching_v1" => json!({ "total_bars": 200 }),
+                _ => params.clone(),
+            };
+            let a = encode(&tape(generator_id, 7, params.clone()), false).unwrap();
+            let b = encode(&tape(generator_id, 7, params.clone()), false).unwrap();
+            let c = encode(&tape(generator_id, 8, params), false).unwrap();
+            assert_eq!(a, b, "{generator_id}");
+            assert_ne!(a, c, "{generator_id}");
+        }
+        // Pinned so a change to the generators is deliberate.
+        let bars = tape("random_walk_v1", 1, json!({ "total_bars": 2 })).bars;
+        assert_eq!(bars[0].open, 50_000_000_000);
+        assert_eq!(bars[1].open, bars[0].close);
+    }
+
+    #[test]
+    fn generated_bars_pass_the_integrity_checks() {
+        for generator_id in GENERATORS {
+            let bars: Vec<ReplayBar> = tape(generator_id, 3, json!({ "total_bars": 500 }))
+                .bars
+                .iter()
+                .map(|bar| ReplayBar {
+                    bar_start_ts_ms: bar.bar_start_ts_ms,
+                    open: bar.open as f64,
+                    high: bar.high as f64,
+                    low: bar.low as f64,
+                    close: bar.close as f64,
+                    volume: bar.volume as f64,
+                })
+                .collect();
+            assert_eq!(validate_bars(&bars, 60_000).len(), 0, "{generator_id}");
+        }
+    }
+
+    #[test]
+    fn stress_scenarios_move_the_price() {
+        let bars = tape(
+            "flash_crash_v1",
+            1,
+            json!({ "total_bars": 100, "vol_bps_per_sqrt_bar": 0, "wick_bps": 0, "crash_at": 10 }),
+        )
+        .bars;
+        assert_eq!(bars[9].close, 50_000_000_000);
+        // 20% over three bars, compounded: 50k * (1 - 0.0667)^3.
+        assert!(bars[12].close < 41_000_000_000);
+        assert!(bars[12].close > 40_000_000_000);
+        assert!(bars[60].close > bars[12].close);
+
+        let bars = tape(
+            "gap_v1",
+            1,
+            json!({ "total_bars": 20, "vol_bps_per_sqrt_bar": 0, "gap_at": 5, "gap_bps": -500 }),
+        )
+        .bars;
+        assert_eq!(bars[5].open, 47_500_000_000);
+        assert_eq!(bars[4].close, 50_000_000_000);
+
+        let Value::Object(params) = json!({ "total_bars": 5, "jump_prob_bps": 5 }) else {
+            unreachable!()
+        };
+        assert!(generate("random_walk_v1", 1, params).is_err());
+        assert!(generate("gbm_v1", 1, Map::new()).is_err());
+    }
+
+    #[test]
+    fn out_of_range_params_are_errors() {
+        let rejected = [
+            json!({ "total_bars": 1, "price_scale": 1_000_000_000_000_000_000i64 }),
+            json!({ "total_bars": 1, "price_scale": -1 }),
+            json!({ "total_bars": 1, "start_price": -5 }),
+            json!({ "total_bars": 1, "vol_bps_per_sqrt_bar": i64::MAX }),
+            json!({ "total_bars": MAX_TAPE_BARS + 1 }),
+            json!({ "total_bars": 2, "bar_interval_seconds": i64::MAX / 1000, "start_ts_ms": 1 }),
+        ];
+        for params in rejected {
+            let Value::Object(map) = params.clone() else {
+                unreachable!()
+            };
+            assert!(generate("random_walk_v1", 1, map).is_err(), "{params}");
+        }
+
+        // In-range rates that compound past i64 fail the bar, not the process.
+        let Value::Object(params) = json!({
+            "total_bars": 100,
+            "start_price": i64::MAX / 2,
+            "drift_bps_per_bar": 1_000_000,
+        }) else {
+            unreachable!()
+        };
+        let err = generate("random_walk_v1", 1, params).err().unwrap();
+        assert!(format!("{err:#}").contains("overflows i64"), "{err:#}");
+    }
+
+    #[test]
+    fn normal_draws_are_centered() {
+        let mut rng = Rng::new(5);
+        let draws: Vec<i128> = (0..10_000).map(|_| normal(&mut rng)).collect();
+        let mean = draws.iter().sum::<i128>() / draws.len() as i128;
+        let var = draws.iter().map(|z| z * z / Z_SCALE).sum::<i128>() / draws.len() as i128;
+        assert!(mean.abs() < Z_SCALE / 20);
+        assert!((var - Z_SCALE).abs() < Z_SCALE / 10);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::abi::{Bar, EvalInputV1};
+use std::collections::BTreeMap;
+
+/// Bars seen so far in the current window, keyed by step index, so each
+/// agent's `ohlcv` can be trimmed or extended to its own lookback length.
+#[derive(Default)]
+pub struct WindowTape {
+    window_id: [u8; 32],
+    bars: BTreeMap<u32, Bar>,
+    retain: u32,
+}
+
+impl WindowTape {
+    /// Keeps at most `retain` bars behind the latest step.
+    pub fn new(retain: u16) -> Self {
+        Self {
+            retain: u32::from(retain),
+            ..Self::default()
+        }
+    }
+
+    /// Records the bars of an eval input. The last bar of `ohlcv` is the bar
+    /// at `step_index`; a new `window_id` starts a fresh tape.
+    pub fn record(&mut self, input: &EvalInputV1) {
+        if input.window_id != self.window_id {
+            self.window_id = input.window_id;
+            self.bars.clear();
+        }
+        let len = input.ohlcv.len() as u32;
+        let Some(first) = input
+            .step_index
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(len))
+        else {
+            return;
+        };
+        for (offset, bar) in input.ohlcv.iter().enumerate() {
+            self.bars.insert(first + offset as u32, bar.clone());
+        }
+        let oldest = input.step_index.saturating_sub(self.retain);
+        self.bars = self.bars.split_off(&oldest);
+    }
+
+    /// Up to `len` contiguous bars ending at `step_index`. Stops early at the
+    /// window start or at the first bar the tape has not seen.
+    pub fn lookback(&self, step_index: u32, len: u16) -> Vec<Bar> {
+        let mut bars = Vec::with_capacity(usize::from(len));
+        let mut step = step_index;
+        while bars.len() < usize::from(len) {
+            match self.bars.get(&step) {
+                Some(bar) => bars.push(bar.clone()),
+                None => break,
+            }
+            match step.checked_sub(1) {
+                Some(prev) => step = prev,
+                None => break,
+            }
+        }
+        bars.reverse();
+        bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(step_index: u32, closes: &[i64]) -> EvalInputV1 {
+        let ohlcv: Vec<Bar> = closes
+            .iter()
+            .map(|&c| Bar {
+                open: c,
+                high: c,
+                low: c,
+                close: c,
+                volume: 1,
+            })
+            .collect();
+        EvalInputV1 {
+            version: 1,
+            window_id: [0; 32],
+            step_index,
+            bar_interval_seconds: 60,
+            price_scale: 1,
+            volume_scale: 1,
+            cash_balance: 10_000,
+            position_qty: 0,
+            avg_entry_price: 0,
+            max_leverage_bps: 10_000,
+            initial_margin_bps: 1_000,
+            maintenance_margin_bps: 500,
+            lookback_len: ohlcv.len() as u16,
+            ohlcv,
+            depth: None,
+            features: None,
+        }
+    }
+
+    fn closes(bars: &[Bar]) -> Vec<i64> {
+        bars.iter().map(|b| b.close).collect()
+    }
+
+    #[test]
+    fn trims_and_extends_lookback() {
+        let mut tape = WindowTape::new(4);
+        for (step, closes) in [(1, [10, 11]), (2, [11, 12]), (3, [12, 13])] {
+            tape.record(&input(step, &closes));
+        }
+        assert_eq!(closes(&tape.lookback(3, 10)), [10, 11, 12, 13]);
+        assert_eq!(closes(&tape.lookback(3, 1)), [13]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Runs `solclash-harness backtest` end to end with builtin agents and the
+//! config's baselines.
+
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+
+const DEFAULT_CONFIG: &str =
+    include_str!("../../../packages/arenas/arenas/btc-perp-v1/default-config.json");
+
+fn read_jsonl(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("read {}: {err}", path.display()))
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("log line"))
+        .collect()
+}
+
+#[test]
+fn backtest_writes_logs_and_summary() {
+    let dir = std::env::temp_dir().join(format!("solclash-backtest-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut config: Value = serde_json::from_str(DEFAULT_CONFIG).unwrap();
+    config["window_duration_bars"] = json!(30);
+    config["lookback_len"] = json!(10);
+    config["baseline_bots_enabled"] = json!(["BUY_AND_HOLD", "FLAT"]);
+    let config_path = dir.join("config.json");
+    std::fs::write(&config_path, config.to_string()).unwrap();
+
+    let bars: Vec<Value> = (0..40)
+        .map(|i| {
+            let price = 100 + i;
+            json!({
+                "symbol": "BTC-PERP",
+                "bar_start_ts_ms": i * 60_000,
+                "bar_end_ts_ms": (i + 1) * 60_000,
+                "open": price, "high": price + 1, "low": price - 1, "close": price,
+                "volume": 10
+            })
+        })
+        .collect();
+    let data_path = dir.join("bars.json");
+    std::fs::write(&data_path, Value::from(bars).to_string()).unwrap();
+
+    let out_dir = dir.join("out");
+    let output = Command::new(env!("CARGO_BIN_EXE_solclash-harness"))
+        .env("RUST_LOG", "off")
+        .arg("backtest")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--data")
+        .arg(&data_path)
+        .args(["--agent", "builtin:always_buy", "--agent", "builtin:hold"])
+        .arg("--out")
+        .arg(&out_dir)
+        .output()
+        .expect("run backtest");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report: Value = serde_json::from_slice(&output.stdout).expect("report json");
+    assert_eq!(report["windows"], json!(["w0"]));
+    let ids: Vec<&str> = report["agents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["always_buy", "hold", "BUY_AND_HOLD", "FLAT"]);
+
+    // One policy line and one equity line per bar; fills start at bar 1.
+    let policy = read_jsonl(&out_dir.join("always_buy/policy_log.jsonl"));
+    assert_eq!(policy.len(), 30);
+    assert_eq!(policy[0]["action_type"], 1);
+    assert_eq!(policy[0]["status"], "OK");
+    let trades = read_jsonl(&out_dir.join("always_buy/trade_log.jsonl"));
+    assert_eq!(trades.len(), 29);
+    assert_eq!(trades[0]["delta_qty"], 1);
+    // always_buy and BUY_AND_HOLD both buy at the first open.
+    assert_eq!(trades[0]["net_qty"], 2);
+    assert!(trades[0]["exec_price"].as_f64().unwrap() > 101.0);
+    let equity = read_jsonl(&out_dir.join("always_buy/equity_log.jsonl"));
+    assert_eq!(equity.len(), 30);
+    assert_eq!(equity[29]["position_qty"], 29);
+
+    assert_eq!(
+        read_jsonl(&out_dir.join("BUY_AND_HOLD/trade_log.jsonl")).len(),
+        1
+    );
+    assert!(read_jsonl(&out_dir.join("FLAT/trade_log.jsonl")).is_empty());
+
+    let summary: Value =
+        serde_json::from_str(&std::fs::read_to_string(out_dir.join("summary.json")).unwrap())
+            .unwrap();
+    assert_eq!(summary[0]["window_id"], "w0");
+    assert_eq!(summary[0]["invalid_window_reason"], Value::Null);
+    let flat = &summary[0]["metrics_by_agent"]["FLAT"];
+    assert_eq!(flat["pnl"], 0.0);
+    assert_eq!(flat["equity_start"], 10_000.0);
+    assert!(
+        summary[0]["metrics_by_agent"]["BUY_AND_HOLD"]["pnl"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,59 @@
+//! Runs `solclash-harness bench --calibrate` on a generated tape with a
+//! builtin program.
+
+use serde_json::Value;
+use std::process::Command;
+
+fn harness(args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_solclash-harness"))
+        .env("RUST_LOG", "off")
+        .args(args)
+        .output()
+        .expect("run harness");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("report json")
+}
+
+#[test]
+fn bench_calibrates_to_the_measured_peak() {
+    let dir = std::env::temp_dir().join(format!("solclash-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("bars.jsonl");
+    let data = data.to_str().unwrap();
+
+    let tape = harness(&[
+        "generate-tape",
+        "random_walk_v1",
+        "--params",
+        r#"{"total_bars": 100}"#,
+        "--out",
+        data,
+    ]);
+    assert_eq!(tape["bars"], 100);
+
+    let report = harness(&[
+        "bench",
+        "builtin:always_buy",
+        "--data",
+        data,
+        "--samples",
+        "20",
+        "--max-lookback",
+        "64",
+        "--calibrate",
+    ]);
+    assert_eq!(report["passed"], true);
+    assert_eq!(report["failures"], Value::Array(Vec::new()));
+    let max = &report["compute_units"]["max"];
+    assert!(max.as_u64().unwrap() > 0);
+    assert_eq!(&report["peak"]["compute_units"], max);
+    assert!(report["peak"]["ohlcv_len"].as_u64().unwrap() <= 64);
+    assert_eq!(&report["calibrated_compute_unit_limit"], max);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
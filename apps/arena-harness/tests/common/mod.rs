@@ -0,0 +1,24 @@
+//! Inputs shared by the integration tests.
+
+use serde_json::{json, Value};
+
+/// A valid v1 `EvalInputJson` with a single lookback bar; tests edit the
+/// fields they exercise.
+pub fn input() -> Value {
+    json!({
+        "version": 1,
+        "window_id": "w1",
+        "step_index": 0,
+        "bar_interval_seconds": 60,
+        "price_scale": 1000000,
+        "volume_scale": 1000000,
+        "cash_balance": "10000000000",
+        "position_qty": "0",
+        "avg_entry_price": "0",
+        "max_leverage_bps": 10000,
+        "initial_margin_bps": 1000,
+        "maintenance_margin_bps": 500,
+        "lookback_len": 1,
+        "ohlcv": [{ "open": "100", "high": "101", "low": "99", "close": "100", "volume": "10" }]
+    })
+}
@@ -0,0 +1,99 @@
+//! Runs `solclash-harness diff` on builtin programs over a generated tape
+//! and over a recorded serve transcript.
+
+mod common;
+
+use common::input;
+use serde_json::{json, Value};
+use std::process::Command;
+
+/// The report, and whether the harness exited successfully.
+fn harness(args: &[&str]) -> (Value, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_solclash-harness"))
+        .env("RUST_LOG", "off")
+        .args(args)
+        .output()
+        .expect("run harness");
+    let report = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|_| panic!("report json: {}", String::from_utf8_lossy(&output.stderr)));
+    (report, output.status.success())
+}
+
+#[test]
+fn diff_reports_every_differing_step() {
+    let dir = std::env::temp_dir().join(format!("solclash-diff-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("bars.json");
+    let data = data.to_str().unwrap();
+    let (_, generated) = harness(&[
+        "generate-tape",
+        "random_walk_v1",
+        "--params",
+        r#"{"total_bars": 30}"#,
+        "--out",
+        data,
+    ]);
+    assert!(generated);
+
+    let (report, passed) = harness(&[
+        "diff",
+        "builtin:hold",
+        "builtin:hold",
+        "--data",
+        data,
+        "--lookback",
+        "8",
+    ]);
+    assert!(passed);
+    assert_eq!(report["steps"], 30);
+    assert_eq!(report["actions"]["HOLD"], json!({"a": 30, "b": 30}));
+    assert_eq!(report["compute_units"]["delta_total"], 0);
+
+    let (report, passed) = harness(&["diff", "builtin:hold", "builtin:always_buy", "--data", data]);
+    assert!(!passed);
+    assert_eq!(report["differing_steps"], 30);
+    assert_eq!(report["actions"]["HOLD"], json!({"a": 30, "b": 0}));
+    assert_eq!(report["actions"]["BUY"], json!({"a": 0, "b": 30}));
+    let first = &report["differences"][0];
+    assert_eq!(
+        (&first["step"], &first["step_index"]),
+        (&json!(0), &json!(0))
+    );
+    assert_eq!(
+        (&first["a"]["action"], &first["b"]["action"]),
+        (&json!("HOLD"), &json!("BUY"))
+    );
+    assert!(first["b"]["compute_units"].as_u64().unwrap() > 0);
+
+    // A transcript replays only its eval inputs; the panicking program's
+    // failures are differences too.
+    let transcript = dir.join("session.jsonl");
+    let eval = |request_id: u64, step: u32| {
+        let mut eval_input = input();
+        eval_input["step_index"] = json!(step);
+        json!({ "type": "eval", "request_id": request_id, "agent_id": "policy", "input": eval_input })
+            .to_string()
+    };
+    let lines = [
+        json!({"type": "init", "request_id": 1, "programs": []}).to_string(),
+        eval(2, 0),
+        eval(3, 1),
+        json!({"type": "shutdown", "request_id": 4}).to_string(),
+    ];
+    std::fs::write(&transcript, lines.join("\n")).unwrap();
+    let (report, passed) = harness(&[
+        "diff",
+        "builtin:hold",
+        "builtin:panic",
+        "--transcript",
+        transcript.to_str().unwrap(),
+    ]);
+    assert!(!passed);
+    assert_eq!(report["steps"], 2);
+    assert_eq!(report["actions"]["ERR"], json!({"a": 0, "b": 2}));
+    assert_eq!(report["differences"][1]["step_index"], 1);
+    assert_eq!(report["differences"][1]["b"]["status"], "ERR");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,474 @@
+//! Drives the harness binary over its stdin/stdout protocol against the
+//! builtin native test programs and baselines.
+
+mod common;
+
+use common::input;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Harness {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Harness {
+    fn spawn() -> Self {
+        Self::spawn_with(&[])
+    }
+
+    fn spawn_with(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_solclash-harness"))
+            .args(args)
+            .env("RUST_LOG", "off")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn harness");
+        let stdin = child.stdin.take().expect("stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout"));
+        Self {
+            child,
+            stdin,
+            stdout,
+        }
+    }
+
+    fn send_line(&mut self, line: &str) -> Value {
+        writeln!(self.stdin, "{line}").expect("write request");
+        self.stdin.flush().expect("flush");
+        let mut response = String::new();
+        self.stdout.read_line(&mut response).expect("read response");
+        serde_json::from_str(&response).expect("response json")
+    }
+
+    fn send(&mut self, request: Value) -> Value {
+        self.send_line(&request.to_string())
+    }
+
+    fn init(&mut self, builtins: &[&str]) -> Value {
+        let programs: Vec<Value> = builtins
+            .iter()
+            .map(|name| json!({ "id": name, "builtin": name }))
+            .collect();
+        self.send(json!({ "type": "init", "request_id": 1, "programs": programs }))
+    }
+
+    fn eval(&mut self, agent_id: &str) -> Value {
+        self.send(json!({
+            "type": "eval",
+            "request_id": 2,
+            "agent_id": agent_id,
+            "input": input(),
+        }))
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn init_loads_builtins_and_rejects_unknown_ones() {
+    let mut harness = Harness::spawn();
+    let response = harness.init(&["hold", "no_such_program"]);
+    assert_eq!(response["type"], "ok");
+    assert_eq!(response["programs"][0]["id"], "hold");
+    assert_eq!(response["programs"][0]["input_version"], 1);
+    assert_eq!(response["rejected"][0]["id"], "no_such_program");
+
+    let response = harness.send(json!({ "type": "shutdown", "request_id": 3 }));
+    assert_eq!(
+        (&response["type"], &response["request_id"]),
+        (&json!("ok"), &json!(3))
+    );
+    let agents = response["agents"].as_object().expect("shutdown stats");
+    assert_eq!(agents.keys().collect::<Vec<_>>(), ["hold"]);
+    assert_eq!(agents["hold"]["evals"], 0);
+    assert!(harness.child.wait().expect("exit").success());
+}
+
+#[test]
+fn init_rejects_repeated_program_ids() {
+    let mut harness = Harness::spawn();
+    let response = harness.send(json!({
+        "type": "init", "request_id": 1,
+        "programs": [
+            { "id": "a", "builtin": "hold" },
+            { "id": "a", "builtin": "always_buy" },
+        ],
+    }));
+    assert_eq!(response["programs"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        response["rejected"],
+        json!([{ "id": "a", "reason": "duplicate program id" }])
+    );
+    assert_eq!(harness.eval("a")["output"]["action_type"], 0);
+}
+
+#[test]
+fn init_rejects_only_programs_without_a_common_abi_version() {
+    let mut harness = Harness::spawn();
+    let response = harness.init(&["future_abi", "hold"]);
+    assert_eq!(response["type"], "ok");
+    assert_eq!(response["programs"].as_array().unwrap().len(), 1);
+    assert_eq!(response["programs"][0]["id"], "hold");
+    assert_eq!(
+        response["rejected"],
+        json!([{ "id": "future_abi", "reason": "no common ABI version" }])
+    );
+    assert_eq!(harness.eval("future_abi")["type"], "error");
+}
+
+#[test]
+fn eval_reports_outputs_of_well_behaved_programs() {
+    let mut harness = Harness::spawn();
+    harness.init(&["hold", "always_buy", "read_clock"]);
+
+    let response = harness.eval("hold");
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["output"]["action_type"], 0);
+    assert_eq!(response["output"]["err_code"], 0);
+
+    let response = harness.eval("always_buy");
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["output"]["action_type"], 1);
+    assert_eq!(response["output"]["order_qty"], "1");
+
+    // Builtins are not artifact-checked, so sysvar reads go through.
+    let response = harness.eval("read_clock");
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["output"]["action_type"], 4);
+}
+
+#[test]
+fn eval_contains_misbehaving_programs() {
+    let mut harness = Harness::spawn();
+    harness.init(&["panic", "infinite_loop", "garbage", "short_output", "hold"]);
+
+    let response = harness.eval("panic");
+    assert_eq!(response["type"], "error");
+    assert!(response["message"]
+        .as_str()
+        .unwrap()
+        .contains("Program failed to complete"));
+
+    let response = harness.eval("infinite_loop");
+    assert_eq!(response["type"], "error");
+    assert_eq!(response["message"], "compute budget exceeded: 200000 units");
+
+    let response = harness.eval("garbage");
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["output"]["action_type"], 0);
+    assert_eq!(response["output"]["err_code"], 6);
+    assert_eq!(response["validation_reason"], "unsupported output version");
+
+    let response = harness.eval("short_output");
+    assert_eq!(response["status"], "SANDBOX_VIOLATION");
+    assert_eq!(response["output"]["err_code"], 13);
+
+    // A failing program leaves the others usable.
+    let response = harness.eval("hold");
+    assert_eq!(response["status"], "OK");
+}
+
+#[test]
+fn malformed_and_out_of_order_requests_get_errors() {
+    let mut harness = Harness::spawn();
+    let response = harness.send_line("{not json");
+    assert_eq!(response["type"], "error");
+    assert_eq!(response["request_id"], 0);
+
+    let response = harness.eval("hold");
+    assert_eq!(response["message"], "not initialized");
+
+    let response = harness.send(json!({
+        "type": "init", "request_id": 1, "programs": [{ "id": "hold", "builtin": "hold" }],
+        "min_lookback_len": 10, "max_lookback_len": 5,
+    }));
+    assert_eq!(response["type"], "error");
+    assert_eq!(
+        response["message"],
+        "min_lookback_len 10 exceeds max_lookback_len 5"
+    );
+
+    harness.init(&["hold"]);
+    let response = harness.eval("missing");
+    assert_eq!(response["type"], "error");
+    assert_eq!(response["message"], "program not found: missing");
+    assert!(response.get("code").is_none());
+
+    let mut bad = input();
+    bad["lookback_len"] = json!(3);
+    let response = harness.send(json!({
+        "type": "eval", "request_id": 5, "agent_id": "hold", "input": bad,
+    }));
+    assert_eq!(response["request_id"], 5);
+    assert_eq!(response["code"], "INVALID_INPUT");
+    assert_eq!(response["field"], "lookback_len");
+    assert_eq!(
+        response["message"],
+        "invalid input: lookback_len: must equal ohlcv length 1"
+    );
+}
+
+#[test]
+fn baselines_answer_eval_like_programs() {
+    let mut harness = Harness::spawn();
+    let response = harness.send(json!({
+        "type": "init",
+        "request_id": 1,
+        "programs": [{ "id": "agent", "builtin": "hold" }],
+        "baselines": ["BUY_AND_HOLD", "FLAT", "SMA_CROSSOVER", "NOPE"],
+    }));
+    let ids: Vec<&str> = response["programs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["agent", "BUY_AND_HOLD", "FLAT", "SMA_CROSSOVER"]);
+    assert_eq!(response["rejected"][0]["id"], "NOPE");
+
+    let response = harness.eval("BUY_AND_HOLD");
+    assert_eq!(response["status"], "OK");
+    assert_eq!(response["output"]["action_type"], 1);
+    assert_eq!(response["output"]["order_qty"], "1");
+
+    let response = harness.eval("FLAT");
+    assert_eq!(response["output"]["action_type"], 0);
+
+    // One bar is not enough history for the crossover.
+    let response = harness.eval("SMA_CROSSOVER");
+    assert_eq!(response["output"]["action_type"], 0);
+    assert_eq!(response["output"]["err_code"], 0);
+}
+
+#[test]
+fn sweep_answers_every_grid_cell_in_one_response() {
+    let mut harness = Harness::spawn();
+    harness.send(json!({
+        "type": "init",
+        "request_id": 1,
+        "programs": [{ "id": "panic", "builtin": "panic" }],
+        "baselines": ["BUY_AND_HOLD"],
+    }));
+    let sweep = |agent_id: &str, grid: Value| {
+        json!({
+            "type": "sweep",
+            "request_id": 3,
+            "agent_id": agent_id,
+            "input": input(),
+            "grid": grid,
+        })
+    };
+
+    let response = harness.send(sweep(
+        "BUY_AND_HOLD",
+        json!({ "position_qty": ["0", "2000000"], "maintenance_margin_bps": [500, 600] }),
+    ));
+    assert_eq!(response["type"], "sweep_result");
+    assert_eq!(response["request_id"], 3);
+    let cells = response["cells"].as_array().unwrap();
+    let keys: Vec<(&str, u64, u64)> = cells
+        .iter()
+        .map(|c| {
+            (
+                c["position_qty"].as_str().unwrap(),
+                c["maintenance_margin_bps"].as_u64().unwrap(),
+                c["output"]["action_type"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            ("0", 500, 1),
+            ("0", 600, 1),
+            ("2000000", 500, 0),
+            ("2000000", 600, 0)
+        ]
+    );
+    assert!(cells.iter().all(|c| c["status"] == "OK"));
+    assert_eq!(cells[0]["cash_balance"], "10000000000");
+
+    // Failing cells are reported in place.
+    let response = harness.send(sweep("panic", json!({ "cash_balance": ["1", "2"] })));
+    let cells = response["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 2);
+    assert_eq!(cells[1]["status"], "ERR");
+    assert_eq!(cells[1]["output"]["action_type"], 0);
+
+    let response = harness.send(sweep("BUY_AND_HOLD", json!({ "position_qty": [] })));
+    assert_eq!(response["type"], "error");
+    assert_eq!(
+        response["message"],
+        "sweep grid has no values for position_qty"
+    );
+}
+
+#[test]
+fn policy_log_records_every_eval_that_reaches_a_program() {
+    let path =
+        std::env::temp_dir().join(format!("solclash-policy-log-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut harness = Harness::spawn_with(&["--policy-log", path.to_str().unwrap()]);
+    harness.init(&["always_buy", "panic"]);
+    harness.eval("always_buy");
+    harness.eval("panic");
+    harness.eval("missing");
+    harness.send(json!({
+        "type": "sweep",
+        "request_id": 3,
+        "agent_id": "always_buy",
+        "input": input(),
+        "grid": { "position_qty": ["0", "1000000"] },
+    }));
+    harness.send(json!({ "type": "shutdown", "request_id": 9 }));
+
+    let log = std::fs::read_to_string(&path).expect("policy log");
+    let records: Vec<Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("record json"))
+        .collect();
+    assert_eq!(records.len(), 4);
+    let buy = &records[0];
+    assert_eq!(
+        (&buy["window_id"], &buy["agent_id"], &buy["status"]),
+        (&json!("w1"), &json!("always_buy"), &json!("OK"))
+    );
+    assert_eq!(
+        (&buy["action_type"], &buy["order_qty"]),
+        (&json!(1), &json!(1))
+    );
+    assert!(buy["compute_units"].as_u64().unwrap() > 0);
+    assert!(buy.get("failure").is_none());
+    let panic = &records[1];
+    assert_eq!(
+        (&panic["status"], &panic["err_code"], &panic["failure"]),
+        (&json!("ERR"), &json!(5), &json!("abort"))
+    );
+    assert_eq!(panic["input_sha256"], buy["input_sha256"]);
+    assert!(buy.get("sweep_cell").is_none());
+    let cells: Vec<&Value> = records[2..].iter().map(|r| &r["sweep_cell"]).collect();
+    assert_eq!(cells, [&json!(0), &json!(1)]);
+    assert_ne!(records[2]["input_sha256"], records[3]["input_sha256"]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn stats_count_evals_per_agent_until_shutdown() {
+    let mut harness = Harness::spawn();
+    let response = harness.send(json!({ "type": "stats", "request_id": 1 }));
+    assert_eq!(response["message"], "not initialized");
+
+    harness.init(&["always_buy", "panic", "garbage"]);
+    for agent in ["always_buy", "always_buy", "panic", "garbage", "missing"] {
+        harness.eval(agent);
+    }
+    harness.send(json!({
+        "type": "sweep",
+        "request_id": 3,
+        "agent_id": "panic",
+        "input": input(),
+        "grid": { "position_qty": ["0", "1000000"] },
+    }));
+    let response = harness.send(json!({ "type": "stats", "request_id": 4 }));
+    assert_eq!(
+        (&response["type"], &response["request_id"]),
+        (&json!("stats"), &json!(4))
+    );
+    let buy = &response["agents"]["always_buy"];
+    assert_eq!(buy["evals"], 2);
+    assert_eq!(buy["statuses"], json!({ "OK": 2 }));
+    assert_eq!(buy["err_codes"], json!({ "0": 2 }));
+    let buckets = buy["compute_unit_histogram"].as_array().unwrap();
+    let counted: u64 = buckets.iter().map(|b| b["count"].as_u64().unwrap()).sum();
+    assert_eq!(counted, 2);
+    assert!(buy["latency_us"]["max"].as_u64().unwrap() > 0);
+    // The eval and both sweep cells failed; their compute still counts.
+    let panic = &response["agents"]["panic"];
+    assert_eq!(panic["evals"], 3);
+    assert_eq!(panic["statuses"], json!({ "ERR": 3 }));
+    assert_eq!(panic["err_codes"], json!({ "5": 3 }));
+    let buckets = panic["compute_unit_histogram"].as_array().unwrap();
+    let counted: u64 = buckets.iter().map(|b| b["count"].as_u64().unwrap()).sum();
+    assert_eq!(counted, 3);
+    assert_eq!(response["agents"]["garbage"]["evals"], 1);
+    assert!(response["agents"].get("missing").is_none());
+
+    let report =
+        std::env::temp_dir().join(format!("solclash-stats-report-{}.json", std::process::id()));
+    let response = harness.send(json!({
+        "type": "shutdown", "request_id": 5, "report": report.to_str().unwrap(),
+    }));
+    assert_eq!(response["agents"]["always_buy"]["evals"], 2);
+    let written: Value = serde_json::from_slice(&std::fs::read(&report).unwrap()).unwrap();
+    assert_eq!(written, response["agents"]);
+    let _ = std::fs::remove_file(&report);
+}
+
+#[test]
+fn json_log_file_carries_request_and_eval_spans() {
+    let dir = std::env::temp_dir().join(format!("solclash-log-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("harness.log");
+    let mut harness = Harness::spawn_with(&[
+        "--log",
+        "solclash_harness=debug",
+        "--log-format",
+        "json",
+        "--log-file",
+        path.to_str().unwrap(),
+        "--log-rotation",
+        "never",
+    ]);
+    harness.init(&["always_buy", "unknown"]);
+    harness.eval("always_buy");
+    harness.send(json!({ "type": "shutdown", "request_id": 9 }));
+    harness.child.wait().expect("harness exits");
+
+    let log = std::fs::read_to_string(&path).expect("log file");
+    let events: Vec<Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("event json"))
+        .collect();
+    let message = |text: &str| {
+        events
+            .iter()
+            .find(|e| e["fields"]["message"] == text)
+            .unwrap_or_else(|| panic!("no {text} event in {log}"))
+    };
+    let rejected = message("program rejected");
+    assert_eq!(rejected["fields"]["id"], "unknown");
+    assert_eq!(rejected["span"]["name"], "init_programs");
+    assert_eq!(
+        (
+            &rejected["spans"][0]["request_id"],
+            &rejected["spans"][0]["kind"]
+        ),
+        (&json!(1), &json!("init"))
+    );
+    let eval = message("eval completed");
+    assert_eq!(
+        (
+            &eval["span"]["agent_id"],
+            &eval["span"]["window_id"],
+            &eval["span"]["step"]
+        ),
+        (&json!("always_buy"), &json!("w1"), &json!(0))
+    );
+    assert_eq!(eval["spans"][0]["request_id"], 2);
+    assert!(eval["fields"]["compute_units"].as_u64().unwrap() > 0);
+    assert!(events
+        .iter()
+        .any(|e| e["fields"]["message"] == "wrote response" && e["span"]["kind"] == "shutdown"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -30,6 +30,7 @@ import {
   prepareProgramsAndInvalidAgents,
   validateWorkspaceForArena,
   writeRoundMeta,
+  type HarnessProgram,
   type OnchainWorkspaceAgent,
   type RoundMeta,
 } from "@solclash/arenas";
@@ -218,7 +219,10 @@ export interface TournamentOpts {
   runtime?: ContainerRuntime;
   arenaImage?: string;
   competitionMode?: "container" | "local";
-  /** Path to the Rust harness binary (required for local mode with workspace agents). */
+  /**
+   * Path to the Rust harness binary (required for local mode with
+   * workspace agents); when set, baselines also run in the harness.
+   */
   harnessPath?: string;
 }
 
@@ -232,7 +236,7 @@ export async function runTournament(
   const {
     config,
     bars,
-    agents = [],
+    agents: callerAgents = [],
     agentSources = [],
     rounds,
     outputDir,
@@ -267,7 +271,10 @@ export async function runTournament(
   // In local mode, compile workspace agents and start the harness once
   // before the round loop. Programs don't change between rounds in local
   // mode (no edit phase without Docker), so one harness serves all rounds.
+  // Baselines run in the same harness, through the same eval path and logs
+  // as the workspace programs, and replace the in-process TS baselines.
   const invalidAgents: Record<string, string> = {};
+  let agents = [...callerAgents];
   let harness: HarnessClient | null = null;
 
   if (!useContainerCompetition && harnessPath) {
@@ -276,6 +283,7 @@ export async function runTournament(
         a.provider !== "builtin" && typeof a.workspace === "string",
     );
 
+    const programs: HarnessProgram[] = [];
     if (workspaceAgents.length > 0) {
       const onchain: OnchainWorkspaceAgent[] = [];
       for (const a of workspaceAgents) {
@@ -288,15 +296,30 @@ export async function runTournament(
 
       const prepared = await prepareProgramsAndInvalidAgents(onchain);
       Object.assign(invalidAgents, prepared.invalidAgents);
+      programs.push(...prepared.programs);
+    }
 
-      if (prepared.programs.length > 0) {
-        harness = await HarnessClient.start(
-          harnessPath,
-          prepared.programs,
-          config.compute_unit_limit,
-        );
-        agents.push(...buildPolicies(prepared.programs, harness));
+    const baselines = config.baseline_bots_enabled;
+    if (programs.length > 0 || baselines.length > 0) {
+      const client = await HarnessClient.start(
+        harnessPath,
+        programs,
+        config.compute_unit_limit,
+        [],
+        baselines,
+      );
+      harness = client;
+      for (const [id, reason] of Object.entries(client.rejected)) {
+        invalidAgents[id] = `harness_rejected: ${reason}`;
       }
+      const accepted = [
+        ...programs,
+        ...baselines.map((id) => ({ id })),
+      ].filter((p) => !(p.id in client.rejected));
+      agents = [
+        ...agents.filter((agent) => !baselines.includes(agent.id)),
+        ...buildPolicies(accepted, client),
+      ];
     }
   }
 
@@ -0,0 +1,65 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Instruction data for the load-time ABI probe. Programs that recognise it
+/// write an `AbiDeclaration` to the output account; legacy programs answer
+/// with a v1 HOLD (INVALID_INSTRUCTION_DATA) and are treated as v1-only.
+pub const ABI_PROBE_IX: &[u8] = b"solclash:abi";
+pub const ABI_DECLARATION_TAG: u8 = 0xab;
+/// Bytes of the tag and version masks. `lookback_len` is an optional
+/// trailer after them, so declarations written before it existed still
+/// decode (with `lookback_len` 0).
+pub const ABI_DECLARATION_HEAD_LEN: usize = 5;
+pub const ABI_DECLARATION_LEN: usize = ABI_DECLARATION_HEAD_LEN + 2;
+
+/// Answer to the ABI probe.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AbiDeclaration {
+    pub tag: u8,
+    /// Bit `v - 1` is set for each supported input version.
+    pub input_versions: u16,
+    /// Bit `v - 1` is set for each supported output version.
+    pub output_versions: u16,
+    /// Requested `ohlcv` length within arena bounds; 0 (or absent) keeps
+    /// the arena's lookback.
+    pub lookback_len: u16,
+}
+
+impl AbiDeclaration {
+    /// Declares the given input and output versions (e.g. `&[1, 2]`).
+    pub fn new(input_versions: &[u8], output_versions: &[u8], lookback_len: u16) -> Self {
+        Self {
+            tag: ABI_DECLARATION_TAG,
+            input_versions: version_mask(input_versions),
+            output_versions: version_mask(output_versions),
+            lookback_len,
+        }
+    }
+
+    /// Decodes a probe answer; anything without the declaration tag is not
+    /// one. A head without the `lookback_len` trailer declares 0.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.first() != Some(&ABI_DECLARATION_TAG) {
+            return None;
+        }
+        let mut bytes = [0; ABI_DECLARATION_LEN];
+        if data.len() >= ABI_DECLARATION_LEN {
+            bytes.copy_from_slice(&data[..ABI_DECLARATION_LEN]);
+        } else {
+            let head = data.get(..ABI_DECLARATION_HEAD_LEN)?;
+            bytes[..ABI_DECLARATION_HEAD_LEN].copy_from_slice(head);
+        }
+        Self::try_from_slice(&bytes).ok()
+    }
+}
+
+fn version_mask(versions: &[u8]) -> u16 {
+    versions
+        .iter()
+        .filter(|v| (1..=16).contains(*v))
+        .fold(0, |mask, v| mask | 1 << (v - 1))
+}
+
+/// Highest version up to `max` whose bit is set in `mask`.
+pub fn highest_common_version(mask: u16, max: u8) -> Option<u8> {
+    (1..=max).rev().find(|v| mask & (1 << (v - 1)) != 0)
+}
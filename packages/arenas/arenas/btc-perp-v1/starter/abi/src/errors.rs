@@ -0,0 +1,47 @@
+/// `err_code` values (ABI §6). Codes are diagnostic and never affect scoring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum ErrCode {
+    Ok = 0,
+    InvalidInstructionData = 1,
+    InvalidInputVersion = 2,
+    InvalidLookbackLen = 3,
+    InputDeserFail = 4,
+    PolicyErr = 5,
+    OutputInvalid = 6,
+    OutputSerFail = 7,
+    TargetPositionInvalid = 8,
+    NotionalInvalid = 9,
+    ReduceOnlyInvalid = 10,
+    OrderTypeInvalid = 11,
+    TriggerPriceInvalid = 12,
+    /// Harness-assigned when a program breaks the account sandbox (ABI §3).
+    SandboxViolation = 13,
+    ActionTypeInvalid = 14,
+    OrderQtyInvalid = 15,
+    ReservedNonzero = 16,
+    TimeInForceInvalid = 17,
+    /// Runtime-assigned when a valid order type cannot be executed by the
+    /// simulator (resting orders), which then holds instead.
+    OrderTypeUnsupported = 18,
+}
+
+pub const ERR_OK: u16 = ErrCode::Ok as u16;
+pub const ERR_INVALID_INSTRUCTION_DATA: u16 = ErrCode::InvalidInstructionData as u16;
+pub const ERR_INVALID_INPUT_VERSION: u16 = ErrCode::InvalidInputVersion as u16;
+pub const ERR_INVALID_LOOKBACK_LEN: u16 = ErrCode::InvalidLookbackLen as u16;
+pub const ERR_INPUT_DESER_FAIL: u16 = ErrCode::InputDeserFail as u16;
+pub const ERR_POLICY_ERR: u16 = ErrCode::PolicyErr as u16;
+pub const ERR_OUTPUT_INVALID: u16 = ErrCode::OutputInvalid as u16;
+pub const ERR_OUTPUT_SER_FAIL: u16 = ErrCode::OutputSerFail as u16;
+pub const ERR_TARGET_POSITION_INVALID: u16 = ErrCode::TargetPositionInvalid as u16;
+pub const ERR_NOTIONAL_INVALID: u16 = ErrCode::NotionalInvalid as u16;
+pub const ERR_REDUCE_ONLY_INVALID: u16 = ErrCode::ReduceOnlyInvalid as u16;
+pub const ERR_ORDER_TYPE_INVALID: u16 = ErrCode::OrderTypeInvalid as u16;
+pub const ERR_TRIGGER_PRICE_INVALID: u16 = ErrCode::TriggerPriceInvalid as u16;
+pub const ERR_SANDBOX_VIOLATION: u16 = ErrCode::SandboxViolation as u16;
+pub const ERR_ACTION_TYPE_INVALID: u16 = ErrCode::ActionTypeInvalid as u16;
+pub const ERR_ORDER_QTY_INVALID: u16 = ErrCode::OrderQtyInvalid as u16;
+pub const ERR_RESERVED_NONZERO: u16 = ErrCode::ReservedNonzero as u16;
+pub const ERR_TIME_IN_FORCE_INVALID: u16 = ErrCode::TimeInForceInvalid as u16;
+pub const ERR_ORDER_TYPE_UNSUPPORTED: u16 = ErrCode::OrderTypeUnsupported as u16;
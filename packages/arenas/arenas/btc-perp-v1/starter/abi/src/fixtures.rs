@@ -0,0 +1,160 @@
+//! Golden byte fixtures for the account layouts. Both sides test their
+//! encoders and decoders against these, so any layout change shows up as a
+//! fixture diff.
+
+use crate::{
+    AbiDeclaration, Bar, DepthLevel, DepthSnapshot, EvalInputV1, EvalOutputV1, EvalOutputV2,
+    ACTION_FLAG_REDUCE_ONLY, ACTION_SELL, INPUT_VERSION_DEPTH, ORDER_STOP, OUTPUT_LEN,
+    OUTPUT_V2_LEN, OUTPUT_VERSION_ORDERS,
+};
+use alloc::vec;
+
+/// `EvalOutputV1::hold(0)`.
+pub const HOLD_V1: [u8; OUTPUT_LEN] = [
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+    0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// [`stop_v2`]: a reduce-only SELL 1 stop at 49_000_000_000, resting 10 bars.
+pub const STOP_V2: [u8; OUTPUT_V2_LEN] = [
+    2, 130, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, //
+    2, 0, 170, 160, 104, 11, 0, 0, 0, 10, 0, 0, //
+    0, 0, 0, 0, 0, 0, 0,
+];
+
+/// `AbiDeclaration::new(&[1, 2], &[1], 0)`, the starter's probe answer.
+pub const STARTER_DECLARATION: [u8; 7] = [171, 3, 0, 1, 0, 0, 0];
+
+/// [`sample_input`] with `version = 1`.
+pub const SAMPLE_INPUT_V1: [u8; 131] = [
+    1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, //
+    11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, //
+    23, 24, 25, 26, 27, 28, 29, 30, 31, 5, 0, 0, //
+    0, 60, 0, 0, 0, 64, 66, 15, 0, 64, 66, 15, //
+    0, 0, 228, 11, 84, 2, 0, 0, 0, 254, 255, 255, //
+    255, 255, 255, 255, 255, 0, 85, 49, 170, 11, 0, 0, //
+    0, 16, 39, 0, 0, 232, 3, 0, 0, 244, 1, 0, //
+    0, 1, 0, 1, 0, 0, 0, 0, 116, 59, 164, 11, //
+    0, 0, 0, 0, 217, 8, 194, 11, 0, 0, 0, 0, //
+    15, 110, 134, 11, 0, 0, 0, 0, 54, 39, 176, 11, //
+    0, 0, 0, 0, 202, 154, 59, 0, 0, 0, 0,
+];
+
+/// Depth trailer of [`sample_input`] at version 2. The full v2 encoding is
+/// [`SAMPLE_INPUT_V1`] with the version byte set to 2, followed by this.
+pub const SAMPLE_DEPTH_TRAILER: [u8; 41] = [
+    1, 1, 0, 0, 0, 128, 159, 142, 175, 11, 0, 0, //
+    0, 160, 37, 38, 0, 0, 0, 0, 0, 1, 0, 0, //
+    0, 128, 204, 191, 176, 11, 0, 0, 0, 64, 119, 27, //
+    0, 0, 0, 0, 0,
+];
+
+/// One-bar input, short 2 units, with a one-level book at `version`.
+pub fn sample_input(version: u8) -> EvalInputV1 {
+    let mut window_id = [0u8; 32];
+    for (i, byte) in window_id.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    EvalInputV1 {
+        version,
+        window_id,
+        step_index: 5,
+        bar_interval_seconds: 60,
+        price_scale: 1_000_000,
+        volume_scale: 1_000_000,
+        cash_balance: 10_000_000_000,
+        position_qty: -2,
+        avg_entry_price: 50_100_000_000,
+        max_leverage_bps: 10_000,
+        initial_margin_bps: 1_000,
+        maintenance_margin_bps: 500,
+        lookback_len: 1,
+        ohlcv: vec![Bar {
+            open: 50_000_000_000,
+            high: 50_500_000_000,
+            low: 49_500_000_000,
+            close: 50_200_000_000,
+            volume: 1_000_000_000,
+        }],
+        depth: (version >= INPUT_VERSION_DEPTH).then(|| DepthSnapshot {
+            bids: vec![DepthLevel {
+                price: 50_190_000_000,
+                size: 2_500_000,
+            }],
+            asks: vec![DepthLevel {
+                price: 50_210_000_000,
+                size: 1_800_000,
+            }],
+        }),
+        features: None,
+    }
+}
+
+pub fn stop_v2() -> EvalOutputV2 {
+    EvalOutputV2 {
+        version: OUTPUT_VERSION_ORDERS,
+        action_type: ACTION_SELL | ACTION_FLAG_REDUCE_ONLY,
+        order_qty: 1,
+        err_code: 0,
+        order_type: ORDER_STOP,
+        trigger_price: 49_000_000_000,
+        time_in_force_bars: 10,
+        reserved: [0u8; 8],
+    }
+}
+
+pub fn starter_declaration() -> AbiDeclaration {
+    AbiDeclaration::new(&[1, 2], &[1], 0)
+}
+
+pub fn hold_v1() -> EvalOutputV1 {
+    EvalOutputV1::hold(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ABI_DECLARATION_HEAD_LEN;
+    use alloc::vec::Vec;
+    use borsh::BorshDeserialize;
+
+    #[test]
+    fn outputs_match_golden_bytes() {
+        assert_eq!(borsh::to_vec(&hold_v1()).unwrap(), HOLD_V1);
+        assert_eq!(borsh::to_vec(&stop_v2()).unwrap(), STOP_V2);
+        assert_eq!(EvalOutputV2::try_from_slice(&STOP_V2).unwrap(), stop_v2());
+        assert_eq!(
+            borsh::to_vec(&starter_declaration()).unwrap(),
+            STARTER_DECLARATION
+        );
+        assert_eq!(
+            AbiDeclaration::decode(&STARTER_DECLARATION),
+            Some(starter_declaration())
+        );
+        // Declarations without the lookback trailer still decode.
+        assert_eq!(
+            AbiDeclaration::decode(&STARTER_DECLARATION[..ABI_DECLARATION_HEAD_LEN]),
+            Some(starter_declaration())
+        );
+        assert_eq!(AbiDeclaration::decode(&STARTER_DECLARATION[..4]), None);
+    }
+
+    #[test]
+    fn inputs_match_golden_bytes() {
+        let v1 = sample_input(1);
+        assert_eq!(v1.to_bytes().unwrap(), SAMPLE_INPUT_V1);
+        assert_eq!(EvalInputV1::from_bytes(&SAMPLE_INPUT_V1).unwrap(), v1);
+
+        let mut v2_bytes: Vec<u8> = SAMPLE_INPUT_V1.to_vec();
+        v2_bytes[0] = INPUT_VERSION_DEPTH;
+        v2_bytes.extend_from_slice(&SAMPLE_DEPTH_TRAILER);
+        let v2 = sample_input(INPUT_VERSION_DEPTH);
+        assert_eq!(v2.to_bytes().unwrap(), v2_bytes);
+        let decoded = EvalInputV1::from_bytes(&v2_bytes).unwrap();
+        assert_eq!(decoded, v2);
+        assert_eq!(decoded.depth.unwrap().spread(), Some(20_000_000));
+
+        v2_bytes.push(0);
+        assert!(EvalInputV1::from_bytes(&v2_bytes).is_err());
+    }
+}
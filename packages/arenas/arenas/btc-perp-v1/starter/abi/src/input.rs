@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+use borsh::io::{Error, ErrorKind, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub const INPUT_VERSION_DEPTH: u8 = 2;
+/// Highest input version defined by the ABI.
+pub const INPUT_VERSION_MAX: u8 = INPUT_VERSION_DEPTH;
+pub const MAX_DEPTH_LEVELS: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Bar {
+    pub open: i64,
+    pub high: i64,
+    pub low: i64,
+    pub close: i64,
+    pub volume: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DepthLevel {
+    pub price: i64,
+    pub size: i64,
+}
+
+/// L2 book snapshot, best level first on each side.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DepthSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+}
+
+impl DepthSnapshot {
+    pub fn best_bid(&self) -> Option<&DepthLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&DepthLevel> {
+        self.asks.first()
+    }
+
+    pub fn spread(&self) -> Option<i64> {
+        self.best_ask()?.price.checked_sub(self.best_bid()?.price)
+    }
+
+    /// Total bid size across the best `levels` levels.
+    pub fn bid_size(&self, levels: usize) -> i64 {
+        total_size(&self.bids, levels)
+    }
+
+    /// Total ask size across the best `levels` levels.
+    pub fn ask_size(&self, levels: usize) -> i64 {
+        total_size(&self.asks, levels)
+    }
+}
+
+fn total_size(side: &[DepthLevel], levels: usize) -> i64 {
+    side.iter()
+        .take(levels)
+        .fold(0i64, |acc, level| acc.saturating_add(level.size))
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvalInputV1 {
+    pub version: u8,
+    pub window_id: [u8; 32],
+    pub step_index: u32,
+    pub bar_interval_seconds: u32,
+    pub price_scale: u32,
+    pub volume_scale: u32,
+    pub cash_balance: i64,
+    pub position_qty: i64,
+    pub avg_entry_price: i64,
+    pub max_leverage_bps: u32,
+    pub initial_margin_bps: u32,
+    pub maintenance_margin_bps: u32,
+    pub lookback_len: u16,
+    pub ohlcv: Vec<Bar>,
+    /// Version 2 trailer, encoded after `ohlcv` as `Option<DepthSnapshot>`.
+    #[borsh(skip)]
+    pub depth: Option<DepthSnapshot>,
+    /// Harness-computed indicators from the optional features account; never
+    /// part of the input account bytes.
+    #[borsh(skip)]
+    pub features: Option<FeaturesV1>,
+}
+
+impl EvalInputV1 {
+    /// Encodes the input account bytes, appending the depth trailer for
+    /// version 2 and later.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = borsh::to_vec(self)?;
+        if self.version >= INPUT_VERSION_DEPTH {
+            bytes.extend(borsh::to_vec(&self.depth)?);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a version 1 input, or a version 2 input with its depth trailer.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut rest = data;
+        let mut input = Self::deserialize(&mut rest)?;
+        if input.version >= INPUT_VERSION_DEPTH {
+            input.depth = Option::<DepthSnapshot>::deserialize(&mut rest)?;
+        }
+        if !rest.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "trailing input bytes"));
+        }
+        Ok(input)
+    }
+}
+
+pub const FEATURES_VERSION: u8 = 1;
+pub const FEATURE_SMA: u8 = 1;
+pub const FEATURE_EMA: u8 = 2;
+pub const FEATURE_ATR: u8 = 3;
+pub const FEATURE_REALIZED_VOL: u8 = 4;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeatureValue {
+    pub kind: u8,
+    pub period: u16,
+    /// `None` until the window has enough bars for the period.
+    pub value: Option<i64>,
+}
+
+/// Contents of the optional read-only features account, one value per
+/// indicator configured at init, in configuration order.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeaturesV1 {
+    pub version: u8,
+    pub step_index: u32,
+    pub values: Vec<FeatureValue>,
+}
+
+impl FeaturesV1 {
+    /// Value of the indicator configured as `(kind, period)`, if present and
+    /// warmed up.
+    pub fn get(&self, kind: u8, period: u16) -> Option<i64> {
+        self.values
+            .iter()
+            .find(|v| v.kind == kind && v.period == period)?
+            .value
+    }
+
+    pub fn sma(&self, period: u16) -> Option<i64> {
+        self.get(FEATURE_SMA, period)
+    }
+
+    pub fn ema(&self, period: u16) -> Option<i64> {
+        self.get(FEATURE_EMA, period)
+    }
+
+    pub fn atr(&self, period: u16) -> Option<i64> {
+        self.get(FEATURE_ATR, period)
+    }
+
+    /// Realized volatility of close-to-close returns, in ppm.
+    pub fn realized_vol(&self, period: u16) -> Option<i64> {
+        self.get(FEATURE_REALIZED_VOL, period)
+    }
+}
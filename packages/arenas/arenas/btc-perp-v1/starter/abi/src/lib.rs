@@ -0,0 +1,21 @@
+//! SolClash policy ABI (`docs/solclash-onchain-abi.md`): the Borsh account
+//! layouts, action/order/err code constants, the ABI probe, and the output
+//! validation rules. The starter program and the harness both depend on this
+//! crate so the two sides cannot drift.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod declaration;
+mod errors;
+pub mod fixtures;
+mod input;
+mod output;
+pub mod validation;
+
+pub use declaration::*;
+pub use errors::*;
+pub use input::*;
+pub use output::*;
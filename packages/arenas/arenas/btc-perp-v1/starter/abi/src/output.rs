@@ -0,0 +1,161 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub const OUTPUT_LEN: usize = 20;
+pub const OUTPUT_V2_LEN: usize = 31;
+pub const OUTPUT_V3_LEN: usize = 63;
+pub const OUTPUT_VERSION_ORDERS: u8 = 2;
+pub const OUTPUT_VERSION_ANNOTATED: u8 = 3;
+/// Highest output version defined by the ABI.
+pub const OUTPUT_VERSION_MAX: u8 = OUTPUT_VERSION_ANNOTATED;
+pub const ANNOTATION_LEN: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
+pub enum ActionType {
+    Hold = 0,
+    Buy = 1,
+    Sell = 2,
+    Close = 3,
+    /// `order_qty` is the desired signed position.
+    TargetPosition = 4,
+    /// `order_qty` is a notional in quote units.
+    BuyNotional = 5,
+    SellNotional = 6,
+}
+
+pub const ACTION_HOLD: u8 = ActionType::Hold as u8;
+pub const ACTION_BUY: u8 = ActionType::Buy as u8;
+pub const ACTION_SELL: u8 = ActionType::Sell as u8;
+pub const ACTION_CLOSE: u8 = ActionType::Close as u8;
+pub const ACTION_TARGET_POSITION: u8 = ActionType::TargetPosition as u8;
+pub const ACTION_BUY_NOTIONAL: u8 = ActionType::BuyNotional as u8;
+pub const ACTION_SELL_NOTIONAL: u8 = ActionType::SellNotional as u8;
+/// High bit of `action_type`; the low bits carry the action itself. Forbids
+/// growing or flipping the position.
+pub const ACTION_FLAG_REDUCE_ONLY: u8 = 0x80;
+
+pub const ORDER_MARKET: u8 = 0;
+pub const ORDER_LIMIT: u8 = 1;
+pub const ORDER_STOP: u8 = 2;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvalOutputV1 {
+    pub version: u8,
+    pub action_type: u8,
+    pub order_qty: i64,
+    pub err_code: u16,
+    pub reserved: [u8; 8],
+}
+
+impl EvalOutputV1 {
+    pub fn hold(err_code: u16) -> Self {
+        Self {
+            version: 1,
+            action_type: ACTION_HOLD,
+            order_qty: 0,
+            err_code,
+            reserved: [0u8; 8],
+        }
+    }
+
+    pub fn new(action: ActionType, order_qty: i64) -> Self {
+        Self {
+            version: 1,
+            action_type: action as u8,
+            order_qty,
+            err_code: 0,
+            reserved: [0u8; 8],
+        }
+    }
+
+    pub fn target_position(position_qty: i64) -> Self {
+        Self::new(ActionType::TargetPosition, position_qty)
+    }
+
+    pub fn reduce_only(mut self) -> Self {
+        self.action_type |= ACTION_FLAG_REDUCE_ONLY;
+        self
+    }
+
+    pub fn is_reduce_only(&self) -> bool {
+        self.action_type & ACTION_FLAG_REDUCE_ONLY != 0
+    }
+}
+
+/// Version 2 output: the v1 fields plus a resting order (limit price or stop
+/// trigger) with a time-in-force in bars.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvalOutputV2 {
+    pub version: u8,
+    pub action_type: u8,
+    pub order_qty: i64,
+    pub err_code: u16,
+    pub order_type: u8,
+    pub trigger_price: i64,
+    pub time_in_force_bars: u16,
+    pub reserved: [u8; 8],
+}
+
+/// Lifts a v1 output into the v2 shape as a market order, keeping `version`.
+impl From<EvalOutputV1> for EvalOutputV2 {
+    fn from(output: EvalOutputV1) -> Self {
+        Self {
+            version: output.version,
+            action_type: output.action_type,
+            order_qty: output.order_qty,
+            err_code: output.err_code,
+            order_type: ORDER_MARKET,
+            trigger_price: 0,
+            time_in_force_bars: 0,
+            reserved: output.reserved,
+        }
+    }
+}
+
+/// Version 3 output: the v2 fields plus free-form agent annotation bytes
+/// (e.g. a signal id, confidence or regime tag).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvalOutputV3 {
+    pub version: u8,
+    pub action_type: u8,
+    pub order_qty: i64,
+    pub err_code: u16,
+    pub order_type: u8,
+    pub trigger_price: i64,
+    pub time_in_force_bars: u16,
+    pub reserved: [u8; 8],
+    pub annotation: [u8; ANNOTATION_LEN],
+}
+
+impl EvalOutputV3 {
+    pub fn hold(err_code: u16) -> Self {
+        EvalOutputV2::from(EvalOutputV1::hold(err_code)).into()
+    }
+}
+
+/// Lifts a v2 output into the v3 shape with a zeroed annotation, keeping `version`.
+impl From<EvalOutputV2> for EvalOutputV3 {
+    fn from(output: EvalOutputV2) -> Self {
+        Self {
+            version: output.version,
+            action_type: output.action_type,
+            order_qty: output.order_qty,
+            err_code: output.err_code,
+            order_type: output.order_type,
+            trigger_price: output.trigger_price,
+            time_in_force_bars: output.time_in_force_bars,
+            reserved: output.reserved,
+            annotation: [0u8; ANNOTATION_LEN],
+        }
+    }
+}
+
+/// Serialized length of an output of the given version.
+pub fn output_len(version: u8) -> usize {
+    match version {
+        OUTPUT_VERSION_ANNOTATED => OUTPUT_V3_LEN,
+        OUTPUT_VERSION_ORDERS => OUTPUT_V2_LEN,
+        _ => OUTPUT_LEN,
+    }
+}
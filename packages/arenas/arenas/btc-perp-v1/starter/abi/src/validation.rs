@@ -0,0 +1,181 @@
+//! Output validation rules from `solclash-onchain-abi.md` §6–7, applied by
+//! both the starter program and the harness.
+
+use crate::{EvalOutputV1, EvalOutputV2, EvalOutputV3};
+use crate::{
+    ACTION_BUY, ACTION_BUY_NOTIONAL, ACTION_CLOSE, ACTION_FLAG_REDUCE_ONLY, ACTION_HOLD,
+    ACTION_SELL, ACTION_SELL_NOTIONAL, ACTION_TARGET_POSITION, ERR_ACTION_TYPE_INVALID,
+    ERR_NOTIONAL_INVALID, ERR_ORDER_QTY_INVALID, ERR_ORDER_TYPE_INVALID, ERR_OUTPUT_INVALID,
+    ERR_REDUCE_ONLY_INVALID, ERR_RESERVED_NONZERO, ERR_TARGET_POSITION_INVALID,
+    ERR_TIME_IN_FORCE_INVALID, ERR_TRIGGER_PRICE_INVALID, ORDER_LIMIT, ORDER_MARKET, ORDER_STOP,
+};
+
+/// The output fields the rules read, common to every output version. V1
+/// outputs are market orders with zeroed order fields.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputFields {
+    pub version: u8,
+    pub action_type: u8,
+    pub order_qty: i64,
+    pub order_type: u8,
+    pub trigger_price: i64,
+    pub time_in_force_bars: u16,
+    pub reserved: [u8; 8],
+}
+
+impl From<&EvalOutputV1> for OutputFields {
+    fn from(output: &EvalOutputV1) -> Self {
+        Self::from(&EvalOutputV2::from(output.clone()))
+    }
+}
+
+impl From<&EvalOutputV2> for OutputFields {
+    fn from(output: &EvalOutputV2) -> Self {
+        Self {
+            version: output.version,
+            action_type: output.action_type,
+            order_qty: output.order_qty,
+            order_type: output.order_type,
+            trigger_price: output.trigger_price,
+            time_in_force_bars: output.time_in_force_bars,
+            reserved: output.reserved,
+        }
+    }
+}
+
+impl From<&EvalOutputV3> for OutputFields {
+    fn from(output: &EvalOutputV3) -> Self {
+        Self {
+            version: output.version,
+            action_type: output.action_type,
+            order_qty: output.order_qty,
+            order_type: output.order_type,
+            trigger_price: output.trigger_price,
+            time_in_force_bars: output.time_in_force_bars,
+            reserved: output.reserved,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    Version,
+    ActionType,
+    Reserved,
+    OrderQty,
+    Notional,
+    TargetPosition,
+    ReduceOnly,
+    OrderType,
+    TriggerPrice,
+    TimeInForce,
+}
+
+impl Violation {
+    pub fn err_code(self) -> u16 {
+        match self {
+            Violation::Version => ERR_OUTPUT_INVALID,
+            Violation::ActionType => ERR_ACTION_TYPE_INVALID,
+            Violation::Reserved => ERR_RESERVED_NONZERO,
+            Violation::OrderQty => ERR_ORDER_QTY_INVALID,
+            Violation::Notional => ERR_NOTIONAL_INVALID,
+            Violation::TargetPosition => ERR_TARGET_POSITION_INVALID,
+            Violation::ReduceOnly => ERR_REDUCE_ONLY_INVALID,
+            Violation::OrderType => ERR_ORDER_TYPE_INVALID,
+            Violation::TriggerPrice => ERR_TRIGGER_PRICE_INVALID,
+            Violation::TimeInForce => ERR_TIME_IN_FORCE_INVALID,
+        }
+    }
+
+    pub fn reason(self) -> &'static str {
+        match self {
+            Violation::Version => "unsupported output version",
+            Violation::ActionType => "unknown action_type",
+            Violation::Reserved => "reserved bytes must be zero",
+            Violation::OrderQty => {
+                "order_qty must be positive for BUY/SELL and non-negative for HOLD/CLOSE"
+            }
+            Violation::Notional => "notional order_qty must be positive",
+            Violation::TargetPosition => "target position must not be i64::MIN",
+            Violation::ReduceOnly => "reduce-only action would grow the position",
+            Violation::OrderType => "invalid order_type for this action",
+            Violation::TriggerPrice => "trigger_price must be positive for LIMIT/STOP",
+            Violation::TimeInForce => "time_in_force_bars must be positive for LIMIT/STOP",
+        }
+    }
+}
+
+/// Checks `output` against every rule, in spec order. `max_version` is the
+/// highest output version the caller accepts.
+pub fn validate_output(
+    output: &OutputFields,
+    max_version: u8,
+    position_qty: i64,
+) -> Result<(), Violation> {
+    if output.version == 0 || output.version > max_version {
+        return Err(Violation::Version);
+    }
+    let reduce_only = output.action_type & ACTION_FLAG_REDUCE_ONLY != 0;
+    let action = output.action_type & !ACTION_FLAG_REDUCE_ONLY;
+    if action > ACTION_SELL_NOTIONAL {
+        return Err(Violation::ActionType);
+    }
+    if output.reserved != [0u8; 8] {
+        return Err(Violation::Reserved);
+    }
+    let qty = output.order_qty;
+    match action {
+        ACTION_BUY | ACTION_SELL if qty <= 0 => return Err(Violation::OrderQty),
+        ACTION_HOLD | ACTION_CLOSE if qty < 0 => return Err(Violation::OrderQty),
+        ACTION_BUY_NOTIONAL | ACTION_SELL_NOTIONAL if qty <= 0 => return Err(Violation::Notional),
+        // The target must be negatable to derive a delta.
+        ACTION_TARGET_POSITION if qty == i64::MIN => return Err(Violation::TargetPosition),
+        _ => {}
+    }
+    if reduce_only && !reduces_position(action, qty, position_qty) {
+        return Err(Violation::ReduceOnly);
+    }
+    validate_order(output, action)
+}
+
+/// Resting orders may only open or exit via BUY, SELL or CLOSE; market orders
+/// must leave the order fields zeroed.
+fn validate_order(output: &OutputFields, action: u8) -> Result<(), Violation> {
+    match output.order_type {
+        ORDER_MARKET => {
+            if output.trigger_price != 0 || output.time_in_force_bars != 0 {
+                return Err(Violation::OrderType);
+            }
+        }
+        ORDER_LIMIT | ORDER_STOP => {
+            if !matches!(action, ACTION_BUY | ACTION_SELL | ACTION_CLOSE) {
+                return Err(Violation::OrderType);
+            }
+            if output.trigger_price <= 0 {
+                return Err(Violation::TriggerPrice);
+            }
+            if output.time_in_force_bars == 0 {
+                return Err(Violation::TimeInForce);
+            }
+        }
+        _ => return Err(Violation::OrderType),
+    }
+    Ok(())
+}
+
+/// Whether a reduce-only action can shrink the position. Deltas that would
+/// flip it are clipped to a close at execution, so only growth (or any trade
+/// from flat) is rejected.
+fn reduces_position(action: u8, qty: i64, position_qty: i64) -> bool {
+    match action {
+        ACTION_HOLD | ACTION_CLOSE => true,
+        ACTION_BUY | ACTION_BUY_NOTIONAL => position_qty < 0,
+        ACTION_SELL | ACTION_SELL_NOTIONAL => position_qty > 0,
+        ACTION_TARGET_POSITION => {
+            qty == 0
+                || (position_qty != 0
+                    && (qty.signum() != position_qty.signum() || qty.abs() <= position_qty.abs()))
+        }
+        _ => false,
+    }
+}
@@ -1,4 +1,3 @@
-use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint,
@@ -9,12 +8,16 @@ use solana_program::{
 
 mod errors;
 mod policy;
-mod types;
+pub mod types;
 #[cfg(test)]
 mod tests;
 
+use borsh::BorshDeserialize;
 use errors::ErrCode;
-use types::{EvalInputV1, EvalOutputV1};
+use solclash_abi::validation::{self, OutputFields};
+use types::{
+    EvalInputV1, EvalOutputV1, FeaturesV1, ABI_PROBE_IX, FEATURES_VERSION, INPUT_VERSION_DEPTH,
+};
 
 entrypoint!(process_instruction);
 
@@ -33,18 +36,28 @@ pub fn process_instruction(
         Err(_) => return Ok(()),
     };
 
+    if instruction_data == ABI_PROBE_IX {
+        return write_declaration(output_account);
+    }
     if !instruction_data.is_empty() {
         return write_output(output_account, EvalOutputV1::hold(ErrCode::InvalidInstructionData as u16));
     }
 
-    let input = match EvalInputV1::try_from_slice(&input_account.data.borrow()) {
+    let mut input = match EvalInputV1::from_bytes(&input_account.data.borrow()) {
         Ok(v) => v,
         Err(_) => {
             return write_output(output_account, EvalOutputV1::hold(ErrCode::InputDeserFail as u16));
         }
     };
+    // The features account is optional; an unreadable one is ignored rather
+    // than failing the step.
+    if let Ok(features_account) = next_account_info(&mut accounts_iter) {
+        input.features = FeaturesV1::try_from_slice(&features_account.data.borrow())
+            .ok()
+            .filter(|f| f.version == FEATURES_VERSION && f.step_index == input.step_index);
+    }
 
-    if input.version != 1 {
+    if input.version != 1 && input.version != INPUT_VERSION_DEPTH {
         return write_output(output_account, EvalOutputV1::hold(ErrCode::InvalidInputVersion as u16));
     }
     if input.lookback_len as usize != input.ohlcv.len() {
@@ -56,19 +69,20 @@ pub fn process_instruction(
         Err(_) => EvalOutputV1::hold(ErrCode::PolicyErr as u16),
     };
 
-    let output = validate_output(output);
+    let output = validate_output(output, input.position_qty);
     write_output(output_account, output)
 }
 
-fn validate_output(mut output: EvalOutputV1) -> EvalOutputV1 {
-    if output.version != 1 {
-        return EvalOutputV1::hold(ErrCode::OutputInvalid as u16);
-    }
-    if (output.action_type == 1 || output.action_type == 2) && output.order_qty <= 0 {
-        return EvalOutputV1::hold(ErrCode::OutputInvalid as u16);
+/// Applies the shared ABI output rules; any violation becomes HOLD with the
+/// rule's err code.
+fn validate_output(output: EvalOutputV1, position_qty: i64) -> EvalOutputV1 {
+    match validation::validate_output(&OutputFields::from(&output), 1, position_qty) {
+        Ok(()) => output,
+        Err(violation) => {
+            msg!("invalid output: {}", violation.reason());
+            EvalOutputV1::hold(violation.err_code())
+        }
     }
-    output.reserved = [0u8; 8];
-    output
 }
 
 fn write_output(output_account: &AccountInfo, output: EvalOutputV1) -> ProgramResult {
@@ -91,3 +105,16 @@ fn write_output(output_account: &AccountInfo, output: EvalOutputV1) -> ProgramRe
     data[..serialized.len()].copy_from_slice(&serialized);
     Ok(())
 }
+
+fn write_declaration(output_account: &AccountInfo) -> ProgramResult {
+    let serialized = match borsh::to_vec(&types::declaration()) {
+        Ok(bytes) => bytes,
+        Err(_) => return Ok(()),
+    };
+    let mut data = output_account.data.borrow_mut();
+    if data.len() < serialized.len() {
+        return Ok(());
+    }
+    data[..serialized.len()].copy_from_slice(&serialized);
+    Ok(())
+}
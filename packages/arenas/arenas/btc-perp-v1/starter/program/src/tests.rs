@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
     use borsh::BorshDeserialize;
-    use crate::types::{EvalInputV1, EvalOutputV1};
+    use crate::types::{
+        ActionType, DepthLevel, DepthSnapshot, EvalInputV1, EvalOutputV1, FeatureValue, FeaturesV1,
+        FEATURE_ATR, FEATURE_SMA,
+    };
 
     #[test]
     fn output_roundtrip() {
@@ -35,10 +38,104 @@ mod tests {
             maintenance_margin_bps: 500,
             lookback_len: 0,
             ohlcv: Vec::new(),
+            depth: None,
+            features: None,
         };
         assert_eq!(input.version, 2);
         // Simulate policy output for invalid input version should be HOLD.
         let out = EvalOutputV1::hold(2);
         assert_eq!(out.action_type, 0);
     }
+
+    #[test]
+    fn v2_input_decodes_depth_trailer() {
+        let mut input = EvalInputV1 {
+            version: 2,
+            window_id: [0u8; 32],
+            step_index: 0,
+            bar_interval_seconds: 60,
+            price_scale: 1_000_000,
+            volume_scale: 1_000_000,
+            cash_balance: 10_000,
+            position_qty: 0,
+            avg_entry_price: 0,
+            max_leverage_bps: 10_000,
+            initial_margin_bps: 1_000,
+            maintenance_margin_bps: 500,
+            lookback_len: 0,
+            ohlcv: Vec::new(),
+            depth: None,
+            features: None,
+        };
+        let depth = DepthSnapshot {
+            bids: vec![DepthLevel { price: 99, size: 3 }, DepthLevel { price: 98, size: 4 }],
+            asks: vec![DepthLevel { price: 101, size: 5 }],
+        };
+        let mut bytes = borsh::to_vec(&input).expect("serialize");
+        bytes.extend(borsh::to_vec(&Some(depth)).expect("serialize depth"));
+
+        input = EvalInputV1::from_bytes(&bytes).expect("decode");
+        let depth = input.depth.expect("depth present");
+        assert_eq!(depth.spread(), Some(2));
+        assert_eq!(depth.bid_size(2), 7);
+        assert_eq!(depth.ask_size(8), 5);
+    }
+
+    #[test]
+    fn reduce_only_target_cannot_grow() {
+        let out = EvalOutputV1::target_position(3).reduce_only();
+        assert!(out.is_reduce_only());
+        let checked = crate::validate_output(out, 2);
+        assert_eq!(checked.action_type, ActionType::Hold as u8);
+        assert_eq!(checked.err_code, crate::types::ERR_REDUCE_ONLY_INVALID);
+
+        // A flip is kept; execution clips it to a close.
+        let flip = crate::validate_output(EvalOutputV1::target_position(-1).reduce_only(), 2);
+        assert_eq!(flip.err_code, 0);
+        assert_eq!(flip.order_qty, -1);
+
+        let trim = crate::validate_output(EvalOutputV1::target_position(1).reduce_only(), 2);
+        assert_eq!(trim.err_code, 0);
+        assert_eq!(trim.order_qty, 1);
+    }
+
+    #[test]
+    fn features_lookup_by_kind_and_period() {
+        let features = FeaturesV1 {
+            version: 1,
+            step_index: 9,
+            values: vec![
+                FeatureValue { kind: FEATURE_SMA, period: 5, value: Some(101) },
+                FeatureValue { kind: FEATURE_ATR, period: 14, value: None },
+            ],
+        };
+        let bytes = borsh::to_vec(&features).expect("serialize");
+        let decoded = FeaturesV1::try_from_slice(&bytes).expect("deserialize");
+        assert_eq!(decoded.sma(5), Some(101));
+        assert_eq!(decoded.sma(20), None);
+        assert_eq!(decoded.get(FEATURE_ATR, 14), None);
+    }
+
+    #[test]
+    fn strict_output_rules_reject_unknown_action_and_reserved_bytes() {
+        use crate::types::{
+            ERR_ACTION_TYPE_INVALID, ERR_ORDER_QTY_INVALID, ERR_RESERVED_NONZERO,
+        };
+
+        let unknown = EvalOutputV1 { action_type: 9, ..EvalOutputV1::hold(0) };
+        assert_eq!(crate::validate_output(unknown, 0).err_code, ERR_ACTION_TYPE_INVALID);
+
+        let mut reserved = EvalOutputV1::hold(0);
+        reserved.reserved[3] = 1;
+        assert_eq!(crate::validate_output(reserved, 0).err_code, ERR_RESERVED_NONZERO);
+
+        let negative_close = EvalOutputV1::new(ActionType::Close, -1);
+        assert_eq!(crate::validate_output(negative_close, 0).err_code, ERR_ORDER_QTY_INVALID);
+    }
+
+    #[test]
+    fn declaration_matches_abi_fixture() {
+        let bytes = borsh::to_vec(&crate::types::declaration()).expect("serialize");
+        assert_eq!(bytes, crate::types::fixtures::STARTER_DECLARATION);
+    }
 }
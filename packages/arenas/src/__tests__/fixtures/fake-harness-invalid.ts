@@ -0,0 +1,19 @@
+for await (const line of console) {
+  const msg = JSON.parse(line);
+  if (msg.type === "init") {
+    console.log(JSON.stringify({ type: "ok", request_id: msg.request_id }));
+  } else if (msg.type === "eval") {
+    console.log(
+      JSON.stringify({
+        type: "error",
+        request_id: msg.request_id,
+        message: "invalid input: ohlcv[0].high: must be >= open and close",
+        code: "INVALID_INPUT",
+        field: "ohlcv[0].high",
+      }),
+    );
+  } else if (msg.type === "shutdown") {
+    console.log(JSON.stringify({ type: "ok", request_id: msg.request_id }));
+    process.exit(0);
+  }
+}
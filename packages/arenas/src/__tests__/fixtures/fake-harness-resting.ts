@@ -0,0 +1,27 @@
+for await (const line of console) {
+  const msg = JSON.parse(line);
+  if (msg.type === "init") {
+    console.log(JSON.stringify({ type: "ok", request_id: msg.request_id }));
+  } else if (msg.type === "eval") {
+    console.log(
+      JSON.stringify({
+        type: "result",
+        request_id: msg.request_id,
+        agent_id: msg.agent_id,
+        status: "OK",
+        output: {
+          version: 2,
+          action_type: 1,
+          order_qty: "1",
+          err_code: 0,
+          order_type: 1,
+          trigger_price: "99000000",
+          time_in_force_bars: 5,
+        },
+      }),
+    );
+  } else if (msg.type === "shutdown") {
+    console.log(JSON.stringify({ type: "ok", request_id: msg.request_id }));
+    process.exit(0);
+  }
+}
@@ -1,7 +1,18 @@
 for await (const line of console) {
   const msg = JSON.parse(line);
   if (msg.type === "init") {
-    console.log(JSON.stringify({ type: "ok", request_id: msg.request_id }));
+    // Programs and baselines whose id starts with "reject" are refused, like
+    // a bad artifact.
+    const ids: string[] = [
+      ...msg.programs.map((p: { id: string }) => p.id),
+      ...(msg.baselines ?? []),
+    ];
+    const rejected = ids
+      .filter((id) => id.startsWith("reject"))
+      .map((id) => ({ id, reason: "invalid artifact" }));
+    console.log(
+      JSON.stringify({ type: "ok", request_id: msg.request_id, rejected }),
+    );
   } else if (msg.type === "eval") {
     console.log(
       JSON.stringify({
@@ -1,5 +1,9 @@
 import { describe, test, expect } from "bun:test";
-import { HarnessClient } from "../runtime/harness.js";
+import {
+  ERR_ORDER_TYPE_UNSUPPORTED,
+  HarnessClient,
+  HarnessInputError,
+} from "../runtime/harness.js";
 import type { EvalInputV1 } from "@solclash/simulator";
 import { join } from "node:path";
 
@@ -110,4 +114,195 @@ describe("HarnessClient", () => {
 
     await harness.shutdown();
   });
+
+  test("init reports rejected programs and keeps the accepted ones", async () => {
+    const harnessPath = process.execPath;
+    const scriptPath = join(import.meta.dir, "fixtures", "fake-harness.ts");
+
+    const harness = await HarnessClient.start(
+      harnessPath,
+      [
+        { id: "agent-1", so_path: "/tmp/fake.so" },
+        { id: "rejected-1", so_path: "/tmp/bad.so" },
+      ],
+      200_000,
+      [scriptPath],
+    );
+
+    expect(harness.rejected).toEqual({ "rejected-1": "invalid artifact" });
+
+    const input: EvalInputV1 = {
+      version: 1,
+      window_id: "w0",
+      step_index: 0,
+      bar_interval_seconds: 60,
+      lookback_len: 1,
+      instrument: {
+        symbol: "BTC-PERP",
+        base_mint: "BTC",
+        quote_mint: "USDC",
+        price_scale: 1_000_000,
+        volume_scale: 1_000_000,
+      },
+      account: {
+        cash_balance: 10000,
+        position_qty: 0,
+        avg_entry_price: 0,
+      },
+      max_leverage_bps: 10000,
+      initial_margin_bps: 1000,
+      maintenance_margin_bps: 500,
+      ohlcv: [
+        {
+          symbol: "BTC-PERP",
+          bar_start_ts_ms: 0,
+          bar_end_ts_ms: 60000,
+          open: 100,
+          high: 101,
+          low: 99,
+          close: 100,
+          volume: 100,
+        },
+      ],
+    };
+
+    const output = await harness.eval("agent-1", input);
+    expect(output.action_type).toBe(0);
+
+    await harness.shutdown();
+  });
+
+  test("init loads baselines in the harness alongside programs", async () => {
+    const harnessPath = process.execPath;
+    const scriptPath = join(import.meta.dir, "fixtures", "fake-harness.ts");
+
+    const harness = await HarnessClient.start(
+      harnessPath,
+      [{ id: "agent-1", so_path: "/tmp/fake.so" }],
+      200_000,
+      [scriptPath],
+      ["FLAT", "reject-baseline"],
+    );
+
+    expect(harness.rejected).toEqual({
+      "reject-baseline": "invalid artifact",
+    });
+
+    await harness.shutdown();
+  });
+
+  test("holds resting orders with ORDER_TYPE_UNSUPPORTED", async () => {
+    const harnessPath = process.execPath;
+    const scriptPath = join(
+      import.meta.dir,
+      "fixtures",
+      "fake-harness-resting.ts",
+    );
+
+    const harness = await HarnessClient.start(
+      harnessPath,
+      [{ id: "agent-1", so_path: "/tmp/fake.so" }],
+      200_000,
+      [scriptPath],
+    );
+
+    const input: EvalInputV1 = {
+      version: 1,
+      window_id: "w0",
+      step_index: 0,
+      bar_interval_seconds: 60,
+      lookback_len: 1,
+      instrument: {
+        symbol: "BTC-PERP",
+        base_mint: "BTC",
+        quote_mint: "USDC",
+        price_scale: 1_000_000,
+        volume_scale: 1_000_000,
+      },
+      account: {
+        cash_balance: 10000,
+        position_qty: 0,
+        avg_entry_price: 0,
+      },
+      max_leverage_bps: 10000,
+      initial_margin_bps: 1000,
+      maintenance_margin_bps: 500,
+      ohlcv: [
+        {
+          symbol: "BTC-PERP",
+          bar_start_ts_ms: 0,
+          bar_end_ts_ms: 60000,
+          open: 100,
+          high: 101,
+          low: 99,
+          close: 100,
+          volume: 100,
+        },
+      ],
+    };
+
+    const output = await harness.eval("agent-1", input);
+    expect(output.action_type).toBe(0);
+    expect(output.order_qty).toBe(0);
+    expect(output.err_code).toBe(ERR_ORDER_TYPE_UNSUPPORTED);
+
+    await harness.shutdown();
+  });
+
+  test("throws INVALID_INPUT as a harness input error", async () => {
+    const harnessPath = process.execPath;
+    const scriptPath = join(
+      import.meta.dir,
+      "fixtures",
+      "fake-harness-invalid.ts",
+    );
+
+    const harness = await HarnessClient.start(
+      harnessPath,
+      [{ id: "agent-1", so_path: "/tmp/fake.so" }],
+      200_000,
+      [scriptPath],
+    );
+
+    const input: EvalInputV1 = {
+      version: 1,
+      window_id: "w0",
+      step_index: 0,
+      bar_interval_seconds: 60,
+      lookback_len: 1,
+      instrument: {
+        symbol: "BTC-PERP",
+        base_mint: "BTC",
+        quote_mint: "USDC",
+        price_scale: 1_000_000,
+        volume_scale: 1_000_000,
+      },
+      account: {
+        cash_balance: 10000,
+        position_qty: 0,
+        avg_entry_price: 0,
+      },
+      max_leverage_bps: 10000,
+      initial_margin_bps: 1000,
+      maintenance_margin_bps: 500,
+      ohlcv: [
+        {
+          symbol: "BTC-PERP",
+          bar_start_ts_ms: 0,
+          bar_end_ts_ms: 60000,
+          open: 100,
+          high: 99,
+          low: 99,
+          close: 100,
+          volume: 100,
+        },
+      ],
+    };
+
+    const error = await harness.eval("agent-1", input).catch((err) => err);
+    expect(error).toBeInstanceOf(HarnessInputError);
+    expect(error.field).toBe("ohlcv[0].high");
+
+    await harness.shutdown();
+  });
 });
@@ -103,6 +103,7 @@ describe("arena on-chain e2e", () => {
           [{ id: "starter", so_path: soPath }],
           config.compute_unit_limit,
         );
+        expect(harness.rejected).toEqual({});
 
         const bars = makeFixtureBars(20);
 
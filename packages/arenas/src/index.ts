@@ -176,7 +176,11 @@ export {
   writeWindowLogs,
   type RoundMeta,
 } from "./runtime/logger.js";
-export { HarnessClient, type HarnessProgram } from "./runtime/harness.js";
+export {
+  HarnessClient,
+  HarnessInputError,
+  type HarnessProgram,
+} from "./runtime/harness.js";
 export {
   buildPolicies,
   prepareProgramsAndInvalidAgents,
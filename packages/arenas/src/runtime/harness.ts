@@ -1,6 +1,22 @@
+import { ActionType, PolicyRuntimeError } from "@solclash/simulator";
 import type { EvalInputV1, EvalOutputV1 } from "@solclash/simulator";
 import type { FileSink, Subprocess } from "bun";
 
+// ORDER_TYPE_UNSUPPORTED (ABI §6): a valid resting order the simulator holds.
+export const ERR_ORDER_TYPE_UNSUPPORTED = 18;
+
+// The harness refused an eval input (`INVALID_INPUT`): a bug in the caller,
+// not the agent, so it is not recorded as an agent failure.
+export class HarnessInputError extends PolicyRuntimeError {
+  readonly field?: string;
+
+  constructor(message: string, field?: string) {
+    super(message);
+    this.name = "HarnessInputError";
+    this.field = field;
+  }
+}
+
 export interface HarnessProgram {
   id: string;
   so_path: string;
@@ -34,6 +50,10 @@ type HarnessEvalOutput = {
   action_type: number;
   order_qty: string;
   err_code: number;
+  order_type?: number;
+  trigger_price?: string;
+  time_in_force_bars?: number;
+  annotation?: string;
 };
 
 type HarnessRequest =
@@ -42,6 +62,8 @@ type HarnessRequest =
       request_id: number;
       programs: HarnessProgram[];
       compute_unit_limit?: number;
+      // Harness-native baseline policies, evaluated under their ids.
+      baselines?: string[];
     }
   | {
       type: "eval";
@@ -55,13 +77,28 @@ type HarnessRequest =
     };
 
 type HarnessResponse =
-  | { type: "ok"; request_id: number }
-  | { type: "error"; request_id: number; message: string }
+  | {
+      type: "ok";
+      request_id: number;
+      rejected?: { id: string; reason: string }[];
+    }
+  | {
+      type: "error";
+      request_id: number;
+      message: string;
+      // "INVALID_INPUT" when the harness refused the input, with its field.
+      code?: string;
+      field?: string;
+    }
   | {
       type: "result";
       request_id: number;
       agent_id: string;
+      // "OK", or "SANDBOX_VIOLATION" with a HOLD output and a reason.
       status: string;
+      reason?: string;
+      // Set when the output broke an ABI rule and was replaced by HOLD.
+      validation_reason?: string;
       output: HarnessEvalOutput;
     };
 
@@ -78,6 +115,11 @@ export class HarnessClient {
   private closed = false;
   private decoder = new TextDecoder();
   private encoder = new TextEncoder();
+  /**
+   * Programs refused at `init`, by id; the rest are loaded and can be
+   * evaluated.
+   */
+  rejected: Record<string, string> = {};
 
   private constructor(proc: Subprocess) {
     this.proc = proc;
@@ -97,6 +139,7 @@ export class HarnessClient {
     programs: HarnessProgram[],
     computeUnitLimit?: number,
     args: string[] = [],
+    baselines?: string[],
   ): Promise<HarnessClient> {
     const client = new HarnessClient(
       Bun.spawn([harnessPath, ...args], {
@@ -105,23 +148,29 @@ export class HarnessClient {
         stderr: "inherit",
       }),
     );
-    await client.init(programs, computeUnitLimit);
+    await client.init(programs, computeUnitLimit, baselines);
     return client;
   }
 
   async init(
     programs: HarnessProgram[],
     computeUnitLimit?: number,
-  ): Promise<void> {
-    await this.sendAndExpect(
+    baselines?: string[],
+  ): Promise<Record<string, string>> {
+    const response = await this.sendAndExpect(
       {
         type: "init",
         request_id: this.nextRequestId(),
         programs,
         compute_unit_limit: computeUnitLimit,
+        baselines,
       },
       "ok",
     );
+    this.rejected = Object.fromEntries(
+      (response.rejected ?? []).map((r) => [r.id, r.reason]),
+    );
+    return this.rejected;
   }
 
   async eval(agentId: string, input: EvalInputV1): Promise<EvalOutputV1> {
@@ -166,7 +215,12 @@ export class HarnessClient {
     expectedType: TType,
   ): Promise<Extract<HarnessResponse, { type: TType }>> {
     const response = await this.send(msg);
-    if (response.type === "error") throw new Error(response.message);
+    if (response.type === "error") {
+      if (response.code === "INVALID_INPUT") {
+        throw new HarnessInputError(response.message, response.field);
+      }
+      throw new Error(response.message);
+    }
     if (response.type !== expectedType) {
       throw new Error(
         `Unexpected harness response: expected ${expectedType}, got ${response.type}`,
@@ -238,11 +292,25 @@ function serializeEvalInput(input: EvalInputV1): HarnessEvalInput {
 }
 
 function parseEvalOutput(output: HarnessEvalOutput): EvalOutputV1 {
+  const annotation =
+    output.annotation !== undefined ? { annotation: output.annotation } : {};
+  // Resting (limit/stop) orders pass harness validation but the simulator
+  // does not execute them yet, so hold rather than fill them at market.
+  if (output.order_type !== undefined && output.order_type !== 0) {
+    return {
+      version: 1,
+      action_type: ActionType.HOLD,
+      order_qty: 0,
+      err_code: ERR_ORDER_TYPE_UNSUPPORTED,
+      ...annotation,
+    };
+  }
   return {
     version: 1,
     action_type: output.action_type,
     order_qty: Number(output.order_qty),
     err_code: output.err_code,
+    ...annotation,
   };
 }
 
@@ -102,7 +102,7 @@ export async function prepareProgramsAndInvalidAgents(
 }
 
 export function buildPolicies(
-  programs: HarnessProgram[],
+  programs: Pick<HarnessProgram, "id">[],
   harness: HarnessClient,
 ): Agent[] {
   return programs.map((program) => ({
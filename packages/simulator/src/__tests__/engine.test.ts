@@ -1,6 +1,6 @@
 import { describe, test, expect } from "bun:test";
 import { runWindow } from "../engine.js";
-import { ActionType } from "../types.js";
+import { ActionType, PolicyRuntimeError, REDUCE_ONLY_FLAG } from "../types.js";
 import type { ArenaConfig, OhlcvBar, PolicyFn } from "../types.js";
 
 // Inline baseline policies for testing (actual baselines live in arenas package)
@@ -302,4 +302,106 @@ describe("engine", () => {
     expect(shortPolicy.err_code).toBe(6);
     expect(shortPolicy.action_type).toBe(ActionType.HOLD);
   });
+
+  test("target position and reduce-only resolve to deltas", async () => {
+    const bars = makeBars([100, 100, 100, 100]);
+    const steps = [
+      { action_type: ActionType.TARGET_POSITION, order_qty: 3 },
+      { action_type: ActionType.BUY_NOTIONAL, order_qty: 250 },
+      {
+        action_type: ActionType.TARGET_POSITION | REDUCE_ONLY_FLAG,
+        order_qty: -2,
+      },
+    ];
+    const policy = (input: { step_index: number }) => ({
+      version: 1 as const,
+      err_code: 0,
+      ...(steps[input.step_index] ?? {
+        action_type: ActionType.HOLD,
+        order_qty: 0,
+      }),
+    });
+
+    // Unit scales, so notional amounts and quantities are whole units.
+    const unitScales = { ...config, price_scale: 1, volume_scale: 1 };
+    const result = await runWindow(unitScales, bars, "w9", [
+      { id: "target", policy },
+    ]);
+    const target = requireValue(
+      result.agent_results["target"],
+      "target agent result",
+    );
+
+    // +3 to reach the target, +2 from 250 notional at 100, then the
+    // reduce-only flip to -2 is clipped to a close of the 5 held.
+    expect(target.trade_log.map((trade) => trade.delta_qty)).toEqual([
+      3, 2, -5,
+    ]);
+    expect(target.final_account.position_qty).toBe(0);
+  });
+
+  test("notional orders apply the instrument scales", async () => {
+    // Prices and notionals at price_scale 100: bars at 50.00, orders for
+    // 100.00 and 25.00 of quote. Quantities at volume_scale 1000.
+    const scaled: ArenaConfig = {
+      ...config,
+      price_scale: 100,
+      volume_scale: 1000,
+      initial_balances: [{ mint: "USDC", amount: 100_000_000 }],
+    };
+    const bars = makeBars([5000, 5000, 5000]);
+    const steps = [
+      { action_type: ActionType.BUY_NOTIONAL, order_qty: 10_000 },
+      { action_type: ActionType.SELL_NOTIONAL, order_qty: 2_500 },
+    ];
+    const policy = (input: { step_index: number }) => ({
+      version: 1 as const,
+      err_code: 0,
+      ...(steps[input.step_index] ?? {
+        action_type: ActionType.HOLD,
+        order_qty: 0,
+      }),
+    });
+
+    const result = await runWindow(scaled, bars, "w12", [
+      { id: "notional", policy },
+    ]);
+    const notional = requireValue(
+      result.agent_results["notional"],
+      "notional agent result",
+    );
+
+    // 100.00 / 50.00 = 2 BTC, then 25.00 / 50.00 = 0.5 BTC back.
+    expect(notional.trade_log.map((trade) => trade.delta_qty)).toEqual([
+      2000, -500,
+    ]);
+  });
+
+  test("throwing agents hold, runtime faults abort the window", async () => {
+    const bars = makeBars([100, 101, 102]);
+    const result = await runWindow(config, bars, "w10", [
+      {
+        id: "thrower",
+        policy: () => {
+          throw new Error("agent bug");
+        },
+      },
+    ]);
+    const thrower = requireValue(
+      result.agent_results["thrower"],
+      "thrower agent result",
+    );
+    expect(thrower.policy_log[0]?.status).toBe("ERR");
+    expect(thrower.policy_log[0]?.err_code).toBe(5);
+
+    const runtimeFault = runWindow(config, bars, "w11", [
+      {
+        id: "served",
+        policy: () => {
+          throw new PolicyRuntimeError("harness refused the input");
+        },
+      },
+    ]);
+    await expect(runtimeFault).rejects.toBeInstanceOf(PolicyRuntimeError);
+  });
 });
@@ -12,7 +12,7 @@ import type {
   WindowAgentResult,
   WindowMultiResult,
 } from "./types.js";
-import { ActionType } from "./types.js";
+import { ActionType, PolicyRuntimeError, REDUCE_ONLY_FLAG } from "./types.js";
 import { computeUniformExecPrice, computeFee } from "./execution.js";
 import { applyTrade, computeEquity, applyFunding } from "./accounting.js";
 import {
@@ -70,14 +70,18 @@ function normalizePolicyOutput(raw: unknown): EvalOutputV1 | null {
   if (version !== 1) {
     return null;
   }
-  if (actionType === null || !isActionType(actionType)) {
+  if (actionType === null || !isActionType(baseAction(actionType))) {
     return null;
   }
   if (orderQty === null) {
     return null;
   }
+  const action = baseAction(actionType);
   if (
-    (actionType === ActionType.BUY || actionType === ActionType.SELL) &&
+    (action === ActionType.BUY ||
+      action === ActionType.SELL ||
+      action === ActionType.BUY_NOTIONAL ||
+      action === ActionType.SELL_NOTIONAL) &&
     orderQty <= 0
   ) {
     return null;
@@ -88,6 +92,9 @@ function normalizePolicyOutput(raw: unknown): EvalOutputV1 | null {
     action_type: actionType,
     order_qty: orderQty,
     err_code: errCode ?? 0,
+    ...(typeof raw.annotation === "string"
+      ? { annotation: raw.annotation }
+      : {}),
   };
 }
 
@@ -100,10 +107,45 @@ function isActionType(value: number): value is ActionType {
     value === ActionType.HOLD ||
     value === ActionType.BUY ||
     value === ActionType.SELL ||
-    value === ActionType.CLOSE
+    value === ActionType.CLOSE ||
+    value === ActionType.TARGET_POSITION ||
+    value === ActionType.BUY_NOTIONAL ||
+    value === ActionType.SELL_NOTIONAL
   );
 }
 
+function baseAction(actionType: number): number {
+  return Number.isInteger(actionType) && actionType >= 0 && actionType <= 0xff
+    ? actionType & ~REDUCE_ONLY_FLAG
+    : actionType;
+}
+
+function isReduceOnly(actionType: number): boolean {
+  return (actionType & REDUCE_ONLY_FLAG) !== 0;
+}
+
+// Reduce-only deltas may shrink the position toward zero but never grow or
+// flip it; anything beyond the current size is clipped.
+function clipReduceOnly(deltaQty: number, positionQty: number): number {
+  if (positionQty === 0 || Math.sign(deltaQty) === Math.sign(positionQty)) {
+    return 0;
+  }
+  return (
+    Math.sign(deltaQty) * Math.min(Math.abs(deltaQty), Math.abs(positionQty))
+  );
+}
+
+// Notional orders are quote amounts at price_scale, like prices, and are
+// sized in base units at volume_scale: (notional / price_scale) /
+// (price / price_scale) * volume_scale, where price_scale cancels.
+function notionalQty(
+  notional: number,
+  price: number,
+  config: ArenaConfig,
+): number {
+  return Math.trunc((notional * config.volume_scale) / price);
+}
+
 function toFiniteNumber(value: unknown): number | null {
   if (typeof value === "number" && Number.isFinite(value)) {
     return value;
@@ -215,7 +257,7 @@ export async function runWindow(
       // 2. Call the agent's policy function. Since agents are untrusted user code,
       //    we catch exceptions and normalize bad output — a broken agent becomes
       //    a HOLD (err_code 5 = throw, 6 = bad output shape) rather than crashing
-      //    the entire simulation.
+      //    the entire simulation. Runtime faults are not the agent's and abort.
       let output: EvalOutputV1;
       let status: "OK" | "ERR" = "OK";
       try {
@@ -227,14 +269,17 @@ export async function runWindow(
         } else {
           output = normalized;
         }
-      } catch (_err) {
+      } catch (err) {
+        if (err instanceof PolicyRuntimeError) throw err;
         status = "ERR";
         output = holdOutput(5);
       }
 
-      // 3. Convert action to delta_qty
+      // 3. Convert action to delta_qty. Notional orders are sized at the open
+      //    they execute against.
+      const conversionPrice = (bars[t + 1] ?? bar).open;
       let deltaQty = 0;
-      switch (output.action_type) {
+      switch (baseAction(output.action_type)) {
         case ActionType.HOLD:
           deltaQty = 0;
           break;
@@ -247,6 +292,18 @@ export async function runWindow(
         case ActionType.CLOSE:
           deltaQty = -state.account.position_qty;
           break;
+        case ActionType.TARGET_POSITION:
+          deltaQty = output.order_qty - state.account.position_qty;
+          break;
+        case ActionType.BUY_NOTIONAL:
+          deltaQty = notionalQty(output.order_qty, conversionPrice, config);
+          break;
+        case ActionType.SELL_NOTIONAL:
+          deltaQty = -notionalQty(output.order_qty, conversionPrice, config);
+          break;
+      }
+      if (isReduceOnly(output.action_type)) {
+        deltaQty = clipReduceOnly(deltaQty, state.account.position_qty);
       }
 
       // 4. Mark-to-market at bar close — record equity *before* the trade executes
@@ -400,6 +457,9 @@ export async function runWindow(
         order_qty: action.output.order_qty,
         status: action.status,
         err_code: action.output.err_code,
+        ...(action.output.annotation !== undefined
+          ? { annotation: action.output.annotation }
+          : {}),
       });
     }
   }
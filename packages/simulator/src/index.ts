@@ -1,6 +1,8 @@
 // Re-export all types
 export {
   ActionType,
+  PolicyRuntimeError,
+  REDUCE_ONLY_FLAG,
   ArenaConfigSchema,
   ScoringWeightsSchema,
 } from "./types.js";
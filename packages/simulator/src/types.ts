@@ -36,8 +36,17 @@ export enum ActionType {
   BUY = 1,
   SELL = 2,
   CLOSE = 3,
+  // order_qty is the desired signed position.
+  TARGET_POSITION = 4,
+  // order_qty is a notional in quote units, converted at the execution open.
+  BUY_NOTIONAL = 5,
+  SELL_NOTIONAL = 6,
 }
 
+// High bit of action_type: the action may shrink the position but never grow
+// or flip it. The low bits carry the ActionType.
+export const REDUCE_ONLY_FLAG = 0x80;
+
 export interface EvalInputV1 {
   version: 1;
   window_id: string;
@@ -57,6 +66,8 @@ export interface EvalOutputV1 {
   action_type: ActionType;
   order_qty: number;
   err_code: number;
+  // Hex-encoded agent annotation bytes (on-chain output version 3).
+  annotation?: string;
 }
 
 // --- Policy ---
@@ -70,6 +81,16 @@ export interface AgentPolicy {
   policy: PolicyFn;
 }
 
+// Thrown by a policy when the runtime serving it is at fault rather than the
+// agent (e.g. the harness refused the input). The engine lets it propagate
+// instead of recording an agent failure.
+export class PolicyRuntimeError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "PolicyRuntimeError";
+  }
+}
+
 // --- Config ---
 
 const BalanceEntrySchema = z.object({
@@ -212,6 +233,7 @@ export interface PolicyLogEntry {
   order_qty: number;
   status: "OK" | "ERR";
   err_code: number;
+  annotation?: string;
 }
 
 export interface TradeLogEntry {